@@ -1,17 +1,42 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{self, BufWriter},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
+use anyhow::Context;
 use chrono::{DateTime, Local};
+use ratatui::layout::Rect;
 
 use crate::{
     baseline::{BaselineProfile, TokenCount},
-    config::{BaselineMode, DEFAULT_MAX_AGE, TIMELINE_BINS, TIMELINE_WINDOW},
+    bookmarks::{self, Bookmark, BookmarkSpec},
+    clipboard::{self, ClipboardMode, Copied},
+    collapse::{DedupKey, Group},
+    columns::ColumnLayout,
+    commands::{common_prefix, complete, Command, Setting},
+    config::{
+        parse_time_range, parse_time_spec, BaselineMode, DEFAULT_MAX_AGE, DEFAULT_MAX_BYTES,
+        TIMELINE_BINS, TIMELINE_WINDOW,
+    },
+    correlate::{fields, Trace, DEFAULT_CORRELATION_FIELDS},
+    export::{self, entry_json, ExportFormat, ExportRequest, ExportScope, ExportWriter},
     filters::{Filters, InputMode},
-    ingest::{drain_ingest, Ingest},
+    highlight::Highlighter,
+    history::SpillStore,
+    ingest::{drain_ingest, Ingest, Ingested},
+    keymap::Keymap,
     log_entry::{Level, LogEntry},
+    queue::QueueStats,
+    record::TeeWriter,
+    saved_filters::{FilterHistory, FilterQuery, Preset},
+    session::{parse_timestamp, timestamp_string, Anchor, SavedBookmark, SessionState},
+    templates::{Template, TemplateMiner, TemplateSort},
+    text_index::TextIndex,
+    theme::Theme,
     timeline::Timeline,
 };
 
@@ -28,34 +53,155 @@ impl Mode {
             Mode::Paused => "PAUSED",
         }
     }
+}
 
-    pub fn color(self) -> ratatui::style::Color {
-        match self {
-            Mode::Live => ratatui::style::Color::Green,
-            Mode::Paused => ratatui::style::Color::Yellow,
+/// Shown on the first row of a collapse group with more than one member.
+pub struct GroupBadge {
+    pub count: usize,
+    pub last: DateTime<Local>,
+    pub expanded: bool,
+}
+
+/// One row of the log view, as returned by `App::visible_logs`.
+pub struct VisibleRow<'a> {
+    /// Position among all rows (`App::filtered_len`), oldest first.
+    pub index: usize,
+    /// Sequence number of `entry`.
+    pub seq: u64,
+    pub entry: &'a LogEntry,
+    /// Shown only as context around a match.
+    pub is_context: bool,
+    /// Rows were skipped between this one and the previous; draw a separator.
+    pub gap_before: bool,
+    /// Timestamp of the row above, for the relative-time column.
+    pub previous: Option<DateTime<Local>>,
+    pub group: Option<GroupBadge>,
+}
+
+/// Geometry of the last drawn frame, recorded by `ui::draw` for keys and the mouse.
+#[derive(Debug, Clone, Default)]
+pub struct ViewLayout {
+    /// Horizontal scroll that brings the end of the longest visible row into view.
+    pub line_end_scroll: usize,
+    /// Screen lines of each drawn log row: `(first line, height, row index)`.
+    pub log_rows: Vec<(u16, u16, usize)>,
+    /// Columns and lines inside the log pane border.
+    pub log_area: Rect,
+    /// Timeline band area and how many bins each of its columns covers.
+    pub timeline_area: Rect,
+    pub timeline_step: usize,
+    /// Clickable INFO/WARN/ERROR chips in the status bar.
+    pub level_chips: Vec<(Level, Rect)>,
+}
+
+impl ViewLayout {
+    /// Index (as in `VisibleRow::index`) of the log row drawn at a screen cell.
+    pub fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        if !contains(self.log_area, x, y) {
+            return None;
         }
+        self.log_rows
+            .iter()
+            .find(|(top, height, _)| (*top..top + height).contains(&y))
+            .map(|(_, _, index)| *index)
+    }
+
+    /// Timeline bin (oldest first) under a screen cell.
+    pub fn timeline_bin_at(&self, x: u16, y: u16) -> Option<usize> {
+        contains(self.timeline_area, x, y)
+            .then(|| usize::from(x - self.timeline_area.x) * self.timeline_step.max(1))
     }
+
+    pub fn level_at(&self, x: u16, y: u16) -> Option<Level> {
+        self.level_chips
+            .iter()
+            .find(|(_, area)| contains(*area, x, y))
+            .map(|(level, _)| *level)
+    }
+}
+
+fn contains(area: Rect, x: u16, y: u16) -> bool {
+    (area.x..area.x + area.width).contains(&x) && (area.y..area.y + area.height).contains(&y)
 }
 
-#[derive(Debug, Clone)]
-pub struct Bookmark {
-    pub timestamp: DateTime<Local>,
-    pub label: String,
+/// Newest entries moved to `spill` to make room for older ones paged in.
+#[derive(Debug, Clone, Copy, Default)]
+struct EvictedTail {
+    entries: u64,
+    /// Count and size of those that were live rather than paged in themselves.
+    live: usize,
+    live_bytes: usize,
 }
 
 pub struct App {
     pub mode: Mode,
     logs: VecDeque<LogEntry>,
+    /// Sequence number of `logs.front()`; entries are numbered in arrival order.
+    first_seq: u64,
+    /// Sequence numbers of the rows shown, oldest first: entries that pass `filters`
+    /// plus, when context is on, their neighbours.
+    filtered: VecDeque<u64>,
+    /// Timeline bin numbers holding lines of the followed trace, kept with `filtered`.
+    trace_hits: BTreeSet<i64>,
+    text_index: TextIndex,
+    /// Lines shown before/after each filter match (grep `-B`/`-A`).
+    context_before: usize,
+    context_after: usize,
+    /// Context lines still owed after the newest match.
+    context_after_left: usize,
+    /// Fold runs of near-identical rows into one (context is not shown meanwhile).
+    collapse: bool,
+    /// Collapse groups keyed by the sequence number of their first member.
+    groups: BTreeMap<u64, Group>,
+    /// Newest group and its key, which the next matching entry may join.
+    tail_group: Option<(u64, DedupKey)>,
     max_lines: usize,
+    max_bytes: usize,
+    /// Approximate heap + inline size of everything in `logs`.
+    bytes: usize,
+    /// Entries at the front of `logs` that were paged back in from `spill`, and their size.
+    paged_in: usize,
+    paged_in_bytes: usize,
+    /// Entries after the back of `logs`, on disk while history is paged in;
+    /// `restore_tail` reads them back before anything newer is added.
+    evicted_tail: EvictedTail,
+    spill: Option<SpillStore>,
     max_age: Duration,
     scroll_offset: usize,
     selected_from_end: usize,
+    /// Wrap long rows instead of cutting them at the pane edge.
+    wrap: bool,
+    /// Column layouts cycled with `L`, and the one in use.
+    layouts: Vec<ColumnLayout>,
+    layout_idx: usize,
+    /// Sequence number of the row where `v` started a visual selection.
+    visual_anchor: Option<u64>,
+    clipboard: ClipboardMode,
+    /// Message characters hidden to the left when not wrapping.
+    h_scroll: usize,
+    view: ViewLayout,
     paused_head_len: Option<usize>,
     paused_buffer: VecDeque<LogEntry>,
+    /// Size of `paused_buffer`, counted against `max_bytes` like `bytes`.
+    paused_bytes: usize,
     filters: Filters,
     filter_error: Option<String>,
     input_mode: InputMode,
+    /// Text filter in effect before `/` was pressed, restored if the edit is cancelled.
+    filter_before_edit: Option<Option<String>>,
+    filter_history: FilterHistory,
+    presets: Vec<Preset>,
+    highlighter: Highlighter,
+    keymap: Keymap,
+    theme: Theme,
+    /// Field names `x` follows, in order of preference.
+    correlation_fields: Vec<String>,
     bookmarks: Vec<Bookmark>,
+    /// Line selected in an earlier run, selected again once it is read back.
+    pending_anchor: Option<Anchor>,
+    /// Set when the saved history was read back only in part; the first line
+    /// read shows which restored bookmarks were left out.
+    check_unloaded_bookmarks: bool,
     ingest: Ingest,
     timeline: Timeline,
     source_label: String,
@@ -67,9 +213,21 @@ pub struct App {
     baseline_profile: Option<BaselineProfile>,
     baseline_target: Option<PathBuf>,
     token_counts: HashMap<String, u64>,
+    templates: TemplateMiner,
+    template_sort: TemplateSort,
+    started_at: DateTime<Local>,
+    tee: Option<TeeWriter>,
 }
 
 const TOKEN_TRACK_LIMIT: usize = 4096;
+/// Templates saved in a baseline profile.
+const BASELINE_TEMPLATES: usize = 200;
+/// Entries read back from the spill store per page.
+const PAGE_LINES: usize = 2000;
+/// Upper bound on entries paged in by a single jump or scroll.
+const PAGE_IN_LIMIT: usize = 200_000;
+/// Upper bound for `(`/`)` context adjustments.
+const MAX_CONTEXT_LINES: usize = 20;
 
 impl App {
     pub fn new(
@@ -106,21 +264,58 @@ impl App {
                 }
             }
         }
+        let started_at = ingest.now();
         Self {
             mode: Mode::Live,
             logs: VecDeque::with_capacity(max_lines),
+            first_seq: 0,
+            filtered: VecDeque::new(),
+            trace_hits: BTreeSet::new(),
+            text_index: TextIndex::default(),
+            context_before: 0,
+            context_after: 0,
+            context_after_left: 0,
+            collapse: false,
+            groups: BTreeMap::new(),
+            tail_group: None,
             max_lines,
+            max_bytes: DEFAULT_MAX_BYTES,
+            bytes: 0,
+            paged_in: 0,
+            paged_in_bytes: 0,
+            evicted_tail: EvictedTail::default(),
+            spill: None,
             max_age: DEFAULT_MAX_AGE,
             scroll_offset: 0,
             selected_from_end: 0,
+            wrap: false,
+            layouts: vec![ColumnLayout::default()],
+            layout_idx: 0,
+            h_scroll: 0,
+            visual_anchor: None,
+            clipboard: ClipboardMode::default(),
+            view: ViewLayout::default(),
             paused_head_len: None,
             paused_buffer: VecDeque::new(),
+            paused_bytes: 0,
             filters: Filters::default(),
             filter_error: None,
             input_mode: InputMode::Normal,
+            filter_before_edit: None,
+            filter_history: FilterHistory::default(),
+            presets: Vec::new(),
+            highlighter: Highlighter::default(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            correlation_fields: DEFAULT_CORRELATION_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
             bookmarks: Vec::new(),
+            pending_anchor: None,
+            check_unloaded_bookmarks: false,
             ingest,
-            timeline: Timeline::new(TIMELINE_BINS, TIMELINE_WINDOW),
+            timeline: Timeline::new_at(TIMELINE_BINS, TIMELINE_WINDOW, started_at),
             source_label,
             timeline_cursor_from_end: None,
             show_help: false,
@@ -130,28 +325,66 @@ impl App {
             baseline_profile,
             baseline_target,
             token_counts: HashMap::new(),
+            templates: TemplateMiner::default(),
+            template_sort: TemplateSort::default(),
+            started_at,
+            tee: None,
+        }
+    }
+
+    /// Starts recording raw input to `path` so the session can be replayed later.
+    pub fn start_tee(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.tee = Some(TeeWriter::create(path, self.started_at)?);
+        Ok(())
+    }
+
+    pub fn finish_tee(&mut self) -> anyhow::Result<()> {
+        match self.tee.take() {
+            Some(tee) => tee.finish(self.ingest.now()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes.max(1);
+        self.enforce_limits();
+    }
+
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        self.max_lines = max_lines.max(1);
+        self.enforce_limits();
+    }
+
+    /// Evicts (spilling as usual) until `logs` and the paused queue fit the
+    /// limits again, rather than waiting for the next line to arrive.
+    fn enforce_limits(&mut self) {
+        while self.logs.len() > self.max_lines || self.bytes > self.max_bytes {
+            if self.pop_front_log().is_none() {
+                break;
+            }
         }
+        self.trim_paused();
+        self.scroll_offset = self.scroll_offset.min(self.filtered_len());
+        self.clamp_selection();
+    }
+
+    /// Keeps evicted entries in an on-disk store under `dir` so they can be paged back in.
+    pub fn enable_spill(&mut self, dir: &Path) -> anyhow::Result<()> {
+        self.spill = Some(SpillStore::create(dir)?);
+        Ok(())
     }
 
     pub fn tick(&mut self) {
-        let now = Local::now();
-        let new_entries = drain_ingest(&mut self.ingest);
-        let mut info = 0;
-        let mut warn = 0;
-        let mut error = 0;
-        for entry in new_entries {
-            match entry.level {
-                Level::Info => info += 1,
-                Level::Warn => warn += 1,
-                Level::Error => error += 1,
+        let lines = drain_ingest(&mut self.ingest);
+        self.ingest_lines(lines);
+        if let Some(tee) = &mut self.tee {
+            if let Err(err) = tee.flush() {
+                self.last_notice = Some(format!("Tee stopped: {err}"));
+                self.tee = None;
             }
-            self.record_tokens(&entry);
-            match self.mode {
-                Mode::Paused => self.push_paused_entry(entry, now),
-                Mode::Live => self.push_log(entry),
-            };
         }
-        self.timeline.record(now, info, warn, error);
+        let now = self.ingest.now();
+        self.timeline.advance(now);
         if matches!(self.mode, Mode::Paused) {
             self.last_tick = Instant::now();
             return;
@@ -197,8 +430,19 @@ impl App {
     }
 
     pub fn scroll_up(&mut self, lines: usize) {
+        let mut wanted = self.scroll_offset + lines;
+        let mut budget = PAGE_IN_LIMIT;
+        while wanted >= self.filtered_len() && budget > 0 {
+            let missing = (wanted + 1).saturating_sub(self.filtered_len());
+            let (loaded, dropped) = self.page_in_older(missing.clamp(1, PAGE_LINES).min(budget));
+            if loaded == 0 {
+                break;
+            }
+            wanted = wanted.saturating_sub(dropped);
+            budget = budget.saturating_sub(loaded);
+        }
         let max_offset = self.filtered_len();
-        self.scroll_offset = (self.scroll_offset + lines).min(max_offset);
+        self.scroll_offset = wanted.min(max_offset);
         self.selected_from_end = self.scroll_offset;
         self.mode = Mode::Paused;
         self.timeline_cursor_from_end = None;
@@ -217,6 +461,162 @@ impl App {
         }
     }
 
+    pub fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.h_scroll = 0;
+        self.last_notice = Some(if self.wrap { "Wrap on" } else { "Wrap off" }.to_string());
+    }
+
+    pub fn set_layouts(&mut self, layouts: Vec<ColumnLayout>) {
+        if !layouts.is_empty() {
+            self.layouts = layouts;
+            self.layout_idx = 0;
+        }
+    }
+
+    pub fn column_layout(&self) -> &ColumnLayout {
+        &self.layouts[self.layout_idx]
+    }
+
+    pub fn cycle_layout(&mut self) {
+        self.layout_idx = (self.layout_idx + 1) % self.layouts.len();
+        self.last_notice = Some(format!("Columns: {}", self.column_layout()));
+    }
+
+    /// Timestamp of the latest bookmark at or before entry `seq`.
+    pub fn bookmark_before(&self, seq: u64) -> Option<DateTime<Local>> {
+        self.bookmark_at_or_before(seq).map(|bm| bm.timestamp)
+    }
+
+    /// The latest bookmark on entry `seq` or an earlier one. Bookmarks whose line
+    /// is loaded compare by sequence number; restored ones still waiting for
+    /// their line come before every loaded one and compare by time.
+    fn bookmark_at_or_before(&self, seq: u64) -> Option<&Bookmark> {
+        let ts = self.entry_at_seq(seq).map(|entry| entry.timestamp);
+        self.bookmarks
+            .iter()
+            .filter(|bm| match bm.seq {
+                Some(mark) => mark <= seq,
+                None => ts.is_none_or(|ts| bm.timestamp <= ts),
+            })
+            .max_by_key(|bm| (bm.seq, bookmark_order(bm)))
+    }
+
+    pub fn set_clipboard_mode(&mut self, mode: ClipboardMode) {
+        self.clipboard = mode;
+    }
+
+    /// Starts a visual selection at the selected row, or drops the current one.
+    pub fn toggle_visual(&mut self) {
+        if self.visual_anchor.take().is_some() {
+            return;
+        }
+        let total = self.filtered.len();
+        if let Some(idx) = total.checked_sub(self.selected_from_end + 1) {
+            self.visual_anchor = Some(self.filtered[idx]);
+        }
+    }
+
+    pub fn cancel_visual(&mut self) {
+        self.visual_anchor = None;
+    }
+
+    /// Rows (as in `VisibleRow::index`) between the anchor and the selected row.
+    pub fn visual_range(&self) -> Option<RangeInclusive<usize>> {
+        let anchor = self.visual_anchor?;
+        let total = self.filtered.len();
+        let selected = total.checked_sub(self.selected_from_end + 1)?;
+        // The anchor may have been filtered out or evicted; use the nearest row instead.
+        let anchor = self
+            .filtered
+            .partition_point(|seq| *seq < anchor)
+            .min(total - 1);
+        Some(anchor.min(selected)..=anchor.max(selected))
+    }
+
+    /// The visual selection (or the selected row) as plain lines or JSON lines.
+    pub fn selection_text(&self, json: bool) -> Option<(String, usize)> {
+        let total = self.filtered.len();
+        let range = match self.visual_range() {
+            Some(range) => range,
+            None => {
+                let idx = total.checked_sub(self.selected_from_end + 1)?;
+                idx..=idx
+            }
+        };
+        let lines: Vec<String> = range
+            .filter_map(|idx| self.entry_at_seq(self.filtered[idx]))
+            .map(|entry| {
+                if json {
+                    entry_json(entry).to_string()
+                } else {
+                    entry.raw_line().into_owned()
+                }
+            })
+            .collect();
+        let count = lines.len();
+        Some((lines.join("\n"), count))
+    }
+
+    /// Copies the selection through the terminal (OSC 52) or into a temp file.
+    pub fn yank(&mut self, json: bool) {
+        let Some((text, count)) = self.selection_text(json) else {
+            return;
+        };
+        let what = if count == 1 {
+            "1 line".to_string()
+        } else {
+            format!("{count} lines")
+        };
+        self.last_notice = Some(
+            match clipboard::copy(&text, self.clipboard, &mut io::stdout()) {
+                Ok(Copied::Clipboard) => format!("Copied {what} to the clipboard"),
+                Ok(Copied::File(path)) => format!("Wrote {what} to {}", path.display()),
+                Err(err) => format!("Copy failed: {err:#}"),
+            },
+        );
+        self.visual_anchor = None;
+    }
+
+    pub fn wrap_enabled(&self) -> bool {
+        self.wrap
+    }
+
+    /// Scrolls long rows sideways; ignored while wrapping.
+    pub fn scroll_horizontal(&mut self, delta: isize) {
+        if self.wrap {
+            return;
+        }
+        let next = self.h_scroll.saturating_add_signed(delta);
+        self.h_scroll = if delta > 0 {
+            next.min(self.view.line_end_scroll.max(self.h_scroll))
+        } else {
+            next
+        };
+    }
+
+    pub fn scroll_line_start(&mut self) {
+        self.h_scroll = 0;
+    }
+
+    pub fn scroll_line_end(&mut self) {
+        if !self.wrap {
+            self.h_scroll = self.view.line_end_scroll;
+        }
+    }
+
+    pub fn h_scroll(&self) -> usize {
+        self.h_scroll
+    }
+
+    pub fn set_view_layout(&mut self, view: ViewLayout) {
+        self.view = view;
+    }
+
+    pub fn view(&self) -> &ViewLayout {
+        &self.view
+    }
+
     pub fn move_timeline_cursor(&mut self, delta: i32) {
         let len = self.timeline.len();
         if len == 0 {
@@ -239,6 +639,31 @@ impl App {
         self.jump_to_timeline_cursor();
     }
 
+    /// Puts the timeline cursor on a bin, as if moved there with left/right.
+    pub fn seek_timeline(&mut self, idx_from_oldest: usize) {
+        let len = self.timeline.len();
+        if len == 0 {
+            return;
+        }
+        let from_end = len - 1 - idx_from_oldest.min(len - 1);
+        let current = self.timeline_cursor_from_end.unwrap_or(0);
+        self.move_timeline_cursor(from_end as i32 - current as i32);
+    }
+
+    /// Selects the row at `index` (oldest first) without scrolling, pausing the view.
+    pub fn select_row(&mut self, index: usize) {
+        let total = self.filtered.len();
+        if index >= total {
+            return;
+        }
+        self.selected_from_end = total - 1 - index;
+        self.mode = Mode::Paused;
+        self.timeline_cursor_from_end = None;
+        if self.paused_head_len.is_none() {
+            self.paused_head_len = Some(self.logs.len());
+        }
+    }
+
     pub fn toggle_level(&mut self, level: Level) {
         match level {
             Level::Info => self.filters.info = !self.filters.info,
@@ -250,6 +675,20 @@ impl App {
 
     pub fn set_regex_mode(&mut self, enabled: bool) {
         self.filters.regex_mode = enabled;
+        self.recompile_filter();
+    }
+
+    pub fn cycle_case_mode(&mut self) {
+        self.filters.case = self.filters.case.next();
+        self.recompile_filter();
+    }
+
+    pub fn toggle_whole_word(&mut self) {
+        self.filters.whole_word = !self.filters.whole_word;
+        self.recompile_filter();
+    }
+
+    fn recompile_filter(&mut self) {
         match self.filters.set_text(self.filters.text.clone()) {
             Ok(_) => self.filter_error = None,
             Err(err) => self.filter_error = Some(err.to_string()),
@@ -265,231 +704,996 @@ impl App {
         self.after_filter_change();
     }
 
-    pub fn clear_filters(&mut self) {
-        self.filters = Filters::default();
-        self.filter_error = None;
-        self.after_filter_change();
-        self.last_notice = Some("Filters cleared".to_string());
+    /// Opens the filter prompt; the filter is applied live as the text changes.
+    pub fn begin_filter_edit(&mut self) {
+        let seed = self.filters.text.clone().unwrap_or_default();
+        self.filter_before_edit = Some(self.filters.text.clone());
+        self.input_mode = InputMode::FilterText(seed);
     }
 
-    pub fn jump_error(&mut self, direction: i32) {
-        let filtered_indices = self.filtered_indices();
-        if filtered_indices.is_empty() {
-            return;
+    /// Re-applies the filter from the prompt buffer while it is being typed.
+    pub fn preview_filter_edit(&mut self) {
+        if let InputMode::FilterText(buf) = &self.input_mode {
+            let text = Some(buf.clone()).filter(|t| !t.is_empty());
+            if text != self.filters.text {
+                self.set_filter_text(text);
+            }
         }
-        let total = filtered_indices.len();
-        let current = self.selected_from_end.min(total.saturating_sub(1));
-        let current_idx = total.saturating_sub(current + 1);
-
-        let target = if direction > 0 {
-            filtered_indices
-                .iter()
-                .enumerate()
-                .skip(current_idx + 1)
-                .find(|(_, idx)| {
-                    self.logs
-                        .get(**idx)
-                        .map(|e| e.level == Level::Error)
-                        .unwrap_or(false)
-                })
-        } else {
-            filtered_indices
-                .iter()
-                .enumerate()
-                .take(current_idx)
-                .rev()
-                .find(|(_, idx)| {
-                    self.logs
-                        .get(**idx)
-                        .map(|e| e.level == Level::Error)
-                        .unwrap_or(false)
-                })
-        };
+    }
 
-        if let Some((idx, _)) = target {
-            let offset_from_end = total.saturating_sub(idx + 1);
-            self.scroll_offset = offset_from_end;
-            self.selected_from_end = offset_from_end;
-            self.mode = Mode::Paused;
-            if self.paused_head_len.is_none() {
-                self.paused_head_len = Some(self.logs.len());
-            }
+    pub fn commit_filter_edit(&mut self) {
+        self.preview_filter_edit();
+        if let InputMode::FilterText(buf) = &self.input_mode {
+            self.filter_history.push(buf);
         }
+        self.filter_before_edit = None;
+        self.input_mode = InputMode::Normal;
     }
 
-    pub fn add_bookmark(&mut self) {
-        if let Some(entry) = self.current_entry() {
-            let label = format!("mark {}", self.bookmarks.len() + 1);
-            self.bookmarks.push(Bookmark {
-                timestamp: entry.timestamp,
-                label,
-            });
-            self.bookmarks.sort_by_key(|b| b.timestamp);
-            if let Some(last) = self.bookmarks.last() {
-                self.last_notice = Some(format!(
-                    "Added bookmark {} @ {}",
-                    last.label, last.timestamp
-                ));
+    pub fn cancel_filter_edit(&mut self) {
+        self.filter_history.reset_cursor();
+        self.input_mode = InputMode::Normal;
+        if let Some(previous) = self.filter_before_edit.take() {
+            if previous != self.filters.text {
+                self.set_filter_text(previous);
             }
         }
     }
 
-    pub fn jump_bookmark(&mut self, direction: i32) {
-        if self.bookmarks.is_empty() || self.logs.is_empty() {
-            return;
-        }
-        let filtered_indices = self.filtered_indices();
-        if filtered_indices.is_empty() {
+    /// Replaces the filter bar text with an older (`Up`) or newer (`Down`) history entry.
+    pub fn step_filter_history(&mut self, older: bool) {
+        let InputMode::FilterText(buf) = &mut self.input_mode else {
             return;
-        }
-        let current_ts = self
-            .current_entry()
-            .map(|e| e.timestamp)
-            .unwrap_or_else(Local::now);
-        let target = if direction > 0 {
-            self.bookmarks
-                .iter()
-                .find(|b| b.timestamp > current_ts)
-                .or_else(|| self.bookmarks.first())
+        };
+        let recalled = if older {
+            self.filter_history.older(buf)
         } else {
-            self.bookmarks
-                .iter()
-                .rev()
-                .find(|b| b.timestamp < current_ts)
-                .or_else(|| self.bookmarks.last())
+            self.filter_history.newer()
         };
-        if let Some(bm) = target {
-            if let Some((idx, _)) = filtered_indices.iter().enumerate().find(|(_, log_idx)| {
-                self.logs
-                    .get(**log_idx)
-                    .map(|entry| entry.timestamp >= bm.timestamp)
-                    .unwrap_or(false)
-            }) {
-                let offset_from_end = filtered_indices.len().saturating_sub(idx + 1);
-                self.scroll_offset = offset_from_end;
-                self.selected_from_end = offset_from_end;
-                self.mode = Mode::Paused;
-                if self.paused_head_len.is_none() {
-                    self.paused_head_len = Some(self.logs.len());
-                }
-                self.last_notice = Some(format!("Jumped to {}", bm.label));
-            }
+        if let Some(text) = recalled {
+            *buf = text.to_string();
+            self.preview_filter_edit();
         }
     }
 
-    pub fn visible_logs(&self, max_visible: usize) -> Vec<(usize, &LogEntry)> {
-        if max_visible == 0 {
-            return Vec::new();
-        }
-        let filtered = self.filtered_indices();
-        if filtered.is_empty() {
-            return Vec::new();
-        }
-        let total = filtered.len();
-        let offset = self.scroll_offset.min(total.saturating_sub(1));
-        let end = total.saturating_sub(offset);
-        let start = end.saturating_sub(max_visible);
-        filtered
-            .into_iter()
-            .enumerate()
-            .skip(start)
-            .take(end - start)
-            .filter_map(|(filtered_idx, log_idx)| {
-                self.logs.get(log_idx).map(|entry| (filtered_idx, entry))
-            })
-            .collect()
+    pub fn set_filter_history(&mut self, history: FilterHistory) {
+        self.filter_history = history;
     }
 
-    pub fn filtered_len(&self) -> usize {
-        self.logs
-            .iter()
-            .filter(|entry| self.filters.matches(entry))
-            .count()
+    pub fn filter_history(&self) -> &FilterHistory {
+        &self.filter_history
     }
 
-    pub fn selected_from_end(&self) -> usize {
-        self.selected_from_end
+    pub fn set_presets(&mut self, presets: Vec<Preset>) {
+        self.presets = presets;
     }
 
-    pub fn timeline_cursor_from_end(&self) -> Option<usize> {
-        self.timeline_cursor_from_end
+    pub fn set_highlighter(&mut self, highlighter: Highlighter) {
+        self.highlighter = highlighter;
     }
 
-    pub fn filters(&self) -> &Filters {
-        &self.filters
+    pub fn highlighter(&self) -> &Highlighter {
+        &self.highlighter
     }
 
-    pub fn filter_error(&self) -> Option<&String> {
-        self.filter_error.as_ref()
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
     }
 
-    pub fn input_mode(&self) -> &InputMode {
-        &self.input_mode
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
     }
 
-    pub fn bookmarks(&self) -> &Vec<Bookmark> {
-        &self.bookmarks
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
     }
 
-    pub fn timeline(&self) -> &Timeline {
-        &self.timeline
+    pub fn theme(&self) -> &Theme {
+        &self.theme
     }
 
-    pub fn queued_len(&self) -> usize {
-        self.paused_head_len
-            .map(|head| {
-                self.logs
-                    .len()
-                    .saturating_sub(head)
-                    .saturating_add(self.paused_buffer.len())
-            })
-            .unwrap_or(0)
+    pub fn presets(&self) -> &[Preset] {
+        &self.presets
     }
 
-    pub fn total_logs(&self) -> usize {
-        self.logs.len()
+    pub fn open_preset_picker(&mut self) {
+        if self.presets.is_empty() {
+            self.last_notice = Some("No presets configured ([presets] in config.toml)".to_string());
+            return;
+        }
+        self.input_mode = InputMode::PresetPicker(0);
     }
 
-    pub fn scroll_offset(&self) -> usize {
-        self.scroll_offset
+    /// Leaves a popup or prompt without applying it; editing a bookmark goes
+    /// back to the bookmark list.
+    pub fn close_input(&mut self) {
+        self.input_mode = match self.input_mode {
+            InputMode::BookmarkEdit(Some(idx), _) => InputMode::Bookmarks(idx),
+            _ => InputMode::Normal,
+        };
     }
 
-    pub fn source_label(&self) -> &str {
-        &self.source_label
+    pub fn move_preset_cursor(&mut self, delta: isize) {
+        if let InputMode::PresetPicker(idx) = &mut self.input_mode {
+            let last = self.presets.len().saturating_sub(1);
+            *idx = idx.saturating_add_signed(delta).min(last);
+        }
     }
 
-    pub fn last_notice(&self) -> Option<&String> {
-        self.last_notice.as_ref()
+    pub fn pick_preset(&mut self) {
+        let mode = std::mem::replace(&mut self.input_mode, InputMode::Normal);
+        if let InputMode::PresetPicker(idx) = mode {
+            self.apply_preset(idx);
+        }
     }
 
-    pub fn baseline_mode(&self) -> &BaselineMode {
-        &self.baseline_mode
+    pub fn set_correlation_fields(&mut self, fields: Vec<String>) {
+        self.correlation_fields = fields;
     }
 
-    pub fn baseline_overlay(&self) -> Option<&BaselineProfile> {
-        let profile = self.baseline_profile.as_ref()?;
-        if profile.bin_count != self.timeline.len()
-            || profile.window_secs != TIMELINE_WINDOW.as_secs()
-        {
-            return None;
+    /// Shows only lines sharing a correlation field value with the selected entry.
+    /// Pressing it again on a line with several such fields moves on to the next
+    /// one, and past the last clears the trace.
+    pub fn follow_correlation(&mut self) {
+        let Some(entry) = self.current_entry() else {
+            return;
+        };
+        let candidates: Vec<Trace> = fields(entry)
+            .into_iter()
+            .filter(|(key, _)| {
+                self.correlation_fields
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(key))
+            })
+            .map(|(key, value)| Trace {
+                key: key.into_owned(),
+                value: value.into_owned(),
+            })
+            .collect();
+        let next = match &self.filters.trace {
+            Some(current) => match candidates.iter().position(|c| c == current) {
+                Some(idx) => candidates.get(idx + 1).cloned(),
+                None => candidates.first().cloned(),
+            },
+            None => candidates.first().cloned(),
+        };
+        if next.is_none() && self.filters.trace.is_none() {
+            self.last_notice = Some(format!(
+                "No correlation field ({}) on this line",
+                self.correlation_fields.join(", ")
+            ));
+            return;
         }
-        Some(profile)
+        self.last_notice = Some(match &next {
+            Some(trace) => format!("Following {}={}", trace.key, trace.value),
+            None => "Trace cleared".to_string(),
+        });
+        self.filters.trace = next;
+        self.after_filter_change();
     }
 
-    pub fn baseline_target(&self) -> Option<&PathBuf> {
-        self.baseline_target.as_ref()
+    /// Timeline bins holding lines of the followed trace, oldest first.
+    pub fn trace_bins(&self) -> Option<Vec<bool>> {
+        self.filters.trace.as_ref()?;
+        let first = self.timeline.first_bin_number();
+        Some(
+            (0..self.timeline.len() as i64)
+                .map(|idx| self.trace_hits.contains(&(first + idx)))
+                .collect(),
+        )
     }
 
-    pub fn save_baseline(&self, path: &Path) -> anyhow::Result<()> {
-        let profile = self.build_baseline_profile();
-        profile.save(path)
+    /// Notes the bin of a line that passed the trace filter; bins scrolled off
+    /// the timeline are forgotten.
+    fn record_trace_hit(&mut self, ts: DateTime<Local>) {
+        if self.filters.trace.is_none() {
+            return;
+        }
+        self.trace_hits.insert(self.timeline.bin_number(ts));
+        let first = self.timeline.first_bin_number();
+        while self.trace_hits.first().is_some_and(|bin| *bin < first) {
+            self.trace_hits.pop_first();
+        }
     }
 
-    pub fn drift_bins(&self) -> Option<Vec<bool>> {
-        let baseline = self.baseline_overlay()?;
-        let current = self.timeline.data();
-        if baseline.bins.len() != current.len() {
-            return None;
+    pub fn open_template_panel(&mut self) {
+        if self.templates.is_empty() {
+            self.last_notice = Some("No templates yet".to_string());
+            return;
+        }
+        self.input_mode = InputMode::Templates(0);
+    }
+
+    /// Templates in the panel's current sort order.
+    pub fn templates(&self) -> Vec<&Template> {
+        self.templates.sorted(self.template_sort)
+    }
+
+    pub fn template_sort(&self) -> TemplateSort {
+        self.template_sort
+    }
+
+    pub fn template_overflow(&self) -> u64 {
+        self.templates.overflow()
+    }
+
+    pub fn cycle_template_sort(&mut self) {
+        self.template_sort = self.template_sort.next();
+        if let InputMode::Templates(idx) = &mut self.input_mode {
+            *idx = 0;
+        }
+    }
+
+    pub fn move_template_cursor(&mut self, delta: isize) {
+        if let InputMode::Templates(idx) = &mut self.input_mode {
+            let last = self.templates.len().saturating_sub(1);
+            *idx = idx.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Filters to the lines of the highlighted template; the other filters stay.
+    pub fn pick_template(&mut self) {
+        let mode = std::mem::replace(&mut self.input_mode, InputMode::Normal);
+        let InputMode::Templates(idx) = mode else {
+            return;
+        };
+        let Some(template) = self.templates.sorted(self.template_sort).get(idx).copied() else {
+            return;
+        };
+        self.filters.template = Some(template.matcher());
+        self.last_notice = Some(format!("Template filter: {}", template.text()));
+        self.after_filter_change();
+    }
+
+    /// Applies the preset bound to `key`, if any; returns whether one was found.
+    pub fn apply_preset_key(&mut self, key: char) -> bool {
+        match self.presets.iter().position(|p| p.key == Some(key)) {
+            Some(idx) => {
+                self.apply_preset(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn apply_preset(&mut self, idx: usize) {
+        let Some(preset) = self.presets.get(idx) else {
+            return;
+        };
+        let (query, name) = (preset.query.clone(), preset.name.clone());
+        match self.apply_query(&query) {
+            Ok(()) => self.last_notice = Some(format!("Preset {name} applied")),
+            Err(err) => {
+                self.filter_error = Some(err.to_string());
+                self.after_filter_change();
+            }
+        }
+    }
+
+    /// Replaces the filters with `query`, keeping the time window: queries
+    /// describe what to match, not when.
+    fn apply_query(&mut self, query: &FilterQuery) -> Result<(), regex::Error> {
+        let filters = query.to_filters()?;
+        self.filters = Filters {
+            since: self.filters.since,
+            until: self.filters.until,
+            ..filters
+        };
+        self.filter_error = None;
+        self.after_filter_change();
+        Ok(())
+    }
+
+    pub fn set_context(&mut self, before: usize, after: usize) {
+        self.context_before = before;
+        self.context_after = after;
+        self.after_filter_change();
+    }
+
+    /// Grows or shrinks symmetric context by one line (`(`/`)`).
+    pub fn adjust_context(&mut self, delta: isize) {
+        let lines = self
+            .context_before
+            .max(self.context_after)
+            .saturating_add_signed(delta)
+            .min(MAX_CONTEXT_LINES);
+        self.set_context(lines, lines);
+        self.last_notice = Some(format!("Context: {lines} line(s) around matches"));
+    }
+
+    pub fn toggle_collapse(&mut self) {
+        self.collapse = !self.collapse;
+        self.groups.clear();
+        self.tail_group = None;
+        self.after_filter_change();
+        self.last_notice = Some(if self.collapse {
+            "Collapsing repeated lines (o expands the selected group)".to_string()
+        } else {
+            "Showing every line".to_string()
+        });
+    }
+
+    pub fn collapse_enabled(&self) -> bool {
+        self.collapse
+    }
+
+    /// Expands the selected collapse group in place, or folds it back up.
+    pub fn toggle_selected_group(&mut self) {
+        let total = self.filtered.len();
+        let Some(idx) = total.checked_sub(self.selected_from_end + 1) else {
+            return;
+        };
+        let seq = self.filtered[idx];
+        let Some((&first, group)) = self.groups.range(..=seq).next_back() else {
+            return;
+        };
+        if seq > group.last || group.count < 2 {
+            return;
+        }
+        let group = group.clone();
+        let members = self.group_members(first, &group);
+        let Some(first_idx) = (0..=idx).rev().find(|i| self.filtered[*i] == first) else {
+            return;
+        };
+        if group.expanded {
+            let end = (first_idx + 1 + members.len()).min(total);
+            let bottom_idx = total.saturating_sub(self.scroll_offset + 1);
+            self.filtered.drain(first_idx + 1..end);
+            self.selected_from_end = self.filtered.len() - first_idx - 1;
+            if bottom_idx > first_idx && bottom_idx < end {
+                self.scroll_offset = self.selected_from_end;
+            }
+        } else {
+            for (i, member) in members.iter().enumerate() {
+                self.filtered.insert(first_idx + 1 + i, *member);
+            }
+            self.selected_from_end = self.filtered.len() - first_idx - 1;
+        }
+        if let Some(group) = self.groups.get_mut(&first) {
+            group.expanded = !group.expanded;
+        }
+        self.clamp_selection();
+    }
+
+    pub fn context(&self) -> (usize, usize) {
+        (self.context_before, self.context_after)
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        self.ingest.now()
+    }
+
+    pub fn set_time_range(
+        &mut self,
+        since: Option<DateTime<Local>>,
+        until: Option<DateTime<Local>>,
+    ) {
+        self.filters.since = since;
+        self.filters.until = until;
+        self.after_filter_change();
+    }
+
+    pub fn begin_time_range_edit(&mut self) {
+        self.input_mode = InputMode::TimeRange(String::new());
+    }
+
+    pub fn begin_goto_edit(&mut self) {
+        self.input_mode = InputMode::GotoTime(String::new());
+    }
+
+    /// Opens the `:` prompt with `seed` already typed.
+    pub fn begin_command_edit(&mut self, seed: &str) {
+        self.input_mode = InputMode::Command(seed.to_string());
+    }
+
+    /// Tab in the `:` prompt: completes the last word as far as the candidates
+    /// agree and lists them when there is more than one.
+    pub fn complete_command(&mut self) {
+        let InputMode::Command(buf) = &mut self.input_mode else {
+            return;
+        };
+        let candidates = complete(buf);
+        let partial = if buf.ends_with(char::is_whitespace) {
+            0
+        } else {
+            buf.split_whitespace().last().map_or(0, str::len)
+        };
+        let prefix = common_prefix(&candidates);
+        if prefix.len() > partial {
+            buf.truncate(buf.len() - partial);
+            buf.push_str(&prefix);
+            let finished = candidates.len() == 1 && !prefix.ends_with(['/', '=']);
+            if finished {
+                buf.push(' ');
+            }
+        }
+        if candidates.len() > 1 {
+            self.last_notice = Some(candidates.join("  "));
+        }
+    }
+
+    /// Applies whichever prompt is open (`G` go-to, `T` range, `:` command or `b` bookmark).
+    pub fn submit_prompt(&mut self) {
+        let now = self.now();
+        match std::mem::replace(&mut self.input_mode, InputMode::Normal) {
+            InputMode::Command(buf) => {
+                if let Err(err) = self.run_command(&buf) {
+                    self.last_notice = Some(err);
+                }
+            }
+            InputMode::GotoTime(buf) => match parse_time_spec(&buf, now) {
+                Some(ts) => self.goto_time(ts),
+                None => self.last_notice = Some(format!("Invalid time {:?}", buf.trim())),
+            },
+            InputMode::TimeRange(buf) => {
+                if let Err(err) = self.apply_time_range(&buf) {
+                    self.last_notice = Some(err);
+                }
+            }
+            InputMode::BookmarkEdit(target, buf) => {
+                if let Err(err) = self.submit_bookmark_edit(target, &buf) {
+                    self.last_notice = Some(err);
+                    self.input_mode = InputMode::BookmarkEdit(target, buf);
+                }
+            }
+            other => self.input_mode = other,
+        }
+    }
+
+    /// Applies a `since..until` spec; an empty one clears the range.
+    fn apply_time_range(&mut self, spec: &str) -> Result<(), String> {
+        let (since, until) = parse_time_range(spec, self.now())?;
+        self.set_time_range(since, until);
+        if let Some(since) = since {
+            self.page_in_since(since);
+            self.after_filter_change();
+        }
+        self.last_notice = Some(if since.is_none() && until.is_none() {
+            "Time range cleared".to_string()
+        } else {
+            "Time range applied".to_string()
+        });
+        Ok(())
+    }
+
+    /// Pauses and selects the first visible entry at or after `ts`, paging in
+    /// spilled history if needed.
+    pub fn goto_time(&mut self, ts: DateTime<Local>) {
+        self.mode = Mode::Paused;
+        self.timeline_cursor_from_end = None;
+        if self.paused_head_len.is_none() {
+            self.paused_head_len = Some(self.logs.len());
+        }
+        self.page_in_since(ts);
+        match self.filtered_offset_at_or_after(ts) {
+            Some(offset) => {
+                self.scroll_offset = offset;
+                self.selected_from_end = offset;
+                self.last_notice = Some(format!("Jumped to {}", ts.format("%Y-%m-%d %H:%M:%S")));
+            }
+            None => {
+                self.scroll_offset = 0;
+                self.selected_from_end = 0;
+                self.last_notice = Some("No visible entries at or after that time".to_string());
+            }
+        }
+        self.clamp_selection();
+    }
+
+    /// Runs one `:` command (also used for the `startup` script in the config).
+    pub fn run_command(&mut self, line: &str) -> Result<(), String> {
+        match Command::parse(line)? {
+            Command::Goto(spec) => {
+                let ts = parse_time_spec(&spec, self.now())
+                    .ok_or_else(|| format!("Invalid time {spec:?}"))?;
+                self.goto_time(ts);
+            }
+            Command::Range(spec) => self.apply_time_range(&spec)?,
+            Command::Filter(query) => {
+                self.apply_query(&query).map_err(|err| err.to_string())?;
+                self.last_notice = Some("Filter applied".to_string());
+            }
+            Command::Export(request) => {
+                let count = self
+                    .export(&request)
+                    .map_err(|err| format!("Export failed: {err:#}"))?;
+                self.last_notice = Some(format!(
+                    "Exported {count} lines to {}",
+                    request.path.display()
+                ));
+            }
+            Command::Bookmark(spec) => self.add_bookmark(spec),
+            Command::Set(setting) => {
+                match setting {
+                    Setting::MaxLines(lines) => self.set_max_lines(lines),
+                    Setting::MaxBytes(bytes) => self.set_max_bytes(bytes),
+                    Setting::Context(lines) => self.set_context(lines, lines),
+                    Setting::Wrap(on) => {
+                        if on != self.wrap {
+                            self.toggle_wrap();
+                        }
+                    }
+                    Setting::Collapse(on) => {
+                        if on != self.collapse {
+                            self.toggle_collapse();
+                        }
+                    }
+                }
+                self.last_notice = Some(format!("Set {setting}"));
+            }
+            Command::SourceAdd(path) => {
+                self.ingest
+                    .add_file(path.clone())
+                    .map_err(|err| format!("{err:#}"))?;
+                self.source_label = format!("{} + {}", self.source_label, path.display());
+                self.last_notice = Some(format!("Tailing {}", path.display()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the entries `request.scope` selects and returns how many there were.
+    pub fn export(&mut self, request: &ExportRequest) -> anyhow::Result<usize> {
+        if request.scope == ExportScope::All {
+            return self.export_all(request);
+        }
+        let mut seq_range = None;
+        let window = match request.scope {
+            ExportScope::Filtered | ExportScope::All => None,
+            ExportScope::Bookmarks => {
+                let selected = self.selected_seq().unwrap_or(u64::MAX);
+                let start = self
+                    .bookmark_at_or_before(selected)
+                    .context("no bookmark at or before the selected line")?;
+                let from = start
+                    .seq
+                    .with_context(|| format!("bookmark {:?} is not loaded", start.label))?;
+                let to = self
+                    .bookmarks
+                    .iter()
+                    .filter_map(|bm| bm.seq)
+                    .filter(|seq| *seq > from)
+                    .min();
+                seq_range = Some((from, to));
+                None
+            }
+            ExportScope::TimelineBin => {
+                let cursor = self
+                    .timeline_cursor_from_end
+                    .context("move the timeline cursor onto a bin first")?;
+                let idx = self.timeline.len().saturating_sub(cursor + 1);
+                Some((
+                    self.timeline.bin_start(idx),
+                    Some(self.timeline.bin_start(idx + 1)),
+                ))
+            }
+        };
+        let in_window = |entry: &LogEntry| {
+            window.is_none_or(|(start, end)| {
+                entry.timestamp >= start && end.is_none_or(|end| entry.timestamp < end)
+            })
+        };
+        let in_range = |seq: u64| {
+            seq_range.is_none_or(|(from, to)| seq >= from && to.is_none_or(|to| seq < to))
+        };
+        let entries: Vec<&LogEntry> = self
+            .matching_seqs()
+            .into_iter()
+            .filter(|seq| in_range(*seq))
+            .filter_map(|seq| self.entry_at_seq(seq))
+            .filter(|entry| in_window(entry))
+            .collect();
+        export::export_to(&request.path, &entries, request.format)?;
+        Ok(entries.len())
+    }
+
+    /// Writes every entry, spilled history included, reading the history back a
+    /// page at a time instead of all at once.
+    fn export_all(&mut self, request: &ExportRequest) -> anyhow::Result<usize> {
+        let end = self.first_seq + self.logs.len() as u64;
+        let tail_end = end + self.evicted_tail.entries;
+        let mut names = BTreeSet::new();
+        if request.format == ExportFormat::Csv {
+            let mut add = |entry: &LogEntry| {
+                names.extend(export::field_names([entry]));
+                Ok(())
+            };
+            for_each_spilled(&mut self.spill, 0, self.first_seq, &mut add)?;
+            for_each_spilled(&mut self.spill, end, tail_end, &mut add)?;
+            names.extend(export::field_names(&self.logs));
+        }
+        let path = &request.path;
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let mut writer = ExportWriter::new(BufWriter::new(file), request.format, names)
+            .with_context(|| format!("writing {}", path.display()))?;
+        let mut count = 0;
+        let mut write = |entry: &LogEntry| {
+            count += 1;
+            writer
+                .write(entry)
+                .with_context(|| format!("writing {}", path.display()))
+        };
+        for_each_spilled(&mut self.spill, 0, self.first_seq, &mut write)?;
+        self.logs.iter().try_for_each(&mut write)?;
+        for_each_spilled(&mut self.spill, end, tail_end, &mut write)?;
+        writer
+            .finish()
+            .with_context(|| format!("writing {}", path.display()))?;
+        Ok(count)
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.filters = Filters::default();
+        self.filter_error = None;
+        self.after_filter_change();
+        self.last_notice = Some("Filters cleared".to_string());
+    }
+
+    pub fn jump_error(&mut self, direction: i32) {
+        let total = self.filtered.len();
+        if total == 0 {
+            return;
+        }
+        let current = self.selected_from_end.min(total.saturating_sub(1));
+        let current_idx = total.saturating_sub(current + 1);
+        let is_error = |seq: &u64| {
+            self.entry_at_seq(*seq)
+                .map(|e| e.level == Level::Error)
+                .unwrap_or(false)
+        };
+
+        let target = if direction > 0 {
+            self.filtered
+                .iter()
+                .enumerate()
+                .skip(current_idx + 1)
+                .find(|(_, seq)| is_error(seq))
+        } else {
+            self.filtered
+                .iter()
+                .enumerate()
+                .take(current_idx)
+                .rev()
+                .find(|(_, seq)| is_error(seq))
+        };
+
+        if let Some((idx, _)) = target {
+            let offset_from_end = total.saturating_sub(idx + 1);
+            self.scroll_offset = offset_from_end;
+            self.selected_from_end = offset_from_end;
+            self.mode = Mode::Paused;
+            if self.paused_head_len.is_none() {
+                self.paused_head_len = Some(self.logs.len());
+            }
+        }
+    }
+
+    /// Bookmarks the selected line; unnamed bookmarks are numbered.
+    pub fn add_bookmark(&mut self, spec: BookmarkSpec) {
+        let Some((seq, entry)) = self
+            .selected_seq()
+            .and_then(|seq| Some((seq, self.entry_at_seq(seq)?)))
+        else {
+            self.last_notice = Some("Nothing to bookmark".to_string());
+            return;
+        };
+        if let Some(existing) = self.bookmarks.iter().find(|b| b.seq == Some(seq)) {
+            self.last_notice = Some(format!("Line is already bookmarked as {}", existing.label));
+            return;
+        }
+        // The lowest free number, so deleting a bookmark cannot cause a repeat.
+        let label = (1..)
+            .map(|n| format!("mark {n}"))
+            .find(|label| self.bookmarks.iter().all(|bm| bm.label != *label))
+            .unwrap_or_default();
+        let mut bookmark = Bookmark::new(seq, entry, self.repeat_of(seq), label);
+        bookmark.apply(spec);
+        self.last_notice = Some(format!(
+            "Added bookmark {} @ {}",
+            bookmark.label,
+            bookmark.timestamp.format("%H:%M:%S")
+        ));
+        let idx = self
+            .bookmarks
+            .partition_point(|b| bookmark_order(b) <= bookmark_order(&bookmark));
+        self.bookmarks.insert(idx, bookmark);
+    }
+
+    /// `b`: asks for a label, `#tag` and `-- note` before bookmarking the selected line.
+    pub fn begin_bookmark_edit(&mut self) {
+        if self.current_entry().is_none() {
+            self.last_notice = Some("Nothing to bookmark".to_string());
+            return;
+        }
+        self.input_mode = InputMode::BookmarkEdit(None, String::new());
+    }
+
+    pub fn open_bookmark_panel(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.last_notice = Some("No bookmarks yet".to_string());
+            return;
+        }
+        let idx = self.current_bookmark_position().map_or(0, |(idx, _)| idx);
+        self.input_mode = InputMode::Bookmarks(idx);
+    }
+
+    pub fn move_bookmark_cursor(&mut self, delta: isize) {
+        if let InputMode::Bookmarks(idx) = &mut self.input_mode {
+            let last = self.bookmarks.len().saturating_sub(1);
+            *idx = idx.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Jumps to the highlighted bookmark and closes the panel.
+    pub fn pick_bookmark(&mut self) {
+        if let InputMode::Bookmarks(idx) = self.input_mode {
+            self.input_mode = InputMode::Normal;
+            self.jump_to_bookmark(idx);
+        }
+    }
+
+    /// Reopens the prompt for the highlighted bookmark with its current text.
+    pub fn begin_bookmark_rename(&mut self) {
+        if let InputMode::Bookmarks(idx) = self.input_mode {
+            if let Some(bookmark) = self.bookmarks.get(idx) {
+                self.input_mode = InputMode::BookmarkEdit(Some(idx), bookmark.spec());
+            }
+        }
+    }
+
+    pub fn delete_bookmark(&mut self) {
+        let InputMode::Bookmarks(idx) = &mut self.input_mode else {
+            return;
+        };
+        if *idx >= self.bookmarks.len() {
+            return;
+        }
+        let removed = self.bookmarks.remove(*idx);
+        self.last_notice = Some(format!("Deleted bookmark {}", removed.label));
+        if self.bookmarks.is_empty() {
+            self.input_mode = InputMode::Normal;
+        } else {
+            *idx = (*idx).min(self.bookmarks.len() - 1);
+        }
+    }
+
+    pub fn cycle_bookmark_tag(&mut self) {
+        if let InputMode::Bookmarks(idx) = self.input_mode {
+            if let Some(bookmark) = self.bookmarks.get_mut(idx) {
+                bookmark.tag = bookmark.tag.next();
+            }
+        }
+    }
+
+    fn submit_bookmark_edit(&mut self, target: Option<usize>, text: &str) -> Result<(), String> {
+        let spec: BookmarkSpec = text.parse()?;
+        match target {
+            None => self.add_bookmark(spec),
+            Some(idx) => {
+                if let Some(bookmark) = self.bookmarks.get_mut(idx) {
+                    bookmark.apply(spec);
+                    self.last_notice = Some(format!("Updated bookmark {}", bookmark.label));
+                }
+                self.input_mode = InputMode::Bookmarks(idx);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn jump_bookmark(&mut self, direction: i32) {
+        if self.bookmarks.is_empty() || self.filtered.is_empty() {
+            return;
+        }
+        let current = self
+            .selected_order()
+            .unwrap_or_else(|| (Local::now(), u64::MAX));
+        let idx = if direction > 0 {
+            self.bookmarks
+                .iter()
+                .position(|b| bookmark_order(b) > current)
+                .unwrap_or(0)
+        } else {
+            self.bookmarks
+                .iter()
+                .rposition(|b| bookmark_order(b) < current)
+                .unwrap_or(self.bookmarks.len() - 1)
+        };
+        self.jump_to_bookmark(idx);
+    }
+
+    /// Selects the bookmarked entry itself, paging it back in from disk if it
+    /// was pruned; when filters hide it, the next visible row is selected.
+    fn jump_to_bookmark(&mut self, idx: usize) {
+        let Some(bm) = self.bookmarks.get(idx) else {
+            return;
+        };
+        let label = bm.label.clone();
+        let Some(seq) = bm.seq else {
+            self.last_notice = Some(format!("{label} is not in the current view"));
+            return;
+        };
+        if seq < self.first_seq {
+            self.page_in_through(seq);
+        }
+        if self.entry_at_seq(seq).is_none() {
+            self.last_notice = Some(format!("{label} is no longer loaded"));
+            return;
+        }
+        let row = self.filtered.partition_point(|s| *s < seq);
+        if row == self.filtered.len() {
+            self.last_notice = Some(format!("{label} is hidden by the filters"));
+            return;
+        }
+        let exact = self.filtered[row] == seq;
+        self.select_row(row);
+        self.scroll_offset = self.selected_from_end;
+        self.last_notice = Some(if exact {
+            format!("Jumped to {label}")
+        } else {
+            format!("{label} is hidden by the filters; showing the next line")
+        });
+    }
+
+    /// Rows that fit in `max_visible` screen lines, ending at the scroll position;
+    /// `row_height` gives the lines a row takes (more than one when wrapped), and
+    /// with context enabled a separator line before a row counts as well.
+    pub fn visible_logs(
+        &self,
+        max_visible: usize,
+        row_height: impl Fn(&VisibleRow) -> usize,
+    ) -> Vec<VisibleRow<'_>> {
+        let total = self.filtered.len();
+        if max_visible == 0 || total == 0 {
+            return Vec::new();
+        }
+        let offset = self.scroll_offset.min(total.saturating_sub(1));
+        let end = total.saturating_sub(offset);
+        let context = self.has_context();
+        let mut rows = Vec::new();
+        let mut used = 0;
+        for idx in (0..end).rev() {
+            let seq = self.filtered[idx];
+            let Some(entry) = self.entry_at_seq(seq) else {
+                continue;
+            };
+            let gap_before = context
+                && idx
+                    .checked_sub(1)
+                    .is_some_and(|prev| self.filtered[prev] + 1 != seq);
+            let row = VisibleRow {
+                index: idx,
+                seq,
+                entry,
+                is_context: context && !self.filters.matches(entry),
+                gap_before,
+                previous: idx
+                    .checked_sub(1)
+                    .and_then(|prev| self.entry_at_seq(self.filtered[prev]))
+                    .map(|prev| prev.timestamp),
+                group: self.group_badge(seq),
+            };
+            let height = row_height(&row).max(1);
+            // The selected (bottom) row is always shown, even if it is taller than the view.
+            if used + height > max_visible && !rows.is_empty() {
+                break;
+            }
+            used += height;
+            rows.push(VisibleRow {
+                gap_before: gap_before && used < max_visible,
+                ..row
+            });
+            used += usize::from(gap_before);
+        }
+        rows.reverse();
+        rows
+    }
+
+    pub fn filtered_len(&self) -> usize {
+        self.filtered.len()
+    }
+
+    pub fn selected_from_end(&self) -> usize {
+        self.selected_from_end
+    }
+
+    pub fn timeline_cursor_from_end(&self) -> Option<usize> {
+        self.timeline_cursor_from_end
+    }
+
+    pub fn filters(&self) -> &Filters {
+        &self.filters
+    }
+
+    pub fn filter_error(&self) -> Option<&String> {
+        self.filter_error.as_ref()
+    }
+
+    pub fn input_mode(&self) -> &InputMode {
+        &self.input_mode
+    }
+
+    pub fn bookmarks(&self) -> &Vec<Bookmark> {
+        &self.bookmarks
+    }
+
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    pub fn queued_len(&self) -> usize {
+        self.paused_head_len
+            .map(|head| {
+                self.logs
+                    .len()
+                    .saturating_sub(head)
+                    .saturating_add(self.paused_buffer.len())
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn ingest_stats(&self) -> Option<QueueStats> {
+        self.ingest.queue_stats()
+    }
+
+    pub fn total_logs(&self) -> usize {
+        self.logs.len()
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Entries on disk that are not currently held in memory.
+    pub fn spilled_len(&self) -> Option<u64> {
+        let store = self.spill.as_ref()?;
+        let first = store.first_seq().unwrap_or(self.first_seq);
+        Some(self.first_seq.saturating_sub(first) + self.evicted_tail.entries)
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    pub fn source_label(&self) -> &str {
+        &self.source_label
+    }
+
+    pub fn set_notice(&mut self, notice: String) {
+        self.last_notice = Some(notice);
+    }
+
+    pub fn last_notice(&self) -> Option<&String> {
+        self.last_notice.as_ref()
+    }
+
+    pub fn baseline_mode(&self) -> &BaselineMode {
+        &self.baseline_mode
+    }
+
+    pub fn baseline_overlay(&self) -> Option<&BaselineProfile> {
+        let profile = self.baseline_profile.as_ref()?;
+        if profile.bin_count != self.timeline.len()
+            || profile.window_secs != TIMELINE_WINDOW.as_secs()
+        {
+            return None;
+        }
+        Some(profile)
+    }
+
+    pub fn baseline_target(&self) -> Option<&PathBuf> {
+        self.baseline_target.as_ref()
+    }
+
+    pub fn save_baseline(&self, path: &Path) -> anyhow::Result<()> {
+        let profile = self.build_baseline_profile();
+        profile.save(path)
+    }
+
+    pub fn drift_bins(&self) -> Option<Vec<bool>> {
+        let baseline = self.baseline_overlay()?;
+        let current = self.timeline.data();
+        if baseline.bins.len() != current.len() {
+            return None;
         }
         Some(
             current
@@ -508,11 +1712,88 @@ impl App {
         self.baseline_overlay().map(|b| &b.top_tokens)
     }
 
+    /// Count recorded for `text` in the compared baseline; `Some(0)` means the
+    /// template is new, `None` that there is no baseline or it predates templates.
+    pub fn baseline_template_count(&self, text: &str) -> Option<u64> {
+        let templates = &self.baseline_overlay()?.top_templates;
+        if templates.is_empty() {
+            return None;
+        }
+        Some(
+            templates
+                .iter()
+                .find(|t| t.token == text)
+                .map_or(0, |t| t.count),
+        )
+    }
+
+    /// Templates seen now that the compared baseline never recorded.
+    pub fn new_template_count(&self) -> Option<usize> {
+        let sorted = self.templates.sorted(TemplateSort::Count);
+        let mut new = 0;
+        for template in sorted {
+            if self.baseline_template_count(&template.text())? == 0 {
+                new += 1;
+            }
+        }
+        Some(new)
+    }
+
+    /// The bookmarks, filters and paused position to remember for this file.
+    pub fn session_state(&self) -> SessionState {
+        let query = FilterQuery::from_filters(&self.filters);
+        SessionState {
+            filter: Some(query).filter(|query| *query != FilterQuery::default()),
+            since: self.filters.since.map(timestamp_string),
+            until: self.filters.until.map(timestamp_string),
+            selected: match self.mode {
+                Mode::Paused => self.selected_seq().and_then(|seq| {
+                    let entry = self.entry_at_seq(seq)?;
+                    Some(Anchor::of_entry(entry, self.repeat_of(seq)))
+                }),
+                Mode::Live => None,
+            },
+            bookmarks: self.bookmarks.iter().map(SavedBookmark::from).collect(),
+        }
+    }
+
+    /// Applies state saved by an earlier run; the selected line is selected
+    /// again when it is read back from the file. `history_truncated` says the
+    /// oldest saved lines were not read back to stay within the memory budget.
+    pub fn restore_session(&mut self, state: SessionState, history_truncated: bool) {
+        if let Some(query) = &state.filter {
+            if let Err(err) = self.apply_query(query) {
+                self.filter_error = Some(format!("saved filter: {err}"));
+            }
+        }
+        let since = state.since.as_deref().and_then(parse_timestamp);
+        let until = state.until.as_deref().and_then(parse_timestamp);
+        if since.is_some() || until.is_some() {
+            self.set_time_range(since, until);
+        }
+        self.bookmarks = state
+            .bookmarks
+            .into_iter()
+            .filter_map(SavedBookmark::into_bookmark)
+            .collect();
+        self.bookmarks.sort_by_key(bookmark_order);
+        self.pending_anchor = state.selected;
+        self.check_unloaded_bookmarks = history_truncated && !self.bookmarks.is_empty();
+        let rotated = self.bookmarks.iter().filter(|b| b.part.is_some()).count();
+        self.last_notice = Some(match rotated {
+            0 => format!("Restored {} bookmark(s)", self.bookmarks.len()),
+            n => format!(
+                "Restored {} bookmark(s), {n} in rotated parts",
+                self.bookmarks.len()
+            ),
+        });
+    }
+
     pub fn current_bookmark_position(&self) -> Option<(usize, &Bookmark)> {
-        let entry_ts = self.current_entry()?.timestamp;
+        let current = self.selected_order()?;
         let mut candidate: Option<(usize, &Bookmark)> = None;
         for (idx, bm) in self.bookmarks.iter().enumerate() {
-            if bm.timestamp <= entry_ts {
+            if bookmark_order(bm) <= current {
                 candidate = Some((idx, bm));
             } else {
                 break;
@@ -525,19 +1806,250 @@ impl App {
         &mut self.input_mode
     }
 
-    pub fn set_input_mode(&mut self, mode: InputMode) {
-        self.input_mode = mode;
+    fn entry_at_seq(&self, seq: u64) -> Option<&LogEntry> {
+        let idx = seq.checked_sub(self.first_seq)?;
+        self.logs.get(usize::try_from(idx).ok()?)
+    }
+
+    /// Sequence number of the selected row; it names one entry for as long as
+    /// the app runs, whatever is pruned or filtered.
+    fn selected_seq(&self) -> Option<u64> {
+        let idx = self
+            .filtered
+            .len()
+            .checked_sub(self.selected_from_end + 1)?;
+        self.filtered.get(idx).copied()
+    }
+
+    /// Where the selected row sorts among bookmarks.
+    fn selected_order(&self) -> Option<(DateTime<Local>, u64)> {
+        let seq = self.selected_seq()?;
+        Some((self.entry_at_seq(seq)?.timestamp, seq))
+    }
+
+    /// Loaded lines just before entry `seq` with the same timestamp and excerpt,
+    /// so saved bookmarks and positions tell repeated lines apart.
+    fn repeat_of(&self, seq: u64) -> usize {
+        let Some(entry) = self.entry_at_seq(seq) else {
+            return 0;
+        };
+        let excerpt = bookmarks::excerpt(&entry.message);
+        (self.first_seq..seq)
+            .rev()
+            .map_while(|earlier| self.entry_at_seq(earlier))
+            .take_while(|earlier| earlier.timestamp == entry.timestamp)
+            .filter(|earlier| bookmarks::excerpt(&earlier.message) == excerpt)
+            .count()
+    }
+
+    /// Gives restored bookmarks waiting for their line the entry `seq` if it is it.
+    fn resolve_bookmarks(&mut self, seq: u64) {
+        let Some(entry) = self.entry_at_seq(seq) else {
+            return;
+        };
+        let waiting: Vec<usize> = (0..self.bookmarks.len())
+            .filter(|&idx| {
+                let bm = &self.bookmarks[idx];
+                bm.seq.is_none() && bm.matches(entry)
+            })
+            .collect();
+        if waiting.is_empty() {
+            return;
+        }
+        let repeat = self.repeat_of(seq);
+        for idx in waiting {
+            let bm = &mut self.bookmarks[idx];
+            if bm.repeat == repeat {
+                bm.seq = Some(seq);
+            }
+        }
+        self.bookmarks.sort_by_key(bookmark_order);
+    }
+
+    /// Recomputes `filtered`, using the text index to skip entries that cannot
+    /// match; entries paged back in from disk are not indexed and are scanned.
+    fn rebuild_filtered(&mut self) {
+        let matches = self.matching_seqs();
+        self.trace_hits.clear();
+        if self.filters.trace.is_some() {
+            let timeline = &self.timeline;
+            self.trace_hits = matches
+                .iter()
+                .filter_map(|seq| self.entry_at_seq(*seq))
+                .map(|entry| timeline.bin_number(entry.timestamp))
+                .collect();
+        }
+        self.context_after_left = 0;
+        self.filtered = if self.collapse {
+            self.collapsed_rows(matches)
+        } else if self.has_context() {
+            self.with_context(matches)
+        } else {
+            matches
+        };
     }
 
-    fn filtered_indices(&self) -> Vec<usize> {
-        self.logs
+    fn matching_seqs(&self) -> VecDeque<u64> {
+        let first_seq = self.first_seq;
+        let Some(candidates) = self.text_index.candidates(&self.filters) else {
+            return self
+                .logs
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| self.filters.matches(entry))
+                .map(|(idx, _)| first_seq + idx as u64)
+                .collect();
+        };
+        let indexed_from = self.text_index.covers_from().max(first_seq);
+        let unindexed = (indexed_from - first_seq).min(self.logs.len() as u64) as usize;
+        let mut filtered: VecDeque<u64> = self
+            .logs
             .iter()
+            .take(unindexed)
             .enumerate()
             .filter(|(_, entry)| self.filters.matches(entry))
-            .map(|(idx, _)| idx)
+            .map(|(idx, _)| first_seq + idx as u64)
+            .collect();
+        filtered.extend(candidates.into_iter().filter(|seq| {
+            *seq >= indexed_from
+                && self
+                    .entry_at_seq(*seq)
+                    .is_some_and(|entry| self.filters.matches(entry))
+        }));
+        filtered
+    }
+
+    /// Widens each match to `context_before`/`context_after` neighbours, merging overlaps.
+    fn with_context(&self, matches: VecDeque<u64>) -> VecDeque<u64> {
+        let Some(last_seq) = (self.first_seq + self.logs.len() as u64).checked_sub(1) else {
+            return matches;
+        };
+        let mut rows = VecDeque::with_capacity(matches.len());
+        let mut next_free = self.first_seq;
+        for seq in matches {
+            let from = seq
+                .saturating_sub(self.context_before as u64)
+                .max(next_free);
+            let to = (seq + self.context_after as u64).min(last_seq);
+            rows.extend(from..=to);
+            next_free = next_free.max(to + 1);
+        }
+        rows
+    }
+
+    fn has_context(&self) -> bool {
+        !self.collapse && (self.context_before > 0 || self.context_after > 0)
+    }
+
+    fn group_badge(&self, seq: u64) -> Option<GroupBadge> {
+        let group = self.groups.get(&seq).filter(|g| g.count > 1)?;
+        Some(GroupBadge {
+            count: group.count,
+            last: self.entry_at_seq(group.last)?.timestamp,
+            expanded: group.expanded,
+        })
+    }
+
+    /// Adds a matching entry to the newest group if it repeats it, else starts a row.
+    fn push_collapsed(&mut self, seq: u64, key: DedupKey) {
+        if let Some((group_seq, tail)) = &self.tail_group {
+            if *tail == key {
+                if let Some(group) = self.groups.get_mut(group_seq) {
+                    group.last = seq;
+                    group.count += 1;
+                    if group.expanded {
+                        self.filtered.push_back(seq);
+                    }
+                    return;
+                }
+            }
+        }
+        self.groups.insert(
+            seq,
+            Group {
+                last: seq,
+                count: 1,
+                expanded: false,
+            },
+        );
+        self.filtered.push_back(seq);
+        self.tail_group = Some((seq, key));
+    }
+
+    /// Members of the group starting at `first` after the first one, oldest first.
+    fn group_members(&self, first: u64, group: &Group) -> Vec<u64> {
+        let Some(key) = self.entry_at_seq(first).map(DedupKey::of) else {
+            return Vec::new();
+        };
+        self.group_members_after(first, group.last, &key)
+    }
+
+    fn group_members_after(&self, first: u64, last: u64, key: &DedupKey) -> Vec<u64> {
+        (first + 1..=last)
+            .filter(|seq| {
+                self.entry_at_seq(*seq)
+                    .is_some_and(|e| self.filters.matches(e) && DedupKey::of(e) == *key)
+            })
             .collect()
     }
 
+    /// Re-keys a group whose first member was just evicted.
+    fn regroup_after_evict(&mut self, evicted: &LogEntry, seq: u64) {
+        let Some(group) = self.groups.remove(&seq) else {
+            return;
+        };
+        let key = DedupKey::of(evicted);
+        let next = (seq + 1..=group.last).find(|n| {
+            self.entry_at_seq(*n)
+                .is_some_and(|e| self.filters.matches(e) && DedupKey::of(e) == key)
+        });
+        let Some(next) = next.filter(|_| group.count > 1) else {
+            if self.tail_group.as_ref().is_some_and(|(k, _)| *k == seq) {
+                self.tail_group = None;
+            }
+            return;
+        };
+        if !group.expanded {
+            self.filtered.push_front(next);
+        }
+        self.groups.insert(
+            next,
+            Group {
+                count: group.count - 1,
+                ..group
+            },
+        );
+        if let Some((k, _)) = &mut self.tail_group {
+            if *k == seq {
+                *k = next;
+            }
+        }
+    }
+
+    fn collapsed_rows(&mut self, matches: VecDeque<u64>) -> VecDeque<u64> {
+        let expanded: HashSet<u64> = self
+            .groups
+            .iter()
+            .filter(|(_, g)| g.expanded)
+            .map(|(seq, _)| *seq)
+            .collect();
+        self.groups.clear();
+        self.tail_group = None;
+        self.filtered = VecDeque::with_capacity(matches.len());
+        for seq in matches {
+            let Some(key) = self.entry_at_seq(seq).map(DedupKey::of) else {
+                continue;
+            };
+            self.push_collapsed(seq, key);
+            if let Some(group) = self.groups.get_mut(&seq) {
+                if expanded.contains(&seq) {
+                    group.expanded = true;
+                }
+            }
+        }
+        std::mem::take(&mut self.filtered)
+    }
+
     fn clamp_selection(&mut self) {
         let filtered_len = self.filtered_len();
         if filtered_len == 0 {
@@ -559,31 +2071,43 @@ impl App {
         }
         let idx_from_oldest = len.saturating_sub(cursor + 1);
         let bin_start = self.timeline.bin_start(idx_from_oldest);
-        let filtered_indices = self.filtered_indices();
-        let filtered_len = filtered_indices.len();
+        self.page_in_since(bin_start);
+        let filtered_len = self.filtered.len();
         if filtered_len == 0 {
             self.scroll_offset = 0;
-            self.selected_from_end = 0;
-            return;
-        }
-        if let Some((idx_in_filtered, _)) =
-            filtered_indices.iter().enumerate().find(|(_, log_idx)| {
-                self.logs
-                    .get(**log_idx)
-                    .map(|entry| entry.timestamp >= bin_start)
-                    .unwrap_or(false)
-            })
-        {
-            self.scroll_offset = filtered_len.saturating_sub(idx_in_filtered + 1);
-            self.selected_from_end = self.scroll_offset;
-        } else {
-            self.scroll_offset = filtered_len;
-            self.selected_from_end = self.scroll_offset;
+            self.selected_from_end = 0;
+            return;
         }
+        self.scroll_offset = self
+            .filtered_offset_at_or_after(bin_start)
+            .unwrap_or(filtered_len);
+        self.selected_from_end = self.scroll_offset;
         self.clamp_selection();
     }
 
+    /// Offset from the newest filtered entry of the first one at or after `ts`.
+    fn filtered_offset_at_or_after(&self, ts: DateTime<Local>) -> Option<usize> {
+        let (idx, _) = self.filtered.iter().enumerate().find(|(_, seq)| {
+            self.entry_at_seq(**seq)
+                .map(|entry| entry.timestamp >= ts)
+                .unwrap_or(false)
+        })?;
+        Some(self.filtered.len().saturating_sub(idx + 1))
+    }
+
+    /// Rebuilds the rows; a paused selection stays on its entry, or moves to the
+    /// next row still shown, at the same height on screen.
     fn after_filter_change(&mut self) {
+        let kept = match self.mode {
+            Mode::Paused => self.selected_seq().map(|seq| {
+                (
+                    seq,
+                    self.selected_from_end.saturating_sub(self.scroll_offset),
+                )
+            }),
+            Mode::Live => None,
+        };
+        self.rebuild_filtered();
         let filtered_len = self.filtered_len();
         if filtered_len == 0 {
             self.scroll_offset = 0;
@@ -596,28 +2120,101 @@ impl App {
         if self.selected_from_end >= filtered_len {
             self.selected_from_end = filtered_len.saturating_sub(1);
         }
+        if let Some((seq, above_bottom)) = kept {
+            let row = self
+                .filtered
+                .partition_point(|s| *s < seq)
+                .min(filtered_len - 1);
+            self.selected_from_end = filtered_len - 1 - row;
+            self.scroll_offset = self.selected_from_end.saturating_sub(above_bottom);
+        }
     }
 
     fn current_entry(&self) -> Option<&LogEntry> {
-        let filtered = self.filtered_indices();
-        if filtered.is_empty() {
-            return None;
+        self.entry_at_seq(self.selected_seq()?)
+    }
+
+    fn ingest_lines(&mut self, lines: Vec<Ingested>) {
+        for Ingested { at, raw, entry } in lines {
+            if self.check_unloaded_bookmarks {
+                self.warn_unloaded_bookmarks(entry.timestamp);
+            }
+            if let Some(tee) = &mut self.tee {
+                if let Err(err) = tee.write_line(at, &raw) {
+                    self.last_notice = Some(format!("Tee stopped: {err}"));
+                    self.tee = None;
+                }
+            }
+            let (info, warn, error) = match entry.level {
+                Level::Info => (1, 0, 0),
+                Level::Warn => (0, 1, 0),
+                Level::Error => (0, 0, 1),
+            };
+            self.timeline.record(at, info, warn, error);
+            self.record_tokens(&entry);
+            self.templates.add(&entry, at);
+            let restores_selection = self.mode == Mode::Live
+                && self
+                    .pending_anchor
+                    .as_ref()
+                    .is_some_and(|anchor| anchor.matches(&entry));
+            match self.mode {
+                Mode::Paused => self.push_paused_entry(entry, at),
+                Mode::Live => self.push_log(entry),
+            };
+            if restores_selection {
+                let seq = self.first_seq + self.logs.len() as u64 - 1;
+                let repeat = self.repeat_of(seq);
+                if self
+                    .pending_anchor
+                    .as_ref()
+                    .is_some_and(|anchor| anchor.repeat == repeat)
+                {
+                    self.pending_anchor = None;
+                    self.select_restored(seq);
+                }
+            }
         }
-        let total = filtered.len();
-        let target_idx = total.saturating_sub(self.selected_from_end + 1);
-        filtered.get(target_idx).and_then(|idx| self.logs.get(*idx))
     }
 
-    fn flush_pending(&mut self) {
-        if self.paused_buffer.is_empty() {
+    /// Warns about restored bookmarks older than `first`, the first line read
+    /// back; they were in the part of the history left out of the budget.
+    fn warn_unloaded_bookmarks(&mut self, first: DateTime<Local>) {
+        self.check_unloaded_bookmarks = false;
+        let unloaded = self
+            .bookmarks
+            .iter()
+            .filter(|bm| bm.seq.is_none() && bm.timestamp < first)
+            .count();
+        if unloaded > 0 {
+            self.last_notice = Some(format!(
+                "{unloaded} bookmark(s) are older than the history that fits in max_bytes"
+            ));
+        }
+    }
+
+    /// Pauses on the entry `seq` if it was just added to the view.
+    fn select_restored(&mut self, seq: u64) {
+        if self.mode != Mode::Live {
             return;
         }
+        if let Ok(idx) = self.filtered.binary_search(&seq) {
+            self.select_row(idx);
+            self.scroll_offset = self.selected_from_end;
+            self.last_notice = Some("Restored the previous position".to_string());
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        self.restore_tail();
         while let Some(entry) = self.paused_buffer.pop_front() {
+            self.paused_bytes -= entry_bytes(&entry);
             self.push_log(entry);
         }
     }
 
     fn push_paused_entry(&mut self, entry: LogEntry, now: DateTime<Local>) {
+        self.paused_bytes += entry_bytes(&entry);
         self.paused_buffer.push_back(entry);
         self.prune_paused(now);
     }
@@ -630,27 +2227,275 @@ impl App {
                 .unwrap_or_default()
                 > self.max_age
             {
-                self.paused_buffer.pop_front();
+                if let Some(entry) = self.paused_buffer.pop_front() {
+                    self.paused_bytes -= entry_bytes(&entry);
+                }
             } else {
                 break;
             }
         }
-        let allowed = self.max_lines.saturating_sub(self.logs.len());
-        if allowed == 0 {
-            self.paused_buffer.clear();
-            return;
-        }
-        let excess = self.paused_buffer.len().saturating_sub(allowed);
-        for _ in 0..excess {
-            self.paused_buffer.pop_front();
+        self.trim_paused();
+    }
+
+    /// Drops the oldest queued lines that would not fit next to the live
+    /// entries; room is what is left once going live drops paged-in history
+    /// and restores the tail.
+    fn trim_paused(&mut self) {
+        let resident = self.logs.len() - self.paged_in + self.evicted_tail.live;
+        let resident_bytes =
+            self.bytes.saturating_sub(self.paged_in_bytes) + self.evicted_tail.live_bytes;
+        let allowed = self.max_lines.saturating_sub(resident);
+        let allowed_bytes = self.max_bytes.saturating_sub(resident_bytes);
+        while self.paused_buffer.len() > allowed || self.paused_bytes > allowed_bytes {
+            let Some(entry) = self.paused_buffer.pop_front() else {
+                break;
+            };
+            self.paused_bytes -= entry_bytes(&entry);
         }
     }
 
     fn push_log(&mut self, entry: LogEntry) {
-        if self.logs.len() >= self.max_lines {
-            self.logs.pop_front();
+        self.restore_tail();
+        let size = entry_bytes(&entry);
+        while !self.logs.is_empty()
+            && (self.logs.len() >= self.max_lines || self.bytes + size > self.max_bytes)
+        {
+            self.pop_front_log();
+        }
+        let seq = self.first_seq + self.logs.len() as u64;
+        let matched = self.filters.matches(&entry);
+        if matched {
+            self.record_trace_hit(entry.timestamp);
+        }
+        if matched && self.collapse {
+            self.push_collapsed(seq, DedupKey::of(&entry));
+        } else if matched {
+            if self.has_context() {
+                let after_shown = self.filtered.back().map_or(self.first_seq, |last| last + 1);
+                let from = seq
+                    .saturating_sub(self.context_before as u64)
+                    .max(after_shown)
+                    .max(self.first_seq);
+                self.filtered.extend(from..seq);
+                self.context_after_left = self.context_after;
+            }
+            self.filtered.push_back(seq);
+        } else if self.context_after_left > 0 {
+            self.context_after_left -= 1;
+            self.filtered.push_back(seq);
         }
+        self.text_index.add(seq, &entry);
+        self.bytes += size;
         self.logs.push_back(entry);
+        self.resolve_bookmarks(seq);
+    }
+
+    fn pop_front_log(&mut self) -> Option<LogEntry> {
+        let entry = self.logs.pop_front()?;
+        let seq = self.first_seq;
+        if self.filtered.front() == Some(&seq) {
+            self.filtered.pop_front();
+        }
+        if seq >= self.text_index.covers_from() {
+            self.text_index.evict_through(seq);
+        }
+        self.first_seq += 1;
+        if self.collapse {
+            self.regroup_after_evict(&entry, seq);
+        }
+        let size = entry_bytes(&entry);
+        self.bytes = self.bytes.saturating_sub(size);
+        if self.paged_in > 0 {
+            self.paged_in -= 1;
+            self.paged_in_bytes = self.paged_in_bytes.saturating_sub(size);
+        }
+        if let Some(store) = &mut self.spill {
+            if seq >= store.next_seq() {
+                if let Err(err) = store.append(seq, &entry) {
+                    self.last_notice = Some(format!("Spill disabled: {err}"));
+                    self.spill = None;
+                }
+            }
+        } else if let Some(bm) = self.bookmarks.iter().find(|bm| bm.seq == Some(seq)) {
+            self.last_notice = Some(format!(
+                "Bookmark {:?} no longer fits in memory; set spill_dir to keep it",
+                bm.label
+            ));
+        }
+        Some(entry)
+    }
+
+    /// Reads up to `count` spilled entries older than `logs.front()` back into
+    /// memory. Once `bytes` would pass `max_bytes`, the newest resident entries
+    /// move to disk to make room, so the window slides back through history.
+    /// Returns the entries loaded and the rows that left the back of `filtered`.
+    fn page_in_older(&mut self, count: usize) -> (usize, usize) {
+        let Some(store) = &mut self.spill else {
+            return (0, 0);
+        };
+        let from = self.first_seq.saturating_sub(count as u64);
+        let page = match store.read_range(from, self.first_seq) {
+            Ok(page) => page,
+            Err(err) => {
+                self.last_notice = Some(format!("Failed to read spilled history: {err}"));
+                return (0, 0);
+            }
+        };
+        let mut loaded = 0;
+        let mut dropped = 0;
+        for (seq, entry) in page.into_iter().rev() {
+            if seq + 1 != self.first_seq {
+                break;
+            }
+            let size = entry_bytes(&entry);
+            match self.evict_tail(size) {
+                Some(rows) => dropped += rows,
+                None => break,
+            }
+            loaded += 1;
+            if self.filters.matches(&entry) {
+                self.filtered.push_front(seq);
+                self.record_trace_hit(entry.timestamp);
+            }
+            self.bytes += size;
+            self.paged_in += 1;
+            self.paged_in_bytes += size;
+            self.logs.push_front(entry);
+            self.first_seq = seq;
+        }
+        if let Some(head) = &mut self.paused_head_len {
+            *head += loaded;
+        }
+        self.scroll_offset = self.scroll_offset.saturating_sub(dropped);
+        self.selected_from_end = self.selected_from_end.saturating_sub(dropped);
+        if loaded > 0 && (self.has_context() || self.collapse) {
+            // Context or groups around the oldest loaded matches can reach into newer rows.
+            let after_left = self.context_after_left;
+            self.rebuild_filtered();
+            self.context_after_left = after_left;
+        }
+        (loaded, dropped)
+    }
+
+    /// Moves the newest resident entries to `spill` until `size` more bytes fit
+    /// in `max_bytes`. Returns the rows that left `filtered`, or `None` when the
+    /// entries could not be written.
+    fn evict_tail(&mut self, size: usize) -> Option<usize> {
+        let mut dropped = 0;
+        while !self.logs.is_empty() && self.bytes + size > self.max_bytes {
+            if !self.spill_resident() {
+                return None;
+            }
+            let entry = self.logs.pop_back()?;
+            let seq = self.first_seq + self.logs.len() as u64;
+            if self.filtered.back() == Some(&seq) {
+                self.filtered.pop_back();
+                dropped += 1;
+            }
+            let size = entry_bytes(&entry);
+            self.bytes -= size;
+            if self.logs.len() < self.paged_in {
+                self.paged_in -= 1;
+                self.paged_in_bytes -= size;
+            } else {
+                self.evicted_tail.live += 1;
+                self.evicted_tail.live_bytes += size;
+            }
+            self.evicted_tail.entries += 1;
+            if let Some(head) = &mut self.paused_head_len {
+                *head = head.saturating_sub(1);
+            }
+        }
+        Some(dropped)
+    }
+
+    /// Appends resident entries the spill store does not have yet, so any of
+    /// them can be dropped from memory.
+    fn spill_resident(&mut self) -> bool {
+        let Some(store) = &mut self.spill else {
+            return false;
+        };
+        let end = self.first_seq + self.logs.len() as u64;
+        for seq in store.next_seq().max(self.first_seq)..end {
+            let entry = &self.logs[(seq - self.first_seq) as usize];
+            if let Err(err) = store.append(seq, entry) {
+                self.last_notice = Some(format!("Spill disabled: {err}"));
+                self.spill = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Reads back the entries `evict_tail` moved to disk, dropping paged-in
+    /// history from the front as the limits require.
+    fn restore_tail(&mut self) {
+        if self.evicted_tail.entries == 0 {
+            return;
+        }
+        let mut from = self.first_seq + self.logs.len() as u64;
+        let to = from + self.evicted_tail.entries;
+        self.evicted_tail = EvictedTail::default();
+        while from < to {
+            let until = (from + PAGE_LINES as u64).min(to);
+            let page = match self
+                .spill
+                .as_mut()
+                .map(|store| store.read_range(from, until))
+            {
+                Some(Ok(page)) if !page.is_empty() => page,
+                Some(Err(err)) => {
+                    self.last_notice = Some(format!("Failed to read spilled history: {err}"));
+                    break;
+                }
+                _ => break,
+            };
+            for (_, entry) in page {
+                let size = entry_bytes(&entry);
+                while !self.logs.is_empty()
+                    && (self.logs.len() >= self.max_lines || self.bytes + size > self.max_bytes)
+                {
+                    self.pop_front_log();
+                }
+                // Still in the text index: it only drops entries evicted from the front.
+                self.bytes += size;
+                self.logs.push_back(entry);
+            }
+            from = until;
+        }
+        if from < to {
+            // Whatever could not be read back is skipped so sequence numbers stay unique.
+            while self.pop_front_log().is_some() {}
+            self.first_seq = to;
+        }
+        let after_left = self.context_after_left;
+        self.rebuild_filtered();
+        self.context_after_left = after_left;
+    }
+
+    /// Pages in spilled history until `logs` reaches back to `ts` (bounded by `PAGE_IN_LIMIT`).
+    fn page_in_since(&mut self, ts: DateTime<Local>) {
+        let covered = self.logs.front().is_some_and(|e| e.timestamp <= ts);
+        if covered {
+            return;
+        }
+        let Some(target) = self.spill.as_ref().and_then(|s| s.seq_at_or_after(ts)) else {
+            return;
+        };
+        self.page_in_through(target);
+    }
+
+    /// Pages spilled entries back in until entry `target` is loaded again.
+    fn page_in_through(&mut self, target: u64) {
+        let wanted = self.first_seq.saturating_sub(target) as usize;
+        let mut remaining = wanted.min(PAGE_IN_LIMIT);
+        while remaining > 0 {
+            let (loaded, _) = self.page_in_older(remaining.min(PAGE_LINES * 8));
+            if loaded == 0 {
+                break;
+            }
+            remaining = remaining.saturating_sub(loaded);
+        }
     }
 
     fn prune(&mut self, now: DateTime<Local>) {
@@ -661,20 +2506,38 @@ impl App {
                 .unwrap_or_default()
                 > self.max_age
             {
-                self.logs.pop_front();
+                self.pop_front_log();
             } else {
                 break;
             }
         }
-        while self.logs.len() > self.max_lines {
-            self.logs.pop_front();
+        while self.logs.len() > self.max_lines || self.bytes > self.max_bytes {
+            if self.pop_front_log().is_none() {
+                break;
+            }
         }
     }
 
     fn build_baseline_profile(&self) -> BaselineProfile {
         let bins = self.timeline.data();
         let tokens = self.top_tokens_now(12);
-        BaselineProfile::new(self.timeline.len(), TIMELINE_WINDOW.as_secs(), bins, tokens)
+        let templates = self
+            .templates
+            .sorted(TemplateSort::Count)
+            .into_iter()
+            .take(BASELINE_TEMPLATES)
+            .map(|template| TokenCount {
+                token: template.text(),
+                count: template.count,
+            })
+            .collect();
+        BaselineProfile::new(
+            self.timeline.len(),
+            TIMELINE_WINDOW.as_secs(),
+            bins,
+            tokens,
+            templates,
+        )
     }
 
     fn record_tokens(&mut self, entry: &LogEntry) {
@@ -692,6 +2555,44 @@ impl App {
     }
 }
 
+/// Passes the spilled entries with `from <= seq < to` to `f`, a page at a time.
+fn for_each_spilled(
+    spill: &mut Option<SpillStore>,
+    from: u64,
+    to: u64,
+    mut f: impl FnMut(&LogEntry) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(store) = spill else {
+        return Ok(());
+    };
+    let mut from = from.max(store.first_seq().unwrap_or(to));
+    while from < to {
+        let until = (from + PAGE_LINES as u64).min(to);
+        for (_, entry) in store.read_range(from, until)? {
+            f(&entry)?;
+        }
+        from = until;
+    }
+    Ok(())
+}
+
+/// Bookmarks sort by time, and by entry among lines sharing a timestamp.
+fn bookmark_order(bm: &Bookmark) -> (DateTime<Local>, u64) {
+    (bm.timestamp, bm.seq.unwrap_or(0))
+}
+
+fn entry_bytes(entry: &LogEntry) -> usize {
+    std::mem::size_of::<LogEntry>()
+        + entry.target.len()
+        + entry.message.len()
+        + entry.raw.as_ref().map_or(0, String::len)
+        + entry
+            .fields
+            .iter()
+            .map(|(key, value)| std::mem::size_of::<(String, String)>() + key.len() + value.len())
+            .sum::<usize>()
+}
+
 fn is_drift(current: &crate::timeline::Bin, baseline: &crate::timeline::Bin) -> bool {
     let cur_total = current.info + current.warn + current.error;
     let base_total = baseline.info + baseline.warn + baseline.error;
@@ -734,8 +2635,9 @@ fn prune_token_counts(map: &mut HashMap<String, u64>, limit: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bookmarks::BookmarkTag;
     use crate::config::BaselineMode;
-    use rand::SeedableRng;
+    use crate::mock::{MockGen, Scenario};
 
     fn base_entry() -> LogEntry {
         LogEntry {
@@ -743,12 +2645,14 @@ mod tests {
             level: Level::Info,
             target: "test".to_string(),
             message: "msg".to_string(),
+            fields: Vec::new(),
+            raw: None,
         }
     }
 
     #[test]
     fn paused_buffer_respects_max_lines() {
-        let ingest = Ingest::Mock(rand::rngs::SmallRng::seed_from_u64(1));
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(1))));
         let mut app = App::new(ingest, 5, "mock".to_string(), BaselineMode::Off);
         app.mode = Mode::Paused;
         for _ in 0..10 {
@@ -759,7 +2663,7 @@ mod tests {
 
     #[test]
     fn paused_buffer_drops_old_entries() {
-        let ingest = Ingest::Mock(rand::rngs::SmallRng::seed_from_u64(2));
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(2))));
         let mut app = App::new(ingest, 10, "mock".to_string(), BaselineMode::Off);
         app.mode = Mode::Paused;
         let old = LogEntry {
@@ -770,6 +2674,873 @@ mod tests {
         assert!(app.paused_buffer.is_empty());
     }
 
+    #[test]
+    fn replaying_a_tee_reproduces_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let scenario = Scenario {
+            rates: vec![crate::mock::RatePoint {
+                at: "0s".to_string(),
+                per_sec: 2_000.0,
+            }],
+            ..Scenario::default()
+        };
+        let ingest = Ingest::Mock(Box::new(MockGen::new(scenario, Some(3))));
+        let mut live = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        live.start_tee(&path).unwrap();
+        for _ in 0..20 {
+            live.tick();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        live.finish_tee().unwrap();
+        assert!(live.total_logs() > 0);
+
+        let ingest = Ingest::new(
+            crate::config::SourceConfig::Replay(path),
+            crate::config::IngestConfig {
+                queue_capacity: 10,
+                drop_policy: crate::queue::DropPolicy::Block,
+            },
+        )
+        .unwrap();
+        let mut replayed = App::new(ingest, 100, "replay".to_string(), BaselineMode::Off);
+        replayed.tick();
+        assert_eq!(replayed.timeline.data(), live.timeline.data());
+        assert_eq!(replayed.token_counts, live.token_counts);
+        assert_eq!(replayed.total_logs(), live.total_logs());
+    }
+
+    #[test]
+    fn filtered_index_tracks_pushes_prunes_and_filter_changes() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(4))));
+        let mut app = App::new(ingest, 6, "mock".to_string(), BaselineMode::Off);
+        app.set_filter_text(Some("deadlock".to_string()));
+        for i in 0..10 {
+            let message = if i % 3 == 0 { "deadlock retry" } else { "ok" };
+            app.push_log(LogEntry {
+                message: message.to_string(),
+                ..base_entry()
+            });
+        }
+        let expected: Vec<u64> = (4..10).filter(|i| i % 3 == 0).collect();
+        assert_eq!(app.filtered.iter().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(app.visible_logs(10, |_| 1).len(), expected.len());
+
+        app.begin_filter_edit();
+        if let InputMode::FilterText(buf) = app.input_mode_mut() {
+            *buf = "retr".to_string();
+        }
+        app.preview_filter_edit();
+        assert_eq!(app.filtered.iter().copied().collect::<Vec<_>>(), expected);
+        app.cancel_filter_edit();
+        assert_eq!(app.filters().text.as_deref(), Some("deadlock"));
+
+        app.clear_filters();
+        assert_eq!(app.filtered_len(), 6);
+        assert_eq!(
+            app.current_entry().map(|e| e.message.as_str()),
+            Some("deadlock retry")
+        );
+    }
+
+    #[test]
+    fn scrolling_past_memory_pages_in_spilled_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(5))));
+        let mut app = App::new(ingest, 10, "mock".to_string(), BaselineMode::Off);
+        app.enable_spill(dir.path()).unwrap();
+        for i in 0..50 {
+            app.push_log(LogEntry {
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        assert_eq!(app.total_logs(), 10);
+        assert_eq!(app.spilled_len(), Some(40));
+
+        app.scroll_up(25);
+        assert_eq!(app.current_entry().unwrap().message, "line 24");
+        assert_eq!(app.queued_len(), 0);
+
+        // Paged-in entries are not in the text index but must still be found.
+        app.set_filter_text(Some("line 2".to_string()));
+        let expected: Vec<u64> = (24..30).collect();
+        assert_eq!(app.filtered.iter().copied().collect::<Vec<_>>(), expected);
+        app.set_filter_text(None);
+
+        app.go_live();
+        app.prune(Local::now());
+        assert_eq!(app.total_logs(), 10);
+        assert_eq!(app.spilled_len(), Some(40));
+    }
+
+    #[test]
+    fn paging_in_slides_the_window_within_the_byte_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(5))));
+        let mut app = App::new(ingest, 10_000, "mock".to_string(), BaselineMode::Off);
+        app.enable_spill(dir.path()).unwrap();
+        let size = entry_bytes(&LogEntry {
+            message: "line 000".to_string(),
+            ..base_entry()
+        });
+        app.set_max_bytes(size * 15);
+        for i in 0..1000 {
+            app.push_log(LogEntry {
+                message: format!("line {i:03}"),
+                ..base_entry()
+            });
+        }
+        assert_eq!(app.total_logs(), 15);
+
+        app.scroll_up(900);
+        assert_eq!(app.current_entry().unwrap().message, "line 099");
+        assert!(app.bytes <= app.max_bytes);
+        app.scroll_up(500);
+        assert_eq!(app.current_entry().unwrap().message, "line 000");
+        assert!(app.bytes <= app.max_bytes);
+        assert_eq!(app.spilled_len(), Some(985));
+
+        // The budget is full, so there is no room to queue new lines.
+        for i in 1000..1020 {
+            app.push_paused_entry(
+                LogEntry {
+                    message: format!("line {i}"),
+                    ..base_entry()
+                },
+                Local::now(),
+            );
+        }
+        assert_eq!((app.paused_buffer.len(), app.paused_bytes), (0, 0));
+        app.go_live();
+        assert_eq!(app.total_logs(), 15);
+        assert_eq!(app.first_seq, 985);
+        assert_eq!(app.current_entry().unwrap().message, "line 999");
+        assert_eq!(app.filtered_len(), 15);
+        app.set_filter_text(Some("line 99".to_string()));
+        assert_eq!(
+            app.filtered.iter().copied().collect::<Vec<_>>(),
+            (990..1000).collect::<Vec<_>>()
+        );
+        app.set_filter_text(None);
+        app.push_log(LogEntry {
+            message: "line 1000".to_string(),
+            ..base_entry()
+        });
+        assert!(app.bytes <= app.max_bytes);
+        assert_eq!(app.logs.back().unwrap().message, "line 1000");
+    }
+
+    #[test]
+    fn lowering_the_limits_evicts_right_away() {
+        let dir = tempfile::tempdir().unwrap();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(5))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        app.enable_spill(dir.path()).unwrap();
+        for i in 0..20 {
+            app.push_log(LogEntry {
+                message: format!("line {i:02}"),
+                ..base_entry()
+            });
+        }
+        app.select_row(2);
+        app.push_paused_entry(base_entry(), Local::now());
+
+        app.run_command("set max_lines=5").unwrap();
+        assert_eq!(app.last_notice().unwrap(), "Set max_lines=5");
+        assert_eq!(app.total_logs(), 5);
+        assert_eq!(app.spilled_len(), Some(15));
+        assert_eq!(app.current_entry().unwrap().message, "line 15");
+        assert_eq!(app.paused_buffer.len(), 0, "no room left to queue");
+
+        let size = entry_bytes(app.current_entry().unwrap());
+        app.run_command(&format!("set max_bytes={}", size * 3))
+            .unwrap();
+        assert_eq!(app.total_logs(), 3);
+        assert!(app.bytes <= app.max_bytes);
+        assert_eq!(app.current_entry().unwrap().message, "line 17");
+        assert_eq!(app.mode, Mode::Paused);
+    }
+
+    #[test]
+    fn context_rows_match_between_incremental_and_rebuild() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(8))));
+        let mut app = App::new(ingest, 18, "mock".to_string(), BaselineMode::Off);
+        app.set_filter_text(Some("deadlock".to_string()));
+        app.set_context(1, 2);
+        for i in 0..24 {
+            let message = if matches!(i, 6 | 8 | 17 | 23) {
+                "deadlock"
+            } else {
+                "ok"
+            };
+            app.push_log(LogEntry {
+                message: message.to_string(),
+                ..base_entry()
+            });
+        }
+        let incremental: Vec<u64> = app.filtered.iter().copied().collect();
+        assert_eq!(incremental, vec![6, 7, 8, 9, 10, 16, 17, 18, 19, 22, 23]);
+        app.rebuild_filtered();
+        assert_eq!(
+            app.filtered.iter().copied().collect::<Vec<_>>(),
+            incremental
+        );
+
+        let rows = app.visible_logs(5, |_| 1);
+        let shape: Vec<_> = rows
+            .iter()
+            .map(|r| (r.index, r.is_context, r.gap_before))
+            .collect();
+        assert_eq!(
+            shape,
+            vec![
+                (7, true, false),
+                (8, true, false),
+                (9, true, true),
+                (10, false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsed_groups_survive_eviction_and_expand_in_place() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(9))));
+        let mut app = App::new(ingest, 8, "mock".to_string(), BaselineMode::Off);
+        app.toggle_collapse();
+        let messages = [
+            "boot", "retry 1", "retry 2", "retry 3", "retry 4", "ok", "retry 5", "retry 6",
+            "retry 7",
+        ];
+        for message in messages {
+            app.push_log(LogEntry {
+                message: message.to_string(),
+                ..base_entry()
+            });
+        }
+        // "boot" (seq 0) was evicted; evicting seq 1 next re-keys its group to seq 2.
+        let shape = |app: &App| -> Vec<(u64, usize)> {
+            app.filtered
+                .iter()
+                .map(|seq| (*seq, app.groups[seq].count))
+                .collect()
+        };
+        assert_eq!(shape(&app), vec![(1, 4), (5, 1), (6, 3)]);
+        app.push_log(base_entry());
+        assert_eq!(shape(&app), vec![(2, 3), (5, 1), (6, 3), (9, 1)]);
+        let incremental = shape(&app);
+        app.rebuild_filtered();
+        assert_eq!(shape(&app), incremental);
+
+        app.selected_from_end = 3;
+        app.toggle_selected_group();
+        assert_eq!(
+            app.filtered.iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 4, 5, 6, 9]
+        );
+        assert_eq!(app.current_entry().unwrap().message, "retry 2");
+        app.selected_from_end = 3;
+        app.toggle_selected_group();
+        assert_eq!(
+            app.filtered.iter().copied().collect::<Vec<_>>(),
+            vec![2, 5, 6, 9]
+        );
+        assert_eq!(
+            app.visible_logs(10, |_| 1)[0].group.as_ref().unwrap().count,
+            3
+        );
+    }
+
+    #[test]
+    fn goto_time_and_time_range_use_entry_timestamps() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(7))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let base = Local::now() - chrono::Duration::minutes(10);
+        for i in 0..20 {
+            app.push_log(LogEntry {
+                timestamp: base + chrono::Duration::seconds(i),
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        app.goto_time(base + chrono::Duration::milliseconds(4500));
+        assert!(matches!(app.mode, Mode::Paused));
+        assert_eq!(app.current_entry().unwrap().message, "line 5");
+
+        app.set_time_range(
+            Some(base + chrono::Duration::seconds(3)),
+            Some(base + chrono::Duration::seconds(6)),
+        );
+        assert_eq!(app.filtered_len(), 4);
+        app.set_time_range(None, None);
+        assert_eq!(app.filtered_len(), 20);
+    }
+
+    #[test]
+    fn picking_a_template_filters_to_its_lines_and_baselines_record_it() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(8))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let messages = [
+            "deadlock retry txn=41 attempt=1",
+            "cache warmed",
+            "deadlock retry txn=97 attempt=2",
+            "deadlock retry txn=98 attempt=1",
+        ];
+        let lines = messages
+            .iter()
+            .map(|message| {
+                let entry = LogEntry {
+                    message: message.to_string(),
+                    ..base_entry()
+                };
+                Ingested {
+                    at: entry.timestamp,
+                    raw: message.to_string(),
+                    entry,
+                }
+            })
+            .collect();
+        app.ingest_lines(lines);
+        app.open_template_panel();
+        app.pick_template();
+        assert!(matches!(app.input_mode(), InputMode::Normal));
+        assert_eq!(app.filtered_len(), 3);
+        assert_eq!(
+            app.filters().template.as_ref().unwrap().text,
+            "deadlock retry txn=<*> attempt=<*>"
+        );
+
+        let profile = app.build_baseline_profile();
+        assert_eq!(profile.top_templates[0].count, 3);
+        assert_eq!(profile.top_templates[1].token, "cache warmed");
+        app.clear_filters();
+        assert_eq!(app.filtered_len(), 4);
+    }
+
+    #[test]
+    fn following_a_correlation_field_cycles_and_clears() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(9))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for message in [
+            "GET /cart req=7 trace_id=t1",
+            "cache miss req=8 trace_id=t1",
+            "charged card req=7",
+            "no ids here",
+        ] {
+            app.push_log(LogEntry {
+                timestamp: app.now() - chrono::Duration::seconds(1),
+                message: message.to_string(),
+                ..base_entry()
+            });
+        }
+        app.follow_correlation();
+        assert_eq!(
+            app.filtered_len(),
+            4,
+            "the last line has no field to follow"
+        );
+
+        app.selected_from_end = 3;
+        app.follow_correlation();
+        assert_eq!(app.filters().trace.as_ref().unwrap().value, "7");
+        assert_eq!(app.filtered_len(), 2);
+        assert_eq!(app.trace_bins().unwrap().iter().filter(|b| **b).count(), 1);
+        app.push_log(LogEntry {
+            timestamp: app.now() - chrono::Duration::seconds(120),
+            message: "refund req=7".to_string(),
+            ..base_entry()
+        });
+        app.push_log(LogEntry {
+            timestamp: app.now() - chrono::Duration::seconds(240),
+            message: "refund req=9".to_string(),
+            ..base_entry()
+        });
+        assert_eq!(app.trace_bins().unwrap().iter().filter(|b| **b).count(), 2);
+
+        app.selected_from_end = 2;
+        app.follow_correlation();
+        assert_eq!(app.filters().trace.as_ref().unwrap().key, "trace_id");
+        assert_eq!(app.filtered_len(), 2);
+        app.selected_from_end = 1;
+        app.follow_correlation();
+        assert!(app.filters().trace.is_none());
+        assert_eq!(app.filtered_len(), 6);
+    }
+
+    #[test]
+    fn wrapped_rows_count_by_height_and_sideways_scroll_is_bounded() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(10))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for len in [5, 30, 12, 25] {
+            app.push_log(LogEntry {
+                message: "x".repeat(len),
+                ..base_entry()
+            });
+        }
+        let height = |row: &VisibleRow| row.entry.message.len().div_ceil(10);
+        let lens: Vec<_> = app
+            .visible_logs(6, height)
+            .iter()
+            .map(|r| r.entry.message.len())
+            .collect();
+        assert_eq!(lens, vec![12, 25]);
+        app.scroll_up(3);
+        assert_eq!(
+            app.visible_logs(2, height).len(),
+            1,
+            "a tall selected row still shows"
+        );
+
+        app.set_view_layout(ViewLayout {
+            line_end_scroll: 20,
+            ..ViewLayout::default()
+        });
+        for _ in 0..4 {
+            app.scroll_horizontal(8);
+        }
+        assert_eq!(app.h_scroll(), 20);
+        app.scroll_horizontal(-8);
+        assert_eq!(app.h_scroll(), 12);
+        app.toggle_wrap();
+        assert_eq!(app.h_scroll(), 0);
+        app.scroll_line_end();
+        assert_eq!(app.h_scroll(), 0);
+    }
+
+    #[test]
+    fn clicks_map_to_rows_levels_and_timeline_bins() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(11))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for _ in 0..5 {
+            app.push_log(base_entry());
+        }
+        let view = ViewLayout {
+            log_rows: vec![(1, 1, 2), (2, 2, 3), (4, 1, 4)],
+            log_area: Rect::new(1, 1, 40, 10),
+            timeline_area: Rect::new(1, 12, 40, 4),
+            timeline_step: 2,
+            level_chips: vec![(Level::Warn, Rect::new(10, 0, 4, 1))],
+            ..ViewLayout::default()
+        };
+        assert_eq!(view.row_at(5, 3), Some(3));
+        assert_eq!(view.row_at(0, 3), None, "border column");
+        assert_eq!(view.row_at(5, 6), None);
+        assert_eq!(view.level_at(13, 0), Some(Level::Warn));
+        assert_eq!(view.level_at(14, 0), None);
+        assert_eq!(view.timeline_bin_at(11, 13), Some(20));
+        app.set_view_layout(view);
+
+        let index = app.view().row_at(5, 2).unwrap();
+        app.select_row(index);
+        assert_eq!(app.selected_from_end(), 1);
+        assert!(matches!(app.mode, Mode::Paused));
+
+        let len = app.timeline().len();
+        app.seek_timeline(len - 3);
+        assert_eq!(app.timeline_cursor_from_end(), Some(2));
+        app.seek_timeline(usize::MAX);
+        assert_eq!(app.timeline_cursor_from_end(), Some(0));
+    }
+
+    #[test]
+    fn yanked_lines_keep_their_original_form() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(12))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let json_line = r#"{"level":"warn","msg":"charged","req":7}"#;
+        app.push_log(crate::log_entry::parse_line(json_line, Local::now()));
+        assert_eq!(app.selection_text(false).unwrap().0, json_line);
+    }
+
+    #[test]
+    fn visual_selection_spans_anchor_to_cursor_and_renders_lines_or_json() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(12))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for i in 0..6 {
+            app.push_log(LogEntry {
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        assert_eq!(app.selection_text(false).unwrap().1, 1);
+        app.scroll_up(1);
+        app.toggle_visual();
+        app.scroll_up(2);
+        assert_eq!(app.visual_range(), Some(2..=4));
+        let (text, count) = app.selection_text(false).unwrap();
+        assert_eq!(count, 3);
+        assert!(text.starts_with(&base_entry().timestamp.format("%Y").to_string()));
+        assert!(text.lines().nth(2).unwrap().ends_with("test line 4"));
+
+        let (json, _) = app.selection_text(true).unwrap();
+        let first: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+        assert_eq!(first["message"], "line 2");
+        assert_eq!(first["level"], "INFO");
+
+        app.set_filter_text(Some("line 1".to_string()));
+        assert_eq!(
+            app.visual_range(),
+            Some(0..=0),
+            "anchor clamps to remaining rows"
+        );
+        app.toggle_visual();
+        assert_eq!(app.visual_range(), None);
+    }
+
+    #[test]
+    fn export_writes_filter_matches_between_bookmarks_or_everything() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(13))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let base = app.now() - chrono::Duration::seconds(10);
+        for i in 0..6 {
+            app.push_log(LogEntry {
+                timestamp: base + chrono::Duration::seconds(i),
+                level: if i == 3 { Level::Error } else { Level::Info },
+                message: format!("line {i} req={i}"),
+                ..base_entry()
+            });
+        }
+        app.select_row(1);
+        app.add_bookmark(BookmarkSpec::default());
+        app.select_row(4);
+        app.add_bookmark(BookmarkSpec::default());
+        app.select_row(2);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.log");
+        let request = |spec: &str| format!("{} {spec}", path.display()).parse().unwrap();
+        assert_eq!(app.export(&request("--marks")).unwrap(), 3);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.lines().next().unwrap().ends_with("line 1 req=1"));
+
+        app.toggle_level(Level::Error);
+        assert_eq!(app.export(&request("--marks --format csv")).unwrap(), 2);
+        assert_eq!(app.export(&request("")).unwrap(), 5);
+        assert!(
+            app.export(&request("--bin")).is_err(),
+            "needs the timeline cursor"
+        );
+        assert_eq!(app.export(&request("--all -f jsonl")).unwrap(), 6);
+        let text = std::fs::read_to_string(&path).unwrap();
+        let last: serde_json::Value = serde_json::from_str(text.lines().last().unwrap()).unwrap();
+        assert_eq!(last["fields"]["req"], "5");
+    }
+
+    #[test]
+    fn export_all_streams_spilled_history_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(13))));
+        let mut app = App::new(ingest, 100_000, "mock".to_string(), BaselineMode::Off);
+        app.enable_spill(dir.path()).unwrap();
+        app.set_max_bytes(entry_bytes(&base_entry()) * 20);
+        for i in 0..2500 {
+            let message = if i == 0 {
+                "first only=1".to_string()
+            } else {
+                format!("line {i}")
+            };
+            app.push_log(LogEntry {
+                message,
+                ..base_entry()
+            });
+        }
+        // Page back so the newest lines are on disk as well.
+        app.scroll_up(100);
+        assert!(app.evicted_tail.entries > 0);
+
+        let path = dir.path().join("all.csv");
+        let request: ExportRequest = format!("{} --all", path.display()).parse().unwrap();
+        assert_eq!(app.export(&request).unwrap(), 2500);
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2501);
+        assert_eq!(lines[0], "timestamp,level,target,message,only");
+        assert!(lines[1].ends_with(",first only=1,1"));
+        assert!((2..2501).all(|i| lines[i].ends_with(&format!(",line {},", i - 1))));
+    }
+
+    #[test]
+    fn mark_ranges_follow_entries_within_one_second() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(13))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let ts = app.now();
+        for i in 0..6 {
+            app.push_log(LogEntry {
+                timestamp: ts,
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        app.select_row(1);
+        app.run_command("bookmark first").unwrap();
+        app.select_row(4);
+        app.run_command("bookmark second").unwrap();
+        assert_eq!(app.bookmark_at_or_before(0).map(|b| &*b.label), None);
+        assert_eq!(app.bookmark_at_or_before(3).unwrap().label, "first");
+        assert_eq!(app.bookmark_at_or_before(5).unwrap().label, "second");
+
+        app.select_row(2);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.log");
+        let request = format!("{} --marks", path.display()).parse().unwrap();
+        assert_eq!(app.export(&request).unwrap(), 3);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.lines().next().unwrap().ends_with("line 1"));
+        assert!(text.lines().last().unwrap().ends_with("line 3"));
+    }
+
+    #[test]
+    fn bookmark_prompt_and_panel_add_rename_tag_jump_and_delete() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(15))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        let base = app.now() - chrono::Duration::seconds(10);
+        for i in 0..5 {
+            app.push_log(LogEntry {
+                timestamp: base + chrono::Duration::seconds(i),
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        app.select_row(3);
+        app.begin_bookmark_edit();
+        if let InputMode::BookmarkEdit(None, buf) = app.input_mode_mut() {
+            buf.push_str("retry storm #red -- after deploy");
+        }
+        app.submit_prompt();
+        app.select_row(1);
+        app.run_command("bookmark").unwrap();
+        let labels: Vec<_> = app.bookmarks().iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["mark 1", "retry storm"]);
+        assert_eq!(app.bookmarks()[1].tag, BookmarkTag::Red);
+        assert_eq!(app.bookmarks()[1].excerpt, "line 3");
+
+        app.open_bookmark_panel();
+        assert!(matches!(app.input_mode(), InputMode::Bookmarks(0)));
+        app.cycle_bookmark_tag();
+        app.begin_bookmark_rename();
+        if let InputMode::BookmarkEdit(Some(0), buf) = app.input_mode_mut() {
+            assert_eq!(buf, "mark 1 #red");
+            buf.push_str(" -- first");
+        }
+        app.submit_prompt();
+        assert!(matches!(app.input_mode(), InputMode::Bookmarks(0)));
+        assert_eq!(app.bookmarks()[0].note.as_deref(), Some("first"));
+
+        app.move_bookmark_cursor(1);
+        app.pick_bookmark();
+        assert!(matches!(app.input_mode(), InputMode::Normal));
+        assert_eq!(app.current_entry().unwrap().message, "line 3");
+
+        app.open_bookmark_panel();
+        app.delete_bookmark();
+        app.delete_bookmark();
+        assert!(app.bookmarks().is_empty());
+        assert!(matches!(app.input_mode(), InputMode::Normal));
+
+        // Three unnamed bookmarks, the first deleted: the next one reuses its number.
+        for row in [0, 1, 2] {
+            app.select_row(row);
+            app.run_command("bookmark").unwrap();
+        }
+        app.select_row(0);
+        app.open_bookmark_panel();
+        app.delete_bookmark();
+        app.input_mode = InputMode::Normal;
+        app.run_command("bookmark").unwrap();
+        let labels: Vec<_> = app.bookmarks().iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["mark 1", "mark 2", "mark 3"]);
+    }
+
+    #[test]
+    fn session_state_round_trips_filters_bookmarks_and_the_paused_line() {
+        let lines: Vec<LogEntry> = (0..6)
+            .map(|i| LogEntry {
+                timestamp: Local::now() - chrono::Duration::seconds(10 - i),
+                level: if i % 2 == 0 { Level::Warn } else { Level::Info },
+                message: format!("line {i}"),
+                ..base_entry()
+            })
+            .collect();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(16))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for entry in lines.clone() {
+            app.push_log(entry);
+        }
+        app.run_command("filter level>=warn").unwrap();
+        app.select_row(0);
+        app.run_command("bookmark start #green").unwrap();
+        app.select_row(1);
+        let state = app.session_state();
+        assert_eq!(
+            state.filter.as_ref().map(|q| q.levels),
+            Some([false, true, true])
+        );
+        assert_eq!(state.selected.as_ref().unwrap().excerpt, "line 2");
+
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(16))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        app.restore_session(state, false);
+        assert_eq!(app.bookmarks()[0].tag, BookmarkTag::Green);
+        let now = Local::now();
+        app.ingest_lines(
+            lines
+                .into_iter()
+                .map(|entry| Ingested {
+                    at: now,
+                    raw: entry.to_line(),
+                    entry,
+                })
+                .collect(),
+        );
+        // Paused on the restored line; the lines after it wait in the paused buffer.
+        assert_eq!(app.mode, Mode::Paused);
+        assert_eq!(app.filtered_len(), 2);
+        assert_eq!(app.current_entry().unwrap().message, "line 2");
+    }
+
+    #[test]
+    fn bookmarks_and_selection_keep_to_one_line_among_repeats() {
+        let at = Local::now();
+        let lines: Vec<LogEntry> = [
+            Level::Info,
+            Level::Warn,
+            Level::Info,
+            Level::Warn,
+            Level::Info,
+        ]
+        .into_iter()
+        .map(|level| LogEntry {
+            timestamp: at,
+            level,
+            message: "retrying upstream".to_string(),
+            ..base_entry()
+        })
+        .collect();
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(17))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for entry in lines.clone() {
+            app.push_log(entry);
+        }
+        app.select_row(3);
+        app.run_command("bookmark second warn").unwrap();
+        assert_eq!(app.bookmarks()[0].seq, Some(3));
+        assert_eq!(app.bookmarks()[0].repeat, 3);
+
+        app.run_command("filter level>=warn").unwrap();
+        assert_eq!(app.selected_seq(), Some(3));
+        app.clear_filters();
+        assert_eq!(app.selected_seq(), Some(3));
+        app.select_row(0);
+        app.jump_bookmark(1);
+        assert_eq!(app.selected_seq(), Some(3));
+        app.run_command("filter level=info").unwrap();
+        app.jump_bookmark(1);
+        assert_eq!(app.selected_seq(), Some(4));
+        assert!(app.last_notice.as_deref().unwrap().contains("hidden"));
+
+        let state = app.session_state();
+        assert_eq!(state.bookmarks[0].anchor.repeat, 3);
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(17))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        app.restore_session(
+            SessionState {
+                filter: None,
+                selected: None,
+                ..state
+            },
+            false,
+        );
+        for entry in lines {
+            app.push_log(entry);
+        }
+        assert_eq!(app.bookmarks()[0].seq, Some(3));
+    }
+
+    #[test]
+    fn bookmarks_out_of_memory_are_reported() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(18))));
+        let mut app = App::new(ingest, 2, "mock".to_string(), BaselineMode::Off);
+        let first = base_entry();
+        app.push_log(first.clone());
+        app.select_row(0);
+        app.run_command("bookmark deploy").unwrap();
+        let state = app.session_state();
+        app.push_log(base_entry());
+        app.push_log(base_entry());
+        assert!(app.last_notice.as_deref().unwrap().contains("\"deploy\""));
+
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(18))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        app.restore_session(state, true);
+        let later = LogEntry {
+            timestamp: first.timestamp + chrono::Duration::seconds(5),
+            ..base_entry()
+        };
+        app.ingest_lines(vec![Ingested {
+            at: later.timestamp,
+            raw: later.to_line(),
+            entry: later,
+        }]);
+        assert!(app
+            .last_notice
+            .as_deref()
+            .unwrap()
+            .starts_with("1 bookmark(s) are older"));
+    }
+
+    #[test]
+    fn command_line_runs_commands_and_completes_words() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(14))));
+        let mut app = App::new(ingest, 100, "mock".to_string(), BaselineMode::Off);
+        for (i, level) in [Level::Info, Level::Warn, Level::Error]
+            .into_iter()
+            .enumerate()
+        {
+            app.push_log(LogEntry {
+                level,
+                message: format!("line {i}"),
+                ..base_entry()
+            });
+        }
+        app.run_command(":filter level>=warn").unwrap();
+        assert_eq!(app.filtered_len(), 2);
+        app.run_command("bookmark deploy").unwrap();
+        assert_eq!(app.bookmarks()[0].label, "deploy");
+        app.run_command("set wrap=on").unwrap();
+        assert!(app.wrap_enabled());
+        app.run_command("filter").unwrap();
+        assert_eq!(app.filtered_len(), 3);
+        assert!(app.run_command("source add /nonexistent").is_err());
+        assert!(app.run_command("frobnicate").is_err());
+
+        app.begin_command_edit("se");
+        app.complete_command();
+        assert!(matches!(app.input_mode(), InputMode::Command(buf) if buf == "set "));
+        app.begin_command_edit("set max");
+        app.complete_command();
+        assert!(matches!(app.input_mode(), InputMode::Command(buf) if buf == "set max_"));
+        assert_eq!(app.last_notice().unwrap(), "max_lines=  max_bytes=");
+        app.begin_command_edit("set wrap=maybe");
+        app.submit_prompt();
+        assert!(app.last_notice().unwrap().contains("on or off"));
+        assert!(matches!(app.input_mode(), InputMode::Normal));
+    }
+
+    #[test]
+    fn byte_budget_evicts_before_line_limit() {
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(6))));
+        let mut app = App::new(ingest, 1000, "mock".to_string(), BaselineMode::Off);
+        let entry = LogEntry {
+            message: "x".repeat(100),
+            ..base_entry()
+        };
+        app.set_max_bytes(entry_bytes(&entry) * 4);
+        for _ in 0..10 {
+            app.push_log(entry.clone());
+        }
+        assert_eq!(app.total_logs(), 4);
+        assert!(app.memory_bytes() <= entry_bytes(&entry) * 4);
+    }
+
     #[test]
     fn token_counts_are_pruned() {
         let mut map = HashMap::new();
@@ -11,6 +11,9 @@ pub struct BaselineProfile {
     pub window_secs: u64,
     pub bins: Vec<Bin>,
     pub top_tokens: Vec<TokenCount>,
+    /// Most frequent message templates; `token` holds the template text.
+    #[serde(default)]
+    pub top_templates: Vec<TokenCount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +28,7 @@ impl BaselineProfile {
         window_secs: u64,
         bins: Vec<Bin>,
         top_tokens: Vec<TokenCount>,
+        top_templates: Vec<TokenCount>,
     ) -> Self {
         Self {
             version: 1,
@@ -32,6 +36,7 @@ impl BaselineProfile {
             window_secs,
             bins,
             top_tokens,
+            top_templates,
         }
     }
 
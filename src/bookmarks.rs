@@ -0,0 +1,204 @@
+use std::{path::PathBuf, str::FromStr};
+
+use chrono::{DateTime, Local};
+
+use crate::log_entry::LogEntry;
+
+/// Characters of the bookmarked line kept for the bookmark list.
+pub const EXCERPT_CHARS: usize = 60;
+
+/// The start of `message` that bookmarks and saved positions keep.
+pub fn excerpt(message: &str) -> String {
+    message.chars().take(EXCERPT_CHARS).collect()
+}
+
+/// Colour tag on a bookmark; each tag has its own timeline marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookmarkTag {
+    #[default]
+    Plain,
+    Red,
+    Yellow,
+    Green,
+    Blue,
+}
+
+impl BookmarkTag {
+    pub const ALL: [BookmarkTag; 5] = [
+        BookmarkTag::Plain,
+        BookmarkTag::Red,
+        BookmarkTag::Yellow,
+        BookmarkTag::Green,
+        BookmarkTag::Blue,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BookmarkTag::Plain => "plain",
+            BookmarkTag::Red => "red",
+            BookmarkTag::Yellow => "yellow",
+            BookmarkTag::Green => "green",
+            BookmarkTag::Blue => "blue",
+        }
+    }
+
+    /// Character drawn on the timeline marker row.
+    pub fn marker(self) -> char {
+        match self {
+            BookmarkTag::Plain => '*',
+            BookmarkTag::Red => 'R',
+            BookmarkTag::Yellow => 'Y',
+            BookmarkTag::Green => 'G',
+            BookmarkTag::Blue => 'B',
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|tag| *tag == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for BookmarkTag {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|tag| tag.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|tag| tag.name()).collect();
+                format!("unknown tag #{name} ({})", names.join(", "))
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    /// Sequence number of the bookmarked entry; `None` until a restored
+    /// bookmark's line has been read back.
+    pub seq: Option<u64>,
+    pub timestamp: DateTime<Local>,
+    pub label: String,
+    pub note: Option<String>,
+    pub tag: BookmarkTag,
+    /// Start of the bookmarked line, so the list still reads after it is pruned.
+    pub excerpt: String,
+    /// Earlier lines with the same timestamp and excerpt, telling repeats apart.
+    pub repeat: usize,
+    /// Rotated part of the log the line was in when the bookmark was restored.
+    pub part: Option<PathBuf>,
+}
+
+impl Bookmark {
+    pub fn new(seq: u64, entry: &LogEntry, repeat: usize, label: String) -> Self {
+        Self {
+            seq: Some(seq),
+            timestamp: entry.timestamp,
+            label,
+            note: None,
+            tag: BookmarkTag::Plain,
+            excerpt: excerpt(&entry.message),
+            repeat,
+            part: None,
+        }
+    }
+
+    /// Whether `entry` could be this bookmark's line; `repeat` picks among matches.
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        entry.timestamp == self.timestamp && excerpt(&entry.message) == self.excerpt
+    }
+
+    /// Applies what was typed in the bookmark prompt; an empty label keeps the old one.
+    pub fn apply(&mut self, spec: BookmarkSpec) {
+        if let Some(label) = spec.label {
+            self.label = label;
+        }
+        self.note = spec.note;
+        if let Some(tag) = spec.tag {
+            self.tag = tag;
+        }
+    }
+
+    /// The prompt text that recreates this bookmark's label, tag and note.
+    pub fn spec(&self) -> String {
+        let mut spec = self.label.clone();
+        if self.tag != BookmarkTag::Plain {
+            spec.push_str(&format!(" #{}", self.tag.name()));
+        }
+        if let Some(note) = &self.note {
+            spec.push_str(&format!(" -- {note}"));
+        }
+        spec
+    }
+}
+
+/// `LABEL [#tag] [-- NOTE]`, as typed after `b` or `:bookmark`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookmarkSpec {
+    pub label: Option<String>,
+    pub note: Option<String>,
+    pub tag: Option<BookmarkTag>,
+}
+
+impl FromStr for BookmarkSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (head, note) = match spec.split_once("--") {
+            Some((head, note)) => (head, Some(note.trim())),
+            None => (spec, None),
+        };
+        let mut tag = None;
+        let mut label = Vec::new();
+        for word in head.split_whitespace() {
+            match word.strip_prefix('#') {
+                Some(name) if !name.is_empty() => tag = Some(name.parse()?),
+                _ => label.push(word),
+            }
+        }
+        let nonempty = |text: String| Some(text).filter(|t| !t.is_empty());
+        Ok(Self {
+            label: nonempty(label.join(" ")),
+            note: note.and_then(|note| nonempty(note.to_string())),
+            tag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::Level;
+
+    #[test]
+    fn specs_parse_labels_tags_and_notes_and_round_trip() {
+        let spec: BookmarkSpec = "deploy started #red -- v2 rollout, watch db"
+            .parse()
+            .unwrap();
+        assert_eq!(spec.label.as_deref(), Some("deploy started"));
+        assert_eq!(spec.tag, Some(BookmarkTag::Red));
+        assert_eq!(spec.note.as_deref(), Some("v2 rollout, watch db"));
+        assert_eq!(
+            " ".parse::<BookmarkSpec>().unwrap(),
+            BookmarkSpec::default()
+        );
+        assert!("x #purple".parse::<BookmarkSpec>().is_err());
+
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: Level::Info,
+            target: "api".to_string(),
+            message: "x".repeat(100),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let mut bookmark = Bookmark::new(7, &entry, 0, "mark 1".to_string());
+        assert_eq!(bookmark.excerpt.len(), EXCERPT_CHARS);
+        assert!(bookmark.matches(&entry));
+        bookmark.apply("#blue -- retry storm".parse().unwrap());
+        assert_eq!(bookmark.label, "mark 1");
+        assert_eq!(bookmark.spec(), "mark 1 #blue -- retry storm");
+        assert_eq!(BookmarkTag::Blue.next(), BookmarkTag::Plain);
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+
+/// Larger payloads are written to a file instead; many terminals drop big OSC 52 writes.
+const OSC52_MAX_BYTES: usize = 100_000;
+
+/// Where yanked lines go (`clipboard` in `config.toml`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardMode {
+    /// The terminal's clipboard via the OSC 52 escape sequence, which also works over SSH.
+    #[default]
+    Osc52,
+    /// Always a temp file, for terminals without OSC 52.
+    File,
+}
+
+/// Where a copy ended up.
+#[derive(Debug)]
+pub enum Copied {
+    Clipboard,
+    File(PathBuf),
+}
+
+/// Copies `text` per `mode`, falling back to a temp file when OSC 52 is not an option.
+pub fn copy(text: &str, mode: ClipboardMode, out: &mut impl Write) -> anyhow::Result<Copied> {
+    if mode == ClipboardMode::Osc52 {
+        let sequence = osc52(text);
+        if sequence.len() <= OSC52_MAX_BYTES
+            && out
+                .write_all(sequence.as_bytes())
+                .and_then(|_| out.flush())
+                .is_ok()
+        {
+            return Ok(Copied::Clipboard);
+        }
+    }
+    let (path, mut file) = create_temp_file()?;
+    file.write_all(text.as_bytes())
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(Copied::File(path))
+}
+
+/// Creates a fresh, private file in the temp dir. `create_new` refuses an existing
+/// path, so a symlink planted at a guessed name is never followed.
+fn create_temp_file() -> anyhow::Result<(PathBuf, File)> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    for attempt in 0..100 {
+        let path = std::env::temp_dir().join(format!(
+            "ltm-selection-{}-{stamp}-{attempt}.log",
+            std::process::id()
+        ));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err).with_context(|| format!("creating {}", path.display())),
+        }
+    }
+    anyhow::bail!("no free temp file name for the selection")
+}
+
+/// The OSC 52 "set clipboard" sequence, wrapped for tmux passthrough when inside tmux.
+pub fn osc52(text: &str) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;\x1b{sequence}\x1b\\")
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_and_file_mode_writes_a_temp_file() {
+        let mut out = Vec::new();
+        let copied = copy("a\nb", ClipboardMode::Osc52, &mut out).unwrap();
+        assert!(matches!(copied, Copied::Clipboard));
+        let written = String::from_utf8(out).unwrap();
+        assert!(written.contains("\x1b]52;c;YQpi\x07"));
+
+        let mut out = Vec::new();
+        let Copied::File(path) = copy("a\nb", ClipboardMode::File, &mut out).unwrap() else {
+            panic!("file mode must write a file");
+        };
+        assert!(out.is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb");
+        let Copied::File(second) = copy("c", ClipboardMode::File, &mut out).unwrap() else {
+            panic!("file mode must write a file");
+        };
+        assert_ne!(path, second, "an existing file is never reused");
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use crate::log_entry::{Level, LogEntry};
+
+/// What makes two consecutive rows "the same line" in the collapsed view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupKey {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+impl DedupKey {
+    pub fn of(entry: &LogEntry) -> Self {
+        Self {
+            level: entry.level,
+            target: entry.target.clone(),
+            message: normalize_message(&entry.message),
+        }
+    }
+}
+
+/// A run of consecutive matching entries shown as one row, keyed in `App` by the
+/// sequence number of its first member.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub last: u64,
+    pub count: usize,
+    pub expanded: bool,
+}
+
+/// Masks every alphanumeric word containing a digit (numbers, ids, hex, UUID
+/// parts) or made of 8+ hex letters with `#`, so `retry 3 of req-9f2c` and
+/// `retry 4 of req-11ab` compare equal.
+pub fn normalize_message(message: &str) -> String {
+    mask_words(message, "#")
+}
+
+/// Letter-only words this long made of hex digits are masked like ids; shorter
+/// ones (`face`, `added`) are more likely to be real words.
+const HEX_WORD_LEN: usize = 8;
+
+/// Replaces each alphanumeric word that contains a digit, or looks like hex and is
+/// at least `HEX_WORD_LEN` long (`deadbeef`), with `mask`.
+pub fn mask_words(message: &str, mask: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        let hex = word.len() >= HEX_WORD_LEN && word.chars().all(|c| c.is_ascii_hexdigit());
+        if hex || word.chars().any(|c| c.is_ascii_digit()) {
+            out.push_str(mask);
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+    for c in message.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_ids_and_uuids_are_masked() {
+        assert_eq!(
+            normalize_message("retry 3 of req-9f2c after 250ms"),
+            normalize_message("retry 14 of req-11ab after 1000ms")
+        );
+        assert_eq!(
+            normalize_message("job 550e8400-e29b-41d4-a716-446655440000 done"),
+            "job #-#-#-#-# done"
+        );
+        assert_eq!(
+            normalize_message("job abcdefab-e29b-41d4-a716-446655440000 id deadbeef added"),
+            "job #-#-#-#-# id # added"
+        );
+        assert_ne!(
+            normalize_message("connection reset"),
+            normalize_message("connection refused")
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Local};
+
+use crate::{correlate::fields, log_entry::LogEntry};
+
+/// Layouts offered by `L` when none are configured.
+pub const DEFAULT_LAYOUTS: &[&str] = &[
+    "time,level,target",
+    "ms,rel,level,target",
+    "date,level,source,target",
+];
+/// Adaptive columns never grow past this many characters.
+const MAX_COLUMN_WIDTH: usize = 24;
+
+/// One column in front of the message in the log list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    /// `HH:MM:SS`
+    Time,
+    /// `HH:MM:SS.mmm`
+    Millis,
+    /// `YYYY-MM-DD HH:MM:SS`
+    Date,
+    /// Time since the row above, e.g. `+1.2s`.
+    Relative,
+    /// Time since the latest bookmark at or before the row.
+    SinceBookmark,
+    Level,
+    Target,
+    Source,
+    /// Value of a `key=value` or JSON field in the message.
+    Field(String),
+}
+
+/// A comma-separated column list such as `ms,rel,level,field:req`; the message
+/// always follows the last column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnLayout {
+    pub columns: Vec<Column>,
+}
+
+impl FromStr for ColumnLayout {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut columns = Vec::new();
+        for name in spec.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            columns.push(match name {
+                "time" => Column::Time,
+                "ms" => Column::Millis,
+                "date" => Column::Date,
+                "rel" => Column::Relative,
+                "mark" => Column::SinceBookmark,
+                "level" => Column::Level,
+                "target" => Column::Target,
+                "source" => Column::Source,
+                other => match other.strip_prefix("field:") {
+                    Some(field) if !field.is_empty() => Column::Field(field.to_string()),
+                    _ => return Err(format!("unknown column {other:?}")),
+                },
+            });
+        }
+        Ok(Self { columns })
+    }
+}
+
+impl fmt::Display for ColumnLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Time => "time".to_string(),
+                Column::Millis => "ms".to_string(),
+                Column::Date => "date".to_string(),
+                Column::Relative => "rel".to_string(),
+                Column::SinceBookmark => "mark".to_string(),
+                Column::Level => "level".to_string(),
+                Column::Target => "target".to_string(),
+                Column::Source => "source".to_string(),
+                Column::Field(name) => format!("field:{name}"),
+            })
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl Default for ColumnLayout {
+    fn default() -> Self {
+        DEFAULT_LAYOUTS[0].parse().expect("built-in layout parses")
+    }
+}
+
+/// What a row needs besides its entry to fill in every column.
+pub struct CellContext<'a> {
+    pub entry: &'a LogEntry,
+    pub previous: Option<DateTime<Local>>,
+    pub bookmark: Option<DateTime<Local>>,
+    pub source: &'a str,
+}
+
+impl Column {
+    pub fn text(&self, cx: &CellContext) -> String {
+        let ts = cx.entry.timestamp;
+        match self {
+            Column::Time => ts.format("%H:%M:%S").to_string(),
+            Column::Millis => ts.format("%H:%M:%S%.3f").to_string(),
+            Column::Date => ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Column::Relative => cx.previous.map_or_else(String::new, |prev| since(prev, ts)),
+            Column::SinceBookmark => cx.bookmark.map_or_else(String::new, |mark| since(mark, ts)),
+            Column::Level => cx.entry.level.label().to_string(),
+            Column::Target => cx.entry.target.clone(),
+            Column::Source => cx.source.to_string(),
+            Column::Field(name) => fields(cx.entry)
+                .into_iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Widest cell among `cells`, capped for the free-form columns.
+    pub fn width<'a>(&self, cells: impl Iterator<Item = &'a str>) -> usize {
+        let widest = cells.map(|c| c.chars().count()).max().unwrap_or(0);
+        match self {
+            Column::Level => 5,
+            Column::Target | Column::Source | Column::Field(_) => widest.min(MAX_COLUMN_WIDTH),
+            _ => widest,
+        }
+    }
+}
+
+/// `+350ms`, `+1.2s`, `+4m05s`, `+2h10m`; negative when `to` is before `from`.
+pub fn since(from: DateTime<Local>, to: DateTime<Local>) -> String {
+    let ms = (to - from).num_milliseconds();
+    let sign = if ms < 0 { '-' } else { '+' };
+    let ms = ms.unsigned_abs();
+    let text = match ms {
+        0..=999 => format!("{ms}ms"),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        60_000..=3_599_999 => format!("{}m{:02}s", ms / 60_000, ms / 1000 % 60),
+        _ => format!("{}h{:02}m", ms / 3_600_000, ms / 60_000 % 60),
+    };
+    format!("{sign}{text}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::Level;
+
+    #[test]
+    fn layouts_parse_and_cells_render() {
+        let layout: ColumnLayout = "ms, rel,level,field:req".parse().unwrap();
+        assert_eq!(layout.to_string(), "ms,rel,level,field:req");
+        assert!("time,colour".parse::<ColumnLayout>().is_err());
+        assert!("field:".parse::<ColumnLayout>().is_err());
+
+        let base = Local::now();
+        let entry = LogEntry {
+            timestamp: base + chrono::Duration::milliseconds(1250),
+            level: Level::Warn,
+            target: "api".to_string(),
+            message: "GET /cart req=4821".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let cx = CellContext {
+            entry: &entry,
+            previous: Some(base),
+            bookmark: None,
+            source: "mock feed",
+        };
+        let cells: Vec<_> = layout.columns.iter().map(|c| c.text(&cx)).collect();
+        assert_eq!(cells[1..], ["+1.2s", "WARN", "4821"]);
+        assert_eq!(Column::SinceBookmark.text(&cx), "");
+        assert_eq!(since(entry.timestamp, base), "-1.2s");
+        assert_eq!(
+            since(base, base + chrono::Duration::seconds(3725)),
+            "+1h02m"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::{fmt, fs, path::PathBuf};
+
+use crate::{
+    bookmarks::BookmarkSpec, config::ByteSize, export::ExportRequest, saved_filters::FilterQuery,
+};
+
+/// Commands understood by the `:` prompt and the `startup` script, with their usage.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("goto", "goto TIME"),
+    ("range", "range SINCE..UNTIL"),
+    ("filter", "filter QUERY"),
+    (
+        "export",
+        "export PATH [--format raw|jsonl|csv] [--marks|--bin|--all]",
+    ),
+    (
+        "bookmark",
+        "bookmark [LABEL] [#red|yellow|green|blue] [-- NOTE]",
+    ),
+    ("set", "set KEY=VALUE"),
+    ("source", "source add PATH"),
+];
+/// Keys accepted by `:set`.
+pub const SETTINGS: &[&str] = &["max_lines", "max_bytes", "context", "wrap", "collapse"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Time spec as typed; resolved against the app clock when run.
+    Goto(String),
+    Range(String),
+    /// Filter query in preset syntax; an empty query clears the filters.
+    Filter(FilterQuery),
+    Export(ExportRequest),
+    Bookmark(BookmarkSpec),
+    Set(Setting),
+    SourceAdd(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    MaxLines(usize),
+    MaxBytes(usize),
+    Context(usize),
+    Wrap(bool),
+    Collapse(bool),
+}
+
+impl fmt::Display for Setting {
+    /// Writes the setting as `key=value`, the form `:set` accepts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let switch = |on: bool| if on { "on" } else { "off" };
+        match self {
+            Setting::MaxLines(lines) => write!(f, "max_lines={lines}"),
+            Setting::MaxBytes(bytes) => write!(f, "max_bytes={bytes}"),
+            Setting::Context(lines) => write!(f, "context={lines}"),
+            Setting::Wrap(on) => write!(f, "wrap={}", switch(*on)),
+            Setting::Collapse(on) => write!(f, "collapse={}", switch(*on)),
+        }
+    }
+}
+
+impl Command {
+    /// Parses one command line; a leading `:` and unambiguous name prefixes are accepted.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim().trim_start_matches(':').trim_start();
+        let (name, args) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, args)| (name, args.trim()));
+        if name.is_empty() {
+            return Err("empty command".to_string());
+        }
+        let (name, usage) = resolve(name)?;
+        let usage = || format!("usage: {usage}");
+        Ok(match name {
+            "goto" if !args.is_empty() => Command::Goto(args.to_string()),
+            "range" => Command::Range(args.to_string()),
+            "filter" => Command::Filter(args.parse()?),
+            "export" => Command::Export(args.parse()?),
+            "bookmark" => Command::Bookmark(args.parse()?),
+            "set" => Command::Set(parse_setting(args).map_err(|err| match err {
+                Some(err) => err,
+                None => usage(),
+            })?),
+            "source" => match args.split_once(char::is_whitespace) {
+                Some(("add", path)) => Command::SourceAdd(PathBuf::from(path.trim())),
+                _ => return Err(usage()),
+            },
+            _ => return Err(usage()),
+        })
+    }
+}
+
+fn resolve(name: &str) -> Result<(&'static str, &'static str), String> {
+    if let Some(&(full, usage)) = COMMANDS.iter().find(|(full, _)| *full == name) {
+        return Ok((full, usage));
+    }
+    let matches: Vec<_> = COMMANDS
+        .iter()
+        .filter(|(full, _)| full.starts_with(name))
+        .collect();
+    match matches.as_slice() {
+        [(full, usage)] => Ok((full, usage)),
+        [] => Err(format!("unknown command {name:?}")),
+        many => Err(format!(
+            "ambiguous command {name:?}: {}",
+            many.iter()
+                .map(|(full, _)| *full)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// `None` means the arguments did not have the `KEY=VALUE` shape at all.
+fn parse_setting(args: &str) -> Result<Setting, Option<String>> {
+    let (key, value) = args.split_once('=').ok_or(None)?;
+    let (key, value) = (key.trim(), value.trim());
+    let number = || {
+        value
+            .parse::<usize>()
+            .map_err(|_| Some(format!("{key} needs a number, got {value:?}")))
+    };
+    let switch = || match value {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(Some(format!("{key} needs on or off, got {value:?}"))),
+    };
+    Ok(match key {
+        "max_lines" => Setting::MaxLines(number()?.max(1)),
+        "max_bytes" => Setting::MaxBytes(value.parse::<ByteSize>().map_err(Some)?.0),
+        "context" => Setting::Context(number()?),
+        "wrap" => Setting::Wrap(switch()?),
+        "collapse" => Setting::Collapse(switch()?),
+        other => {
+            return Err(Some(format!(
+                "unknown setting {other:?} ({})",
+                SETTINGS.join(", ")
+            )))
+        }
+    })
+}
+
+/// Candidates for the last word of a partly typed command line: command names,
+/// `:set` keys, the `source` subcommand, or file paths for `export` and `source add`.
+pub fn complete(line: &str) -> Vec<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let partial = if line.ends_with(char::is_whitespace) || line.is_empty() {
+        ""
+    } else {
+        words.last().copied().unwrap_or("")
+    };
+    let position = if partial.is_empty() {
+        words.len()
+    } else {
+        words.len() - 1
+    };
+    let starting = |options: &mut dyn Iterator<Item = String>| -> Vec<String> {
+        options.filter(|o| o.starts_with(partial)).collect()
+    };
+    if position == 0 {
+        return starting(&mut COMMANDS.iter().map(|(name, _)| name.to_string()));
+    }
+    let Ok((command, _)) = resolve(words[0]) else {
+        return Vec::new();
+    };
+    match (command, position) {
+        ("set", 1) => starting(&mut SETTINGS.iter().map(|key| format!("{key}="))),
+        ("source", 1) => starting(&mut std::iter::once("add".to_string())),
+        ("source", 2) => complete_path(partial),
+        ("export", _) if !partial.starts_with('-') => complete_path(partial),
+        _ => Vec::new(),
+    }
+}
+
+fn complete_path(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(slash) => (&partial[..=slash], &partial[slash + 1..]),
+        None => ("", partial),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    let mut found: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (prefix.is_empty() && name.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{name}{slash}"))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Longest prefix shared by all `candidates`.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for other in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(other.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((idx, a), _)| idx + a.len_utf8())
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::ExportScope;
+
+    #[test]
+    fn commands_parse_with_prefixes_and_complete() {
+        assert_eq!(
+            Command::parse(":go -5m").unwrap(),
+            Command::Goto("-5m".to_string())
+        );
+        assert_eq!(
+            Command::parse("bookmark deploy started").unwrap(),
+            Command::Bookmark(BookmarkSpec {
+                label: Some("deploy started".to_string()),
+                ..BookmarkSpec::default()
+            })
+        );
+        assert_eq!(
+            Command::parse("set max_bytes=2M").unwrap(),
+            Command::Set(Setting::MaxBytes(2 * 1024 * 1024))
+        );
+        let Command::Export(request) = Command::parse("e out.csv --all").unwrap() else {
+            panic!("export expected");
+        };
+        assert_eq!(request.scope, ExportScope::All);
+        let Command::Set(setting) = Command::parse(":set wrap = yes").unwrap() else {
+            panic!("set expected");
+        };
+        assert_eq!(setting.to_string(), "wrap=on");
+        assert!(Command::parse("set colour=red").is_err());
+        assert!(Command::parse("s wrap=on")
+            .unwrap_err()
+            .contains("ambiguous"));
+        assert!(Command::parse("goto").unwrap_err().starts_with("usage"));
+
+        assert_eq!(complete("f"), vec!["filter"]);
+        assert_eq!(complete("set max"), vec!["max_lines=", "max_bytes="]);
+        assert_eq!(common_prefix(&complete("set max")), "max_");
+        assert_eq!(complete("source "), vec!["add"]);
+        assert!(complete("export --f").is_empty());
+    }
+}
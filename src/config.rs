@@ -1,14 +1,39 @@
-use std::{fs, path::PathBuf, time::Duration};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
 use clap::Parser;
 use serde::Deserialize;
 
+use crate::{
+    clipboard::ClipboardMode,
+    columns::{ColumnLayout, DEFAULT_LAYOUTS},
+    correlate::DEFAULT_CORRELATION_FIELDS,
+    highlight::{HighlightSpec, Highlighter},
+    keymap::Keymap,
+    queue::DropPolicy,
+    saved_filters::{Preset, PresetSpec},
+    theme::Theme,
+};
+
 pub const TICK_RATE: Duration = Duration::from_millis(200);
 pub const DEFAULT_MAX_LINES: usize = 1200;
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
 pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(20 * 60);
 pub const TIMELINE_BINS: usize = 80;
 pub const TIMELINE_WINDOW: Duration = Duration::from_secs(20 * 60);
 pub const TAIL_SLEEP: Duration = Duration::from_millis(150);
+pub const DRAIN_BATCH_LINES: usize = 5000;
+pub const DEFAULT_QUEUE_CAPACITY: usize = 20_000;
+/// Characters moved per `h`/`l` press.
+pub const H_SCROLL_STEP: isize = 8;
+/// Lines moved per mouse wheel notch.
+pub const MOUSE_SCROLL_LINES: usize = 3;
 
 #[derive(Parser, Debug)]
 #[command(name = "log-time-machine")]
@@ -25,6 +50,14 @@ pub struct Args {
     #[arg(long)]
     pub max_lines: Option<usize>,
 
+    /// Memory budget for retained lines, e.g. 64M or 1G
+    #[arg(long, value_name = "SIZE")]
+    pub max_bytes: Option<ByteSize>,
+
+    /// Spill evicted lines to disk under DIR so older history can be paged back in
+    #[arg(long, value_name = "DIR")]
+    pub spill_dir: Option<PathBuf>,
+
     /// Record a baseline profile to this file on exit (incompatible with --baseline-compare)
     #[arg(long, value_name = "FILE", conflicts_with = "baseline_compare")]
     pub baseline_record: Option<PathBuf>,
@@ -32,19 +65,87 @@ pub struct Args {
     /// Compare against a baseline profile file (incompatible with --baseline-record)
     #[arg(long, value_name = "FILE", conflicts_with = "baseline_record")]
     pub baseline_compare: Option<PathBuf>,
+
+    /// Record every raw input line with its arrival time (gzip if FILE ends in .gz)
+    #[arg(long, value_name = "FILE")]
+    pub tee: Option<PathBuf>,
+
+    /// Replay a session recorded with --tee instead of reading a live source
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["file", "stdin"])]
+    pub replay: Option<PathBuf>,
+
+    /// Drive the mock feed from a scenario file (TOML)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["file", "stdin", "replay"])]
+    pub scenario: Option<PathBuf>,
+
+    /// Seed for the mock feed (overrides the scenario's seed)
+    #[arg(long, conflicts_with_all = ["file", "stdin", "replay"])]
+    pub seed: Option<u64>,
+
+    /// Print the mock feed to stdout as plain log lines instead of opening the viewer
+    #[arg(long, conflicts_with_all = ["file", "stdin", "replay"])]
+    pub emit: bool,
+
+    /// Lines parsed ahead of the UI before the drop policy applies
+    #[arg(long, value_name = "LINES")]
+    pub queue_capacity: Option<usize>,
+
+    /// What to do when the ingest queue is full
+    #[arg(long, value_enum)]
+    pub drop_policy: Option<DropPolicy>,
+
+    /// Only show entries at or after this time (12:03:10, RFC 3339, or relative like -5m)
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+
+    /// Only show entries at or before this time (same forms as --since)
+    #[arg(long, value_name = "TIME")]
+    pub until: Option<String>,
+
+    /// Show NUM lines after each filter match
+    #[arg(short = 'A', long = "after-context", value_name = "NUM")]
+    pub after_context: Option<usize>,
+
+    /// Show NUM lines before each filter match
+    #[arg(short = 'B', long = "before-context", value_name = "NUM")]
+    pub before_context: Option<usize>,
+
+    /// Show NUM lines before and after each filter match
+    #[arg(short = 'C', long = "context", value_name = "NUM")]
+    pub context: Option<usize>,
+
+    /// Columns before the message, e.g. `ms,rel,level,target,field:req`; repeat to
+    /// offer several layouts (cycled with L)
+    #[arg(long = "columns", value_name = "SPEC")]
+    pub columns: Vec<ColumnLayout>,
+
+    /// Open the file without restoring (or saving) its bookmarks, filters and position
+    #[arg(long)]
+    pub fresh: bool,
 }
 
 #[derive(Clone)]
 pub enum SourceConfig {
-    Mock,
-    File { path: PathBuf, start: TailStart },
+    Mock {
+        scenario: Option<PathBuf>,
+        seed: Option<u64>,
+    },
+    File {
+        path: PathBuf,
+        start: TailStart,
+        /// Rotated parts read before `path`, oldest first, each from a byte offset.
+        preload: Vec<(PathBuf, u64)>,
+    },
     Stdin,
+    Replay(PathBuf),
 }
 
 #[derive(Clone, Copy)]
 pub enum TailStart {
     Beginning,
     End,
+    /// The first whole line at or after this byte offset.
+    Offset(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -57,16 +158,216 @@ pub enum BaselineMode {
 impl SourceConfig {
     pub fn label(&self) -> String {
         match self {
-            SourceConfig::Mock => "mock feed".to_string(),
+            SourceConfig::Mock { scenario: None, .. } => "mock feed".to_string(),
+            SourceConfig::Mock {
+                scenario: Some(path),
+                ..
+            } => format!("mock: {}", path.display()),
             SourceConfig::Stdin => "stdin".to_string(),
             SourceConfig::File { path, .. } => format!("file: {} (live tail)", path.display()),
+            SourceConfig::Replay(path) => format!("replay: {}", path.display()),
+        }
+    }
+}
+
+/// A byte count written as a plain number or with a `k`/`M`/`G` suffix (powers of 1024).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub usize);
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let spec = spec.trim();
+        let split = spec
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(spec.len());
+        let value: usize = spec[..split]
+            .parse()
+            .map_err(|_| format!("invalid size {spec:?}"))?;
+        let unit = match spec[split..].trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1024,
+            "m" | "mb" | "mib" => 1024 * 1024,
+            "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+            other => return Err(format!("unknown size unit {other:?}")),
+        };
+        value
+            .checked_mul(unit)
+            .map(ByteSize)
+            .ok_or_else(|| format!("size {spec:?} is too large"))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(usize),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(n) => Ok(ByteSize(n)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Parses durations such as `250ms`, `30s`, `5m`, `1h30m` or `2d`.
+pub fn parse_duration(spec: &str) -> Option<Duration> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return None;
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = spec;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let value: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let secs = match &rest[..unit_len] {
+            "ms" => value / 1000.0,
+            "s" => value,
+            "m" => value * 60.0,
+            "h" => value * 3600.0,
+            "d" => value * 86400.0,
+            _ => return None,
+        };
+        total = total.checked_add(Duration::try_from_secs_f64(secs).ok()?)?;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+/// Parses a point in time relative to `now`: `now`, `-5m` (any `parse_duration`
+/// form), RFC 3339, `YYYY-MM-DD HH:MM[:SS]`, or a bare `HH:MM[:SS]`, which means
+/// today unless that is still in the future, in which case it means yesterday.
+pub fn parse_time_spec(spec: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let spec = spec.trim();
+    if spec == "now" {
+        return Some(now);
+    }
+    if let Some(ago) = spec.strip_prefix('-') {
+        let ago = chrono::Duration::from_std(parse_duration(ago)?).ok()?;
+        return now.checked_sub_signed(ago);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(spec) {
+        return Some(dt.with_timezone(&Local));
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|fmt| NaiveDateTime::parse_from_str(spec, fmt).ok());
+    if let Some(naive) = naive {
+        return Local.from_local_datetime(&naive).earliest();
+    }
+    let time = parse_clock_time(spec)?;
+    let today: NaiveDate = now.date_naive();
+    let at = Local
+        .from_local_datetime(&today.and_time(time))
+        .earliest()?;
+    if at > now {
+        at.checked_sub_signed(chrono::Duration::days(1))
+    } else {
+        Some(at)
+    }
+}
+
+/// Optional `(since, until)` bounds of a time window.
+pub type TimeRange = (Option<DateTime<Local>>, Option<DateTime<Local>>);
+
+/// A bare `HH:MM[:SS]` time of day, without a date.
+fn parse_clock_time(spec: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S%.f", "%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveTime::parse_from_str(spec.trim(), fmt).ok())
+}
+
+/// Parses `SINCE..UNTIL`, where either side may be empty; a lone time means `SINCE..`.
+pub fn parse_time_range(spec: &str, now: DateTime<Local>) -> Result<TimeRange, String> {
+    let (since, until) = spec.split_once("..").unwrap_or((spec, ""));
+    time_range(
+        Some(since).filter(|part| !part.trim().is_empty()),
+        Some(until).filter(|part| !part.trim().is_empty()),
+        now,
+    )
+}
+
+/// Resolves both bounds of a window. A bare time of day for UNTIL is taken on
+/// SINCE's day (the next day if it is earlier than SINCE), so `12:00..12:05`
+/// stays five minutes long whatever the current time; an UNTIL before SINCE is
+/// an error.
+pub fn time_range(
+    since: Option<&str>,
+    until: Option<&str>,
+    now: DateTime<Local>,
+) -> Result<TimeRange, String> {
+    let parse = |part: &str| {
+        parse_time_spec(part, now).ok_or_else(|| format!("invalid time {:?}", part.trim()))
+    };
+    let since = since.map(parse).transpose()?;
+    let until = match (since, until) {
+        (Some(since), Some(part)) => match parse_clock_time(part) {
+            Some(time) => {
+                let on_day =
+                    |day: NaiveDate| Local.from_local_datetime(&day.and_time(time)).earliest();
+                let at = on_day(since.date_naive())
+                    .filter(|at| *at >= since)
+                    .or_else(|| on_day(since.date_naive().succ_opt()?))
+                    .ok_or_else(|| format!("invalid time {:?}", part.trim()))?;
+                Some(at)
+            }
+            None => Some(parse(part)?),
+        },
+        (None, Some(part)) => Some(parse(part)?),
+        (_, None) => None,
+    };
+    if let (Some(since), Some(until)) = (since, until) {
+        if until < since {
+            return Err(format!("end {until} is before start {since}"));
         }
     }
+    Ok((since, until))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IngestConfig {
+    pub queue_capacity: usize,
+    pub drop_policy: DropPolicy,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FileConfig {
     pub max_lines: Option<usize>,
+    pub max_bytes: Option<ByteSize>,
+    pub spill_dir: Option<PathBuf>,
+    pub queue_capacity: Option<usize>,
+    pub drop_policy: Option<DropPolicy>,
+    #[serde(default)]
+    pub presets: BTreeMap<String, PresetSpec>,
+    #[serde(default)]
+    pub highlight: Vec<HighlightSpec>,
+    pub correlation_fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub columns: Vec<String>,
+    pub clipboard: Option<ClipboardMode>,
+    /// `:` commands run once at startup, in order.
+    #[serde(default)]
+    pub startup: Vec<String>,
+    /// Key chord -> action name, e.g. `"ctrl-d" = "page-down"`.
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+    /// `dark`, `light`, `high-contrast`, or a theme file path.
+    pub theme: Option<String>,
 }
 
 impl FileConfig {
@@ -79,7 +380,27 @@ impl FileConfig {
 #[derive(Debug)]
 pub struct AppConfig {
     pub max_lines: usize,
+    pub max_bytes: usize,
+    pub spill_dir: Option<PathBuf>,
     pub baseline: BaselineMode,
+    pub ingest: IngestConfig,
+    pub presets: Vec<Preset>,
+    pub highlighter: Highlighter,
+    /// Presets and highlight rules that failed to parse, reported once the UI is up.
+    pub config_errors: Vec<String>,
+    /// Where typed filters are remembered between sessions.
+    pub filter_history: Option<PathBuf>,
+    /// Where per-file bookmarks, filters and positions are remembered.
+    pub state_dir: Option<PathBuf>,
+    /// Field names `x` follows from the selected entry, in order of preference.
+    pub correlation_fields: Vec<String>,
+    /// Column layouts cycled with `L`; the first is shown at startup.
+    pub layouts: Vec<ColumnLayout>,
+    pub clipboard: ClipboardMode,
+    /// `:` commands to run before the first frame.
+    pub startup: Vec<String>,
+    pub keymap: Keymap,
+    pub theme: Theme,
 }
 
 impl AppConfig {
@@ -97,14 +418,117 @@ impl AppConfig {
             .max_lines
             .or_else(|| file_cfg.as_ref().and_then(|c| c.max_lines))
             .unwrap_or(DEFAULT_MAX_LINES);
+        let max_bytes = args
+            .max_bytes
+            .or_else(|| file_cfg.as_ref().and_then(|c| c.max_bytes))
+            .map_or(DEFAULT_MAX_BYTES, |size| size.0);
+        let spill_dir = args
+            .spill_dir
+            .clone()
+            .or_else(|| file_cfg.as_ref().and_then(|c| c.spill_dir.clone()));
         let baseline = match (&args.baseline_record, &args.baseline_compare) {
             (Some(path), None) => BaselineMode::Record(path.clone()),
             (None, Some(path)) => BaselineMode::Compare(path.clone()),
             _ => BaselineMode::Off,
         };
+        let ingest = IngestConfig {
+            queue_capacity: args
+                .queue_capacity
+                .or_else(|| file_cfg.as_ref().and_then(|c| c.queue_capacity))
+                .unwrap_or(DEFAULT_QUEUE_CAPACITY),
+            drop_policy: args
+                .drop_policy
+                .or_else(|| file_cfg.as_ref().and_then(|c| c.drop_policy))
+                .unwrap_or_default(),
+        };
+        let mut presets = Vec::new();
+        let mut config_errors = Vec::new();
+        for (name, spec) in file_cfg.iter().flat_map(|c| c.presets.iter()) {
+            match Preset::parse(name, spec) {
+                Ok(preset) => presets.push(preset),
+                Err(err) => config_errors.push(err),
+            }
+        }
+        let (keymap, key_errors) =
+            Keymap::with_overrides(file_cfg.as_ref().map_or(&BTreeMap::new(), |c| &c.keys));
+        config_errors.extend(key_errors);
+        for preset in &mut presets {
+            let Some(key) = preset.key else {
+                continue;
+            };
+            if let Some(action) = keymap.char_action(key) {
+                config_errors.push(format!(
+                    "preset {}: key {key:?} is bound to {}; the preset key is ignored",
+                    preset.name,
+                    action.info().name
+                ));
+                preset.key = None;
+            }
+        }
+        let (highlighter, highlight_errors) =
+            Highlighter::from_specs(file_cfg.as_ref().map_or(&[][..], |c| &c.highlight));
+        config_errors.extend(highlight_errors);
+        let filter_history = std::env::var_os("LOGTM_HISTORY")
+            .map(PathBuf::from)
+            .or_else(|| dirs::data_dir().map(|dir| dir.join("logtm/filter_history")));
+        let state_dir = std::env::var_os("LOGTM_STATE")
+            .map(PathBuf::from)
+            .or_else(|| dirs::data_dir().map(|dir| dir.join("logtm/state")));
+        let correlation_fields = file_cfg
+            .as_ref()
+            .and_then(|c| c.correlation_fields.clone())
+            .unwrap_or_else(|| {
+                DEFAULT_CORRELATION_FIELDS
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect()
+            });
+        let mut layouts = args.columns.clone();
+        if layouts.is_empty() {
+            for spec in file_cfg.iter().flat_map(|c| c.columns.iter()) {
+                match spec.parse() {
+                    Ok(layout) => layouts.push(layout),
+                    Err(err) => config_errors.push(format!("columns {spec:?}: {err}")),
+                }
+            }
+        }
+        if layouts.is_empty() {
+            layouts = DEFAULT_LAYOUTS
+                .iter()
+                .map(|spec| spec.parse().expect("built-in layout parses"))
+                .collect();
+        }
+        let mut theme = match file_cfg.as_ref().and_then(|c| c.theme.as_deref()) {
+            Some(spec) => {
+                let config_dir = config_path.parent().unwrap_or(Path::new("."));
+                Theme::load(spec, config_dir).unwrap_or_else(|err| {
+                    config_errors.push(format!("theme {spec:?}: {err:#}"));
+                    Theme::default()
+                })
+            }
+            None => Theme::default(),
+        };
+        theme.plain = Theme::no_color_requested();
         AppConfig {
             max_lines,
+            max_bytes,
+            spill_dir,
             baseline,
+            ingest,
+            presets,
+            highlighter,
+            config_errors,
+            filter_history,
+            state_dir,
+            correlation_fields,
+            layouts,
+            clipboard: file_cfg
+                .as_ref()
+                .and_then(|c| c.clipboard)
+                .unwrap_or_default(),
+            startup: file_cfg.map(|c| c.startup).unwrap_or_default(),
+            keymap,
+            theme,
         }
     }
 }
@@ -112,6 +536,7 @@ impl AppConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keymap::Action;
     use std::fs;
     use std::sync::{Mutex, OnceLock};
 
@@ -143,8 +568,24 @@ mod tests {
             file: None,
             stdin: false,
             max_lines: None,
+            max_bytes: None,
+            spill_dir: None,
             baseline_record: None,
             baseline_compare: None,
+            tee: None,
+            replay: None,
+            scenario: None,
+            seed: None,
+            emit: false,
+            queue_capacity: None,
+            drop_policy: None,
+            since: None,
+            until: None,
+            after_context: None,
+            before_context: None,
+            context: None,
+            columns: Vec::new(),
+            fresh: false,
         };
         let cfg = with_logtm_config_path(Some(path.as_path()), || AppConfig::load(&args));
         assert_eq!(cfg.max_lines, DEFAULT_MAX_LINES);
@@ -154,16 +595,113 @@ mod tests {
     fn config_reads_file() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("config.toml");
-        fs::write(&path, "max_lines = 42").unwrap();
+        fs::write(
+            &path,
+            "max_lines = 42\nmax_bytes = \"2M\"\ndrop_policy = \"drop-oldest\"\n\n\
+             correlation_fields = [\"txn\"]\ncolumns = [\"ms,level\", \"nope\"]\n\
+             startup = [\"set wrap=on\", \":filter level>=warn\"]\ntheme = \"light\"\n\n\
+             [presets]\ndb-errors = \"level>=error target:db\"\n\
+             slow = { query = \"re:took [0-9]{4,}ms\", key = \"5\" }\nbad = \"level>=loud\"\n\n\
+             [keys]\n\"ctrl-d\" = \"page-down\"\nu = \"nonsense\"\n\n\
+             [[highlight]]\npattern = \"status=5\\\\d\\\\d\"\nfg = \"red\"\nbold = true\n",
+        )
+        .unwrap();
         let args = Args {
             file: None,
             stdin: false,
             max_lines: None,
+            max_bytes: None,
+            spill_dir: None,
             baseline_record: None,
             baseline_compare: None,
+            tee: None,
+            replay: None,
+            scenario: None,
+            seed: None,
+            emit: false,
+            queue_capacity: None,
+            drop_policy: None,
+            since: None,
+            until: None,
+            after_context: None,
+            before_context: None,
+            context: None,
+            columns: Vec::new(),
+            fresh: false,
         };
         let cfg = with_logtm_config_path(Some(path.as_path()), || AppConfig::load(&args));
         assert_eq!(cfg.max_lines, 42);
+        assert_eq!(cfg.max_bytes, 2 * 1024 * 1024);
+        assert_eq!(cfg.ingest.drop_policy, DropPolicy::DropOldest);
+        let names: Vec<_> = cfg.presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["db-errors", "slow"]);
+        assert_eq!(cfg.presets[1].key, Some('5'));
+        assert_eq!(cfg.config_errors.len(), 3);
+        assert_eq!(cfg.layouts.len(), 1);
+        assert_eq!(cfg.highlighter.spans("status=503").len(), 1);
+        assert_eq!(cfg.correlation_fields, vec!["txn"]);
+        assert_eq!(cfg.startup, vec!["set wrap=on", ":filter level>=warn"]);
+        assert_eq!(cfg.keymap.keys(Action::PageDown), "pgdn/ctrl-d");
+        assert_eq!(cfg.theme.name, "light");
+    }
+
+    #[test]
+    fn durations_accept_compound_units() {
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("5 minutes"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert!(parse_duration("18446744073709549568s").is_some());
+        assert_eq!(parse_duration("18446744073709549568s1d"), None);
+    }
+
+    #[test]
+    fn time_specs_resolve_against_now() {
+        let now = Local.with_ymd_and_hms(2024, 5, 2, 12, 0, 0).unwrap();
+        assert_eq!(
+            parse_time_spec("-5m", now),
+            Some(Local.with_ymd_and_hms(2024, 5, 2, 11, 55, 0).unwrap())
+        );
+        assert_eq!(
+            parse_time_spec("11:30", now),
+            Some(Local.with_ymd_and_hms(2024, 5, 2, 11, 30, 0).unwrap())
+        );
+        assert_eq!(
+            parse_time_spec("12:03:10", now),
+            Some(Local.with_ymd_and_hms(2024, 5, 1, 12, 3, 10).unwrap())
+        );
+        assert_eq!(
+            parse_time_spec("2024-05-01 08:00:00", now),
+            Some(Local.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap())
+        );
+        assert!(parse_time_spec("2024-05-01T08:00:00Z", now).is_some());
+        assert_eq!(parse_time_spec("yesterday", now), None);
+        assert_eq!(parse_time_spec("-99999999999999999999s", now), None);
+        assert_eq!(parse_time_spec("-100000000000d", now), None);
+
+        let (since, until) = parse_time_range("-1h..-30m", now).unwrap();
+        assert!(since.unwrap() < until.unwrap());
+        assert_eq!(parse_time_range("..", now), Ok((None, None)));
+        assert!(parse_time_range("soon..", now).is_err());
+        assert!(parse_time_range("-99999999999999999999s..", now).is_err());
+
+        // At 12:03 both ends are on the same day, not 12:05 yesterday.
+        let at_1203 = Local.with_ymd_and_hms(2024, 5, 2, 12, 3, 0).unwrap();
+        assert_eq!(
+            parse_time_range("12:00..12:05", at_1203),
+            Ok((
+                Some(Local.with_ymd_and_hms(2024, 5, 2, 12, 0, 0).unwrap()),
+                Some(Local.with_ymd_and_hms(2024, 5, 2, 12, 5, 0).unwrap()),
+            ))
+        );
+        let (_, until) = parse_time_range("23:50..00:10", now).unwrap();
+        assert_eq!(
+            until,
+            Some(Local.with_ymd_and_hms(2024, 5, 2, 0, 10, 0).unwrap())
+        );
+        assert!(parse_time_range("-10m..-1h", now).is_err());
     }
 
     #[test]
@@ -172,8 +710,24 @@ mod tests {
             file: None,
             stdin: false,
             max_lines: None,
+            max_bytes: None,
+            spill_dir: None,
             baseline_record: Some(PathBuf::from("/tmp/base.json")),
             baseline_compare: None,
+            tee: None,
+            replay: None,
+            scenario: None,
+            seed: None,
+            emit: false,
+            queue_capacity: None,
+            drop_policy: None,
+            since: None,
+            until: None,
+            after_context: None,
+            before_context: None,
+            context: None,
+            columns: Vec::new(),
+            fresh: false,
         };
         let cfg = with_logtm_config_path(None, || AppConfig::load(&args));
         match cfg.baseline {
@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+
+use crate::log_entry::LogEntry;
+
+/// Field names followed by `x` when `correlation_fields` is not configured.
+pub const DEFAULT_CORRELATION_FIELDS: &[&str] =
+    &["req", "request_id", "trace_id", "span_id", "correlation_id"];
+
+/// Filter that passes entries carrying `key=value`, e.g. every line of one request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub key: String,
+    pub value: String,
+}
+
+impl Trace {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        fields(entry)
+            .iter()
+            .any(|(key, value)| key.eq_ignore_ascii_case(&self.key) && *value == self.value)
+    }
+}
+
+/// The fields of `entry`: those found in its message, then those of its JSON line.
+pub fn fields(entry: &LogEntry) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+    let mut out = message_fields(&entry.message);
+    out.extend(
+        entry
+            .fields
+            .iter()
+            .map(|(key, value)| (Cow::Borrowed(key.as_str()), Cow::Borrowed(value.as_str()))),
+    );
+    out
+}
+
+/// `key=value` words in a message, in order, plus the scalar fields of a JSON
+/// object embedded in it (`payload {"trace_id":"ab12"}`).
+fn message_fields(message: &str) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+    let mut out = Vec::new();
+    for word in message.split_whitespace() {
+        let Some((key, value)) = word.split_once('=') else {
+            continue;
+        };
+        let key = key.trim_start_matches(['(', '[', '{', ',']);
+        let value = value
+            .trim_end_matches([',', ';', ')', ']', '}'])
+            .trim_matches(['"', '\'']);
+        let key_ok = key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !key.is_empty() && key_ok && !value.is_empty() {
+            out.push((Cow::Borrowed(key), Cow::Borrowed(value)));
+        }
+    }
+    if let (Some(start), Some(end)) = (message.find('{'), message.rfind('}')) {
+        if let Ok(serde_json::Value::Object(map)) =
+            serde_json::from_str::<serde_json::Value>(&message[start..=end])
+        {
+            for (key, value) in map {
+                if let Some(value) = scalar_text(&value) {
+                    out.push((Cow::Owned(key), Cow::Owned(value)));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Strings, numbers and booleans as they would be written after `key=`.
+pub fn scalar_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_values_and_embedded_json_are_extracted() {
+        let pairs: Vec<_> = message_fields(
+            r#"GET /a (req=4821, user="bob") took=12ms {"trace_id":"ab12","n":3,"x":[1]}"#,
+        )
+        .into_iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect();
+        assert_eq!(
+            pairs,
+            vec!["req=4821", "user=bob", "took=12ms", "n=3", "trace_id=ab12"]
+        );
+        let trace = Trace {
+            key: "REQ".to_string(),
+            value: "4821".to_string(),
+        };
+        let entry = |message: &str| LogEntry {
+            message: message.to_string(),
+            ..crate::log_entry::parse_line(r#"{"msg":"","req":4821}"#, chrono::Local::now())
+        };
+        assert!(trace.matches(&entry("cache miss req=4821")));
+        assert!(trace.matches(&entry("cache miss")), "JSON fields count too");
+        let plain = crate::log_entry::parse_line("cache miss req=48210", chrono::Local::now());
+        assert!(!trace.matches(&plain));
+        assert!(!message_fields("a == b").iter().any(|(k, _)| k.is_empty()));
+    }
+}
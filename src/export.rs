@@ -0,0 +1,296 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use chrono::SecondsFormat;
+
+use crate::{correlate::fields, log_entry::LogEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// Lines as they were read (plain-text form for generated entries).
+    #[default]
+    Raw,
+    /// One JSON object per line, with `key=value` fields under `fields`.
+    Jsonl,
+    /// A header row, then one row per entry with a column per field name seen.
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "raw" | "log" | "txt" => Ok(ExportFormat::Raw),
+            "jsonl" | "json" | "ndjson" => Ok(ExportFormat::Jsonl),
+            "csv" => Ok(ExportFormat::Csv),
+            other => Err(format!("unknown format {other:?} (raw, jsonl or csv)")),
+        }
+    }
+}
+
+/// Which entries an export covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportScope {
+    /// Everything matching the current filters.
+    #[default]
+    Filtered,
+    /// Filter matches between the bookmarks around the selected line.
+    Bookmarks,
+    /// Filter matches inside the timeline cursor's bin.
+    TimelineBin,
+    /// Every buffered entry, spilled history included, ignoring filters.
+    All,
+}
+
+/// A parsed `path [--format raw|jsonl|csv] [--marks|--bin|--all]` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRequest {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    pub scope: ExportScope,
+}
+
+impl FromStr for ExportRequest {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut path = None;
+        let mut format = None;
+        let mut scope = ExportScope::default();
+        let mut words = spec.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "--format" | "-f" => {
+                    let name = words.next().ok_or("--format needs raw, jsonl or csv")?;
+                    format = Some(name.parse()?);
+                }
+                "--marks" => scope = ExportScope::Bookmarks,
+                "--bin" => scope = ExportScope::TimelineBin,
+                "--all" => scope = ExportScope::All,
+                flag if flag.starts_with("--") => {
+                    if let Some(name) = flag.strip_prefix("--format=") {
+                        format = Some(name.parse()?);
+                    } else {
+                        return Err(format!("unknown option {flag}"));
+                    }
+                }
+                word if path.is_none() => path = Some(expand_home(word)),
+                word => return Err(format!("unexpected {word:?} after the path")),
+            }
+        }
+        let path = path.ok_or("export needs a file path")?;
+        // Without --format, go by the extension: out.csv, out.jsonl, anything else raw.
+        let format = format.unwrap_or_else(|| {
+            path.extension()
+                .and_then(|ext| ext.to_str()?.parse().ok())
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            path,
+            format,
+            scope,
+        })
+    }
+}
+
+fn expand_home(word: &str) -> PathBuf {
+    match (word.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(word),
+    }
+}
+
+/// The JSON form used by `jsonl` exports and `Y`.
+pub fn entry_json(entry: &LogEntry) -> serde_json::Value {
+    let extracted: serde_json::Map<String, serde_json::Value> = fields(entry)
+        .into_iter()
+        .map(|(key, value)| (key.into_owned(), value.into_owned().into()))
+        .collect();
+    let mut json = serde_json::json!({
+        "timestamp": entry.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        "level": entry.level.label(),
+        "target": entry.target,
+        "message": entry.message,
+    });
+    if !extracted.is_empty() {
+        json["fields"] = extracted.into();
+    }
+    json
+}
+
+/// Writes an export entry by entry, so history read back from disk can be
+/// passed through a page at a time. CSV columns are fixed when it is created.
+pub struct ExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    columns: Vec<String>,
+}
+
+impl<W: Write> ExportWriter<W> {
+    /// Starts the export, writing the CSV header row with a column per name in `fields`.
+    pub fn new(mut out: W, format: ExportFormat, fields: BTreeSet<String>) -> io::Result<Self> {
+        let columns: Vec<String> = fields.into_iter().collect();
+        if format == ExportFormat::Csv {
+            let mut header = vec!["timestamp", "level", "target", "message"];
+            header.extend(columns.iter().map(String::as_str));
+            write_csv_row(&mut out, header)?;
+        }
+        Ok(Self {
+            out,
+            format,
+            columns,
+        })
+    }
+
+    pub fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Raw => writeln!(self.out, "{}", entry.raw_line()),
+            ExportFormat::Jsonl => writeln!(self.out, "{}", entry_json(entry)),
+            ExportFormat::Csv => {
+                let found = fields(entry);
+                let timestamp = entry
+                    .timestamp
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, false);
+                let mut row = vec![
+                    timestamp.as_str(),
+                    entry.level.label(),
+                    &entry.target,
+                    &entry.message,
+                ];
+                row.extend(self.columns.iter().map(|name| {
+                    found
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map_or("", |(_, value)| value.as_ref())
+                }));
+                write_csv_row(&mut self.out, row)
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Names of the fields found in `entries`, which become CSV columns.
+pub fn field_names<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> BTreeSet<String> {
+    entries
+        .into_iter()
+        .flat_map(fields)
+        .map(|(key, _)| key.into_owned())
+        .collect()
+}
+
+pub fn write_entries(
+    entries: &[&LogEntry],
+    format: ExportFormat,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let names = match format {
+        ExportFormat::Csv => field_names(entries.iter().copied()),
+        _ => BTreeSet::new(),
+    };
+    let mut writer = ExportWriter::new(out, format, names)?;
+    for entry in entries {
+        writer.write(entry)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes `entries` to `path`, replacing the file.
+pub fn export_to(path: &Path, entries: &[&LogEntry], format: ExportFormat) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_entries(entries, format, &mut out).with_context(|| format!("writing {}", path.display()))
+}
+
+fn write_csv_row<'a>(
+    out: &mut impl Write,
+    cells: impl IntoIterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    let cells: Vec<String> = cells
+        .into_iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+    writeln!(out, "{}", cells.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::Level;
+    use chrono::Local;
+
+    #[test]
+    fn requests_parse_and_csv_gets_a_column_per_field() {
+        let req: ExportRequest = "out.csv --marks".parse().unwrap();
+        assert_eq!(req.format, ExportFormat::Csv);
+        assert_eq!(req.scope, ExportScope::Bookmarks);
+        let req: ExportRequest = "dump.txt --format=jsonl --all".parse().unwrap();
+        assert_eq!(
+            (req.format, req.scope),
+            (ExportFormat::Jsonl, ExportScope::All)
+        );
+        assert!("--bin".parse::<ExportRequest>().is_err());
+        assert!("a.log --format xml".parse::<ExportRequest>().is_err());
+
+        let entry = |message: &str| LogEntry {
+            timestamp: Local::now(),
+            level: Level::Warn,
+            target: "api".to_string(),
+            message: message.to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let (a, b) = (
+            entry("slow req=7 took=900ms"),
+            entry("said \"hi, there\" user=bo"),
+        );
+        let mut out = Vec::new();
+        write_entries(&[&a, &b], ExportFormat::Csv, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "timestamp,level,target,message,req,took,user");
+        assert!(lines[1].ends_with(",WARN,api,slow req=7 took=900ms,7,900ms,"));
+        assert!(lines[2].ends_with(",\"said \"\"hi, there\"\" user=bo\",,,bo"));
+
+        let json = entry_json(&a);
+        assert_eq!(json["fields"]["took"], "900ms");
+    }
+
+    #[test]
+    fn raw_exports_keep_lines_as_read() {
+        let lines = [
+            r#"{"level":"warn","msg":"slow","trace_id":"ab12","ms":900}"#,
+            "2024-12-17T12:00:00Z  ERROR db   deadlock",
+        ];
+        let entries: Vec<LogEntry> = lines
+            .iter()
+            .map(|line| crate::log_entry::parse_line(line, Local::now()))
+            .collect();
+        let mut out = Vec::new();
+        write_entries(
+            &entries.iter().collect::<Vec<_>>(),
+            ExportFormat::Raw,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), lines.join("\n") + "\n");
+    }
+}
@@ -1,6 +1,18 @@
-use regex::Regex;
+use std::{cell::RefCell, fmt::Write};
 
-use crate::log_entry::{Level, LogEntry};
+use chrono::{DateTime, Datelike, Local, Timelike};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    correlate::Trace,
+    log_entry::{Level, LogEntry},
+    templates::TemplateMatch,
+};
+
+thread_local! {
+    static HAYSTACK: RefCell<String> = const { RefCell::new(String::new()) };
+}
 
 #[derive(Debug, Clone)]
 pub struct Filters {
@@ -9,9 +21,51 @@ pub struct Filters {
     pub error: bool,
     pub text: Option<String>,
     pub regex_mode: bool,
+    pub case: CaseMode,
+    pub whole_word: bool,
+    /// Only entries whose target equals this (ignoring ASCII case) pass.
+    pub target: Option<String>,
+    /// Inclusive time window; entries outside it are hidden.
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+    /// Only entries whose message has this template's shape pass.
+    pub template: Option<TemplateMatch>,
+    /// Only entries carrying this correlation field value pass.
+    pub trace: Option<Trace>,
     pub compiled: Option<Regex>,
 }
 
+/// How letter case is treated by the text filter, in both literal and regex mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CaseMode {
+    /// Case-insensitive unless the filter contains an uppercase letter.
+    #[default]
+    #[serde(rename = "smart")]
+    Smart,
+    #[serde(rename = "match")]
+    Sensitive,
+    #[serde(rename = "ignore")]
+    Insensitive,
+}
+
+impl CaseMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseMode::Smart => "smart",
+            CaseMode::Sensitive => "match",
+            CaseMode::Insensitive => "ignore",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Smart => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Insensitive,
+            CaseMode::Insensitive => CaseMode::Smart,
+        }
+    }
+}
+
 impl Default for Filters {
     fn default() -> Self {
         Self {
@@ -20,6 +74,13 @@ impl Default for Filters {
             error: true,
             text: None,
             regex_mode: false,
+            case: CaseMode::default(),
+            whole_word: false,
+            target: None,
+            since: None,
+            until: None,
+            template: None,
+            trace: None,
             compiled: None,
         }
     }
@@ -35,53 +96,159 @@ impl Filters {
         if !level_ok {
             return false;
         }
-        let Some(ref text) = self.text else {
-            return true;
-        };
-        if text.is_empty() {
-            return true;
+        if self.since.is_some_and(|since| entry.timestamp < since)
+            || self.until.is_some_and(|until| entry.timestamp > until)
+        {
+            return false;
         }
-
-        let haystack = format!(
-            "{} {} {} {}",
-            entry.timestamp.format("%Y-%m-%dT%H:%M:%S"),
-            entry.level.label(),
-            entry.target,
-            entry.message
-        );
-
-        if self.regex_mode {
-            if let Some(re) = &self.compiled {
-                return re.is_match(&haystack);
+        if let Some(target) = &self.target {
+            if !entry.target.eq_ignore_ascii_case(target) {
+                return false;
             }
-            return true;
         }
-
-        let needle = text.to_lowercase();
-        haystack.to_lowercase().contains(&needle)
+        if self
+            .template
+            .as_ref()
+            .is_some_and(|template| !template.matches(&entry.message))
+        {
+            return false;
+        }
+        if self
+            .trace
+            .as_ref()
+            .is_some_and(|trace| !trace.matches(entry))
+        {
+            return false;
+        }
+        let Some(re) = &self.compiled else {
+            return true;
+        };
+        HAYSTACK.with(|buf| {
+            let mut haystack = buf.borrow_mut();
+            write_haystack(&mut haystack, entry);
+            re.is_match(&haystack)
+        })
     }
 
+    /// Sets the text filter and compiles it; literal text becomes an escaped pattern
+    /// so both modes share one matcher, with case and word options applied on top.
     pub fn set_text(&mut self, text: Option<String>) -> Result<(), regex::Error> {
         self.text = text;
-        if self.regex_mode {
-            if let Some(t) = &self.text {
-                if t.is_empty() {
-                    self.compiled = None;
+        self.compiled = match self.text.as_deref() {
+            None | Some("") => None,
+            Some(t) => {
+                let mut pattern = if self.regex_mode {
+                    t.to_string()
                 } else {
-                    self.compiled = Some(Regex::new(t)?);
+                    regex::escape(t)
+                };
+                if self.whole_word {
+                    let (start, end) = word_edges(t, self.regex_mode);
+                    let (start, end) =
+                        (if start { r"\b" } else { "" }, if end { r"\b" } else { "" });
+                    pattern = format!("{start}(?:{pattern}){end}");
                 }
+                Some(
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(self.ignores_case())
+                        .build()?,
+                )
             }
-        } else {
-            self.compiled = None;
-        }
+        };
         Ok(())
     }
+
+    /// Whether the current text is matched case-insensitively, resolving smart case.
+    pub fn ignores_case(&self) -> bool {
+        match self.case {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !self
+                .text
+                .as_deref()
+                .is_some_and(|t| has_uppercase(t, self.regex_mode)),
+        }
+    }
+}
+
+/// Whether each end of a whole-word needle needs a `\b`: only where it starts or
+/// ends with a word character, so `error:` still matches `error: timeout`. Regex
+/// edges count as non-word only when they are plain or escaped punctuation.
+fn word_edges(text: &str, regex_mode: bool) -> (bool, bool) {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    if !regex_mode {
+        return (
+            text.chars().next().is_some_and(is_word),
+            text.chars().last().is_some_and(is_word),
+        );
+    }
+    let plain = |c: char| !is_word(c) && !c.is_whitespace() && !r"\.+*?()|[]{}^$".contains(c);
+    let mut chars = text.chars();
+    let start = match (chars.next(), chars.next()) {
+        (Some('\\'), Some(c)) => c.is_alphanumeric(),
+        (Some(c), _) => !plain(c),
+        (None, _) => true,
+    };
+    let mut rev = text.chars().rev();
+    let end = match (rev.next(), rev.next()) {
+        (Some(c), Some('\\')) => c.is_alphanumeric(),
+        (Some(c), _) => !plain(c),
+        (None, _) => true,
+    };
+    (start, end)
+}
+
+/// Uppercase letters that are part of a regex escape (`\W`, `\D`, ...) don't count.
+fn has_uppercase(text: &str, regex_mode: bool) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if regex_mode && c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Writes `"<timestamp> <LEVEL> <target> <message>"`, the text filters match against,
+/// reusing `buf` instead of allocating per entry.
+pub(crate) fn write_haystack(buf: &mut String, entry: &LogEntry) {
+    buf.clear();
+    let ts = entry.timestamp;
+    let _ = write!(
+        buf,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02} {} {} {}",
+        ts.year(),
+        ts.month(),
+        ts.day(),
+        ts.hour(),
+        ts.minute(),
+        ts.second(),
+        entry.level.label(),
+        entry.target,
+        entry.message
+    );
 }
 
 #[derive(Debug, Clone)]
 pub enum InputMode {
     Normal,
     FilterText(String),
+    /// `G` prompt: a time to jump to.
+    GotoTime(String),
+    /// `T` prompt: a `since..until` window for the time-range filter.
+    TimeRange(String),
+    /// `:` prompt: a command line (`E` opens it with `export ` typed).
+    Command(String),
+    /// Preset picker popup; holds the highlighted row.
+    PresetPicker(usize),
+    /// Template panel; holds the highlighted row in the current sort order.
+    Templates(usize),
+    /// Bookmark side panel; holds the highlighted bookmark.
+    Bookmarks(usize),
+    /// Bookmark prompt: `None` adds one on the selected line, `Some` edits that bookmark.
+    BookmarkEdit(Option<usize>, String),
 }
 
 #[cfg(test)]
@@ -96,15 +263,86 @@ mod tests {
             level: Level::Warn,
             target: "api".to_string(),
             message: "timeout while calling upstream".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let mut filters = Filters {
+            regex_mode: true,
+            ..Filters::default()
         };
-        let mut filters = Filters::default();
-        filters.regex_mode = true;
         filters
             .set_text(Some("WARN.*api.*timeout".to_string()))
             .unwrap();
         assert!(filters.matches(&entry));
     }
 
+    #[test]
+    fn smart_case_and_whole_word() {
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: Level::Info,
+            target: "proc".to_string(),
+            message: "stderr closed".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let mut filters = Filters::default();
+        filters.set_text(Some("STDERR".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+        filters.set_text(Some("stderr".to_string())).unwrap();
+        assert!(filters.matches(&entry));
+
+        filters.case = CaseMode::Insensitive;
+        filters.whole_word = true;
+        filters.set_text(Some("ERR".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+        filters.set_text(Some("STDERR".to_string())).unwrap();
+        assert!(filters.matches(&entry));
+
+        filters.regex_mode = true;
+        filters.whole_word = false;
+        filters.case = CaseMode::Smart;
+        filters.set_text(Some(r"R\Wclosed".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+        filters.set_text(Some(r"r\Wclosed".to_string())).unwrap();
+        assert!(filters.ignores_case());
+        assert!(filters.matches(&entry));
+    }
+
+    #[test]
+    fn whole_word_needles_may_start_or_end_with_punctuation() {
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: Level::Error,
+            target: "db".to_string(),
+            message: "error: timeout (retry=3)".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let mut filters = Filters {
+            whole_word: true,
+            ..Filters::default()
+        };
+        for needle in ["error:", "(retry=3)", "retry=", "timeout ("] {
+            filters.set_text(Some(needle.to_string())).unwrap();
+            assert!(filters.matches(&entry), "{needle}");
+        }
+        filters.set_text(Some("erro".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+        filters.set_text(Some("rror:".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+
+        filters.regex_mode = true;
+        for pattern in [r"error:", r"\(retry=\d\)", r"time\w+ \("] {
+            filters.set_text(Some(pattern.to_string())).unwrap();
+            assert!(filters.matches(&entry), "{pattern}");
+        }
+        filters.set_text(Some(r"err\w*".to_string())).unwrap();
+        assert!(filters.matches(&entry));
+        filters.set_text(Some(r"rr\w*".to_string())).unwrap();
+        assert!(!filters.matches(&entry));
+    }
+
     #[test]
     fn filters_match_all_fields_with_text() {
         let entry = LogEntry {
@@ -112,6 +350,8 @@ mod tests {
             level: Level::Info,
             target: "ingest".to_string(),
             message: "ingest worker started".to_string(),
+            fields: Vec::new(),
+            raw: None,
         };
         let mut filters = Filters::default();
         filters.set_text(Some("ingest worker".to_string())).unwrap();
@@ -125,6 +365,8 @@ mod tests {
             level: Level::Error,
             target: "db".to_string(),
             message: "failed to commit".to_string(),
+            fields: Vec::new(),
+            raw: None,
         };
         let mut filters = Filters::default();
         filters.set_text(Some("error db".to_string())).unwrap();
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{filters::write_haystack, log_entry::LogEntry};
+
+/// One `[[highlight]]` table from `config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightSpec {
+    pub pattern: String,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub scope: Scope,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Style only the matched text in the message.
+    #[default]
+    Match,
+    /// Style the whole row when the pattern matches anywhere in it.
+    Line,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    regex: Regex,
+    style: Style,
+    scope: Scope,
+}
+
+/// Highlight rules in config order; earlier rules win where they overlap.
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    rules: Vec<Rule>,
+}
+
+impl Highlighter {
+    /// Builds the usable rules and describes the rejected ones.
+    pub fn from_specs(specs: &[HighlightSpec]) -> (Self, Vec<String>) {
+        let mut rules = Vec::new();
+        let mut errors = Vec::new();
+        for spec in specs {
+            match Rule::from_spec(spec) {
+                Ok(rule) => rules.push(rule),
+                Err(err) => errors.push(format!("highlight {:?}: {err}", spec.pattern)),
+            }
+        }
+        (Self { rules }, errors)
+    }
+
+    /// Style of the first line-scoped rule matching the entry's filter haystack.
+    pub fn line_style(&self, entry: &LogEntry) -> Option<Style> {
+        let mut line_rules = self
+            .rules
+            .iter()
+            .filter(|r| r.scope == Scope::Line)
+            .peekable();
+        line_rules.peek()?;
+        let mut haystack = String::new();
+        write_haystack(&mut haystack, entry);
+        line_rules
+            .find(|rule| rule.regex.is_match(&haystack))
+            .map(|rule| rule.style)
+    }
+
+    /// Non-overlapping `(range, style)` pairs for match-scoped rules, in text order.
+    pub fn spans(&self, text: &str) -> Vec<(Range<usize>, Style)> {
+        let mut taken: Vec<(Range<usize>, Style)> = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.scope == Scope::Match) {
+            for m in rule.regex.find_iter(text).filter(|m| !m.is_empty()) {
+                let overlaps = taken
+                    .iter()
+                    .any(|(r, _)| m.start() < r.end && r.start < m.end());
+                if !overlaps {
+                    taken.push((m.range(), rule.style));
+                }
+            }
+        }
+        taken.sort_by_key(|(range, _)| range.start);
+        taken
+    }
+}
+
+impl Rule {
+    fn from_spec(spec: &HighlightSpec) -> Result<Self, String> {
+        let regex = Regex::new(&spec.pattern).map_err(|err| err.to_string())?;
+        let mut style = Style::default();
+        if let Some(fg) = &spec.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &spec.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        if spec.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        Ok(Self {
+            regex,
+            style,
+            scope: spec.scope,
+        })
+    }
+}
+
+/// Accepts colour names (`red`, `lightblue`), `#rrggbb` and 256-colour indexes.
+fn parse_color(name: &str) -> Result<Color, String> {
+    name.parse::<Color>()
+        .map_err(|_| format!("unknown colour {name:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(pattern: &str, fg: &str, scope: Scope) -> HighlightSpec {
+        HighlightSpec {
+            pattern: pattern.to_string(),
+            fg: Some(fg.to_string()),
+            bg: None,
+            bold: false,
+            scope,
+        }
+    }
+
+    #[test]
+    fn earlier_rules_win_overlaps_and_bad_rules_are_reported() {
+        let (highlighter, errors) = Highlighter::from_specs(&[
+            spec(r"status=5\d\d", "red", Scope::Match),
+            spec(r"\d+", "blue", Scope::Match),
+            spec("req-", "nope", Scope::Match),
+            spec("(", "red", Scope::Match),
+            spec("upstream", "#ff00ff", Scope::Line),
+        ]);
+        assert_eq!(errors.len(), 2);
+        let text = "id 7 status=503 took 12ms";
+        let spans: Vec<_> = highlighter
+            .spans(text)
+            .into_iter()
+            .map(|(range, style)| (&text[range], style.fg))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("7", Some(Color::Blue)),
+                ("status=503", Some(Color::Red)),
+                ("12", Some(Color::Blue)),
+            ]
+        );
+
+        let entry = LogEntry {
+            timestamp: chrono::Local::now(),
+            level: crate::log_entry::Level::Warn,
+            target: "upstream".to_string(),
+            message: "slow".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        assert_eq!(
+            highlighter.line_style(&entry).and_then(|s| s.fg),
+            Some(Color::Rgb(255, 0, 255))
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::log_entry::{Level, LogEntry};
+
+/// Start a new segment file once the current one reaches this size.
+const SEGMENT_BYTES: u64 = 8 * 1024 * 1024;
+/// One time-index point is kept for every this many spilled entries.
+const INDEX_EVERY: u64 = 64;
+
+/// Append-only, on-disk store for entries evicted from memory, numbered by the
+/// same sequence numbers `App` uses.
+pub struct SpillStore {
+    dir: PathBuf,
+    segments: Vec<PathBuf>,
+    writer: Option<BufWriter<File>>,
+    written: u64,
+    /// Sparse index: (seq, timestamp, segment, byte offset), in seq order.
+    index: Vec<IndexPoint>,
+    first_seq: Option<u64>,
+    next_seq: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IndexPoint {
+    seq: u64,
+    timestamp: DateTime<Local>,
+    segment: usize,
+    offset: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpilledEntry {
+    seq: u64,
+    ts: String,
+    level: String,
+    target: String,
+    message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+}
+
+impl SpillStore {
+    /// Creates a private session directory under `parent`; it is removed on drop.
+    pub fn create(parent: &Path) -> anyhow::Result<Self> {
+        let dir = parent.join(format!(
+            "ltm-spill-{}-{}",
+            std::process::id(),
+            Local::now().format("%Y%m%d%H%M%S")
+        ));
+        fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            segments: Vec::new(),
+            writer: None,
+            written: 0,
+            index: Vec::new(),
+            first_seq: None,
+            next_seq: 0,
+        })
+    }
+
+    /// Sequence number the next appended entry must have; anything below it is on disk.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    pub fn first_seq(&self) -> Option<u64> {
+        self.first_seq
+    }
+
+    pub fn append(&mut self, seq: u64, entry: &LogEntry) -> anyhow::Result<()> {
+        if self.writer.is_none() || self.written >= SEGMENT_BYTES {
+            self.roll_segment()?;
+        }
+        if self.first_seq.is_none() {
+            self.first_seq = Some(seq);
+        }
+        let segment = self.segments.len() - 1;
+        if (seq - self.first_seq.unwrap_or(seq)).is_multiple_of(INDEX_EVERY) || self.written == 0 {
+            self.index.push(IndexPoint {
+                seq,
+                timestamp: entry.timestamp,
+                segment,
+                offset: self.written,
+            });
+        }
+        let record = SpilledEntry {
+            seq,
+            ts: entry
+                .timestamp
+                .to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            level: entry.level.label().to_string(),
+            target: entry.target.clone(),
+            message: entry.message.clone(),
+            fields: entry.fields.clone(),
+            raw: entry.raw.clone(),
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        if let Some(writer) = &mut self.writer {
+            writer.write_all(&line)?;
+        }
+        self.written += line.len() as u64;
+        self.next_seq = seq + 1;
+        Ok(())
+    }
+
+    /// Reads entries with `from <= seq < to`, oldest first.
+    pub fn read_range(&mut self, from: u64, to: u64) -> anyhow::Result<Vec<(u64, LogEntry)>> {
+        let Some(first) = self.first_seq else {
+            return Ok(Vec::new());
+        };
+        let from = from.max(first);
+        let to = to.min(self.next_seq);
+        if from >= to {
+            return Ok(Vec::new());
+        }
+        if let Some(writer) = &mut self.writer {
+            writer.flush()?;
+        }
+        let start = self
+            .index
+            .partition_point(|p| p.seq <= from)
+            .saturating_sub(1);
+        let Some(point) = self.index.get(start).copied() else {
+            return Ok(Vec::new());
+        };
+        let mut out = Vec::with_capacity((to - from) as usize);
+        for (segment_idx, path) in self.segments.iter().enumerate().skip(point.segment) {
+            let mut file =
+                File::open(path).with_context(|| format!("opening {}", path.display()))?;
+            if segment_idx == point.segment {
+                file.seek(SeekFrom::Start(point.offset))?;
+            }
+            for line in BufReader::new(file).lines() {
+                let record: SpilledEntry = serde_json::from_str(&line?)?;
+                if record.seq >= to {
+                    return Ok(out);
+                }
+                if record.seq >= from {
+                    out.push((record.seq, record.into_entry()));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Approximate sequence number of the first spilled entry at or after `ts`.
+    pub fn seq_at_or_after(&self, ts: DateTime<Local>) -> Option<u64> {
+        let first = self.first_seq?;
+        let idx = self.index.partition_point(|p| p.timestamp < ts);
+        Some(match idx {
+            0 => first,
+            idx => self.index[idx - 1].seq,
+        })
+    }
+
+    fn roll_segment(&mut self) -> anyhow::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        let path = self
+            .dir
+            .join(format!("segment-{:06}.jsonl", self.segments.len() + 1));
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("creating {}", path.display()))?;
+        self.segments.push(path);
+        self.writer = Some(BufWriter::new(file));
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        self.writer = None;
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+impl SpilledEntry {
+    fn into_entry(self) -> LogEntry {
+        LogEntry {
+            timestamp: DateTime::parse_from_rfc3339(&self.ts)
+                .map(|dt| dt.with_timezone(&Local))
+                .unwrap_or_else(|_| Local::now()),
+            level: Level::from_name(&self.level).unwrap_or(Level::Info),
+            target: self.target,
+            message: self.message,
+            fields: self.fields,
+            raw: self.raw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(i: u64, base: DateTime<Local>) -> LogEntry {
+        LogEntry {
+            timestamp: base + chrono::Duration::seconds(i as i64),
+            level: Level::Warn,
+            target: "db".to_string(),
+            message: format!("line {i}"),
+            fields: Vec::new(),
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn spilled_entries_page_back_by_seq_and_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = Local::now();
+        let session_dir;
+        {
+            let mut store = SpillStore::create(dir.path()).unwrap();
+            session_dir = store.dir.clone();
+            for i in 10..500 {
+                store.append(i, &entry(i, base)).unwrap();
+            }
+            assert_eq!((store.first_seq(), store.next_seq()), (Some(10), 500));
+            let page = store.read_range(200, 203).unwrap();
+            let messages: Vec<_> = page.iter().map(|(_, e)| e.message.as_str()).collect();
+            assert_eq!(messages, vec!["line 200", "line 201", "line 202"]);
+            assert_eq!(page[0].1.level, Level::Warn);
+
+            let seq = store
+                .seq_at_or_after(base + chrono::Duration::seconds(300))
+                .unwrap();
+            assert!(seq <= 300 && seq + INDEX_EVERY > 300);
+            assert!(store.read_range(0, 5).unwrap().is_empty());
+        }
+        assert!(!session_dir.exists());
+    }
+}
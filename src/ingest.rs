@@ -1,8 +1,9 @@
 use std::{
+    collections::VecDeque,
     fs::{File, OpenOptions},
     io::{self, BufRead, BufReader, Seek},
-    path::PathBuf,
-    sync::mpsc,
+    path::{Path, PathBuf},
+    sync::Arc,
     thread,
 };
 
@@ -12,31 +13,110 @@ use std::os::unix::fs::MetadataExt;
 use std::os::windows::fs::MetadataExt;
 
 use anyhow::Context;
-use rand::{rngs::SmallRng, Rng, SeedableRng};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::{SourceConfig, TailStart, TAIL_SLEEP},
-    log_entry::{fake_entry, parse_line, LogEntry},
+    config::{IngestConfig, SourceConfig, TailStart, DRAIN_BATCH_LINES, TAIL_SLEEP},
+    log_entry::{parse_line, LogEntry},
+    mock::{MockGen, Scenario},
+    queue::{LineQueue, QueueStats},
+    record::{load_recording, RecordedBatch},
 };
 
 #[derive(Debug)]
 pub enum Ingest {
-    Mock(SmallRng),
-    Channel(mpsc::Receiver<String>),
+    Mock(Box<MockGen>),
+    Channel(Arc<LineQueue>),
+    Replay(Replay),
+}
+
+/// A recorded session fed back at its original arrival times.
+#[derive(Debug)]
+pub struct Replay {
+    batches: VecDeque<RecordedBatch>,
+    clock: DateTime<Local>,
+}
+
+/// A raw line, its arrival time and the entry parsed from it.
+#[derive(Debug)]
+pub struct Ingested {
+    pub at: DateTime<Local>,
+    pub raw: String,
+    pub entry: LogEntry,
+}
+
+impl Ingested {
+    fn parse(raw: String) -> Self {
+        let at = Local::now();
+        let entry = parse_line(&raw, at);
+        Self { at, raw, entry }
+    }
 }
 
 impl Ingest {
-    pub fn new(source: SourceConfig) -> Self {
-        match source {
-            SourceConfig::Mock => Ingest::Mock(SmallRng::seed_from_u64(42)),
-            SourceConfig::Stdin => Ingest::Channel(spawn_stdin_reader()),
-            SourceConfig::File { path, start } => Ingest::Channel(spawn_file_tail(path, start)),
+    pub fn new(source: SourceConfig, config: IngestConfig) -> anyhow::Result<Self> {
+        Ok(match source {
+            SourceConfig::Mock { scenario, seed } => {
+                let scenario = match scenario {
+                    Some(path) => Scenario::load(&path)?,
+                    None => Scenario::default(),
+                };
+                Ingest::Mock(Box::new(MockGen::new(scenario, seed)))
+            }
+            SourceConfig::Stdin => {
+                let queue = Arc::new(LineQueue::new(config.queue_capacity, config.drop_policy));
+                spawn_stdin_reader(queue.clone());
+                Ingest::Channel(queue)
+            }
+            SourceConfig::File {
+                path,
+                start,
+                preload,
+            } => {
+                let queue = Arc::new(LineQueue::new(config.queue_capacity, config.drop_policy));
+                spawn_file_tail(path, start, preload, queue.clone());
+                Ingest::Channel(queue)
+            }
+            SourceConfig::Replay(path) => {
+                let batches = load_recording(&path)?;
+                let clock = batches.front().map(|b| b.at).unwrap_or_else(Local::now);
+                Ingest::Replay(Replay { batches, clock })
+            }
+        })
+    }
+
+    /// Current time as seen by this source; replays run on the recorded clock.
+    pub fn now(&self) -> DateTime<Local> {
+        match self {
+            Ingest::Replay(replay) => replay.clock,
+            _ => Local::now(),
+        }
+    }
+
+    /// Tails another file into the same queue, starting at its end.
+    pub fn add_file(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        let Ingest::Channel(queue) = self else {
+            anyhow::bail!("sources can only be added while tailing a file or stdin");
+        };
+        anyhow::ensure!(path.is_file(), "{} is not a readable file", path.display());
+        spawn_file_tail(path, TailStart::End, Vec::new(), queue.clone());
+        Ok(())
+    }
+
+    /// Backlog and drop counters for sources fed by reader threads.
+    pub fn queue_stats(&self) -> Option<QueueStats> {
+        match self {
+            Ingest::Channel(queue) => Some(queue.stats()),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct FileId {
+/// Identity of a file that survives renames, so a rotated log can be told apart
+/// from the new file created at its old path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileId {
     #[cfg(unix)]
     dev: u64,
     #[cfg(unix)]
@@ -74,6 +154,26 @@ impl From<&File> for FileId {
 }
 
 impl FileId {
+    pub fn of_path(path: &Path) -> io::Result<Self> {
+        Ok(FileId::from(&File::open(path)?))
+    }
+
+    /// Short name for this identity, used for state file names.
+    pub fn key(&self) -> String {
+        #[cfg(unix)]
+        {
+            format!("{}-{}", self.dev, self.ino)
+        }
+        #[cfg(windows)]
+        {
+            format!("{}-{}", self.volume, self.file_index)
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            format!("len-{}", self.len)
+        }
+    }
+
     fn matches(&self, other: &File) -> bool {
         let other_id = FileId::from(other);
         #[cfg(unix)]
@@ -91,36 +191,68 @@ impl FileId {
     }
 }
 
-pub fn drain_ingest(ingest: &mut Ingest) -> Vec<LogEntry> {
+pub fn drain_ingest(ingest: &mut Ingest) -> Vec<Ingested> {
     match ingest {
-        Ingest::Mock(rng) => {
-            let count = rng.gen_range(0..=3);
-            (0..count).map(|_| fake_entry(rng)).collect()
-        }
-        Ingest::Channel(rx) => {
-            let mut entries = Vec::new();
-            while let Ok(line) = rx.try_recv() {
-                entries.push(parse_line(&line));
+        Ingest::Mock(gen) => {
+            let at = Local::now();
+            gen.generate(at)
+                .into_iter()
+                .map(|entry| Ingested {
+                    at,
+                    raw: entry.to_line(),
+                    entry,
+                })
+                .collect()
+        }
+        Ingest::Channel(queue) => queue.drain(DRAIN_BATCH_LINES),
+        Ingest::Replay(replay) => {
+            let mut lines = Vec::new();
+            while lines.len() < DRAIN_BATCH_LINES {
+                let Some(recorded) = replay.batches.pop_front() else {
+                    break;
+                };
+                replay.clock = recorded.at;
+                lines.extend(recorded.lines.into_iter().map(|raw| Ingested {
+                    at: recorded.at,
+                    entry: parse_line(&raw, recorded.at),
+                    raw,
+                }));
             }
-            entries
+            lines
         }
     }
 }
 
-fn spawn_stdin_reader() -> mpsc::Receiver<String> {
-    let (tx, rx) = mpsc::channel();
+fn spawn_stdin_reader(queue: Arc<LineQueue>) {
     thread::spawn(move || {
         let stdin = io::stdin();
         for line in stdin.lock().lines().map_while(Result::ok) {
-            let _ = tx.send(line);
+            queue.push(Ingested::parse(line));
         }
     });
-    rx
 }
 
-fn spawn_file_tail(path: PathBuf, start: TailStart) -> mpsc::Receiver<String> {
-    let (tx, rx) = mpsc::channel();
+/// Reads each `preload` file (rotated parts, oldest first) from its offset to
+/// the end, then tails `path`.
+fn spawn_file_tail(
+    path: PathBuf,
+    start: TailStart,
+    preload: Vec<(PathBuf, u64)>,
+    queue: Arc<LineQueue>,
+) {
     thread::spawn(move || {
+        for (part, offset) in preload {
+            let Ok(file) = File::open(&part) else {
+                continue;
+            };
+            let mut reader = BufReader::new(file);
+            if seek_to_line(&mut reader, offset).is_err() {
+                continue;
+            }
+            for line in reader.lines().map_while(Result::ok) {
+                queue.push(Ingested::parse(line));
+            }
+        }
         let mut reopen_start = start;
         loop {
             match open_reader(&path, reopen_start) {
@@ -136,7 +268,7 @@ fn spawn_file_tail(path: PathBuf, start: TailStart) -> mpsc::Receiver<String> {
                         Ok(n) => {
                             pos += n as u64;
                             let trimmed = line.trim_end_matches(&['\n', '\r'][..]).to_string();
-                            let _ = tx.send(trimmed);
+                            queue.push(Ingested::parse(trimmed));
                         }
                         Err(_) => {
                             break;
@@ -150,7 +282,6 @@ fn spawn_file_tail(path: PathBuf, start: TailStart) -> mpsc::Receiver<String> {
             reopen_start = TailStart::Beginning;
         }
     });
-    rx
 }
 
 fn open_reader(path: &PathBuf, start: TailStart) -> anyhow::Result<(BufReader<File>, u64, FileId)> {
@@ -169,10 +300,25 @@ fn open_reader(path: &PathBuf, start: TailStart) -> anyhow::Result<(BufReader<Fi
             .get_mut()
             .seek(io::SeekFrom::End(0))
             .context("seek to end")?,
+        TailStart::Offset(offset) => {
+            seek_to_line(&mut reader, offset).context("seek to saved history")?
+        }
     };
     Ok((reader, pos, file_id))
 }
 
+/// Positions `reader` at the first line starting at or after `offset`, so a
+/// read never begins mid-line; returns the new position.
+fn seek_to_line(reader: &mut BufReader<File>, offset: u64) -> io::Result<u64> {
+    if offset == 0 {
+        return reader.seek(io::SeekFrom::Start(0));
+    }
+    let mut pos = reader.seek(io::SeekFrom::Start(offset - 1))?;
+    let mut partial = Vec::new();
+    pos += reader.read_until(b'\n', &mut partial)? as u64;
+    Ok(pos)
+}
+
 fn should_reopen(path: &PathBuf, pos: u64, file_id: &FileId) -> bool {
     if let Ok(file) = OpenOptions::new().read(true).open(path) {
         if !file_id.matches(&file) {
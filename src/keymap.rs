@@ -0,0 +1,584 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Everything a key can do in the log view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    GoLive,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollTop,
+    TimelineBack,
+    TimelineForward,
+    ToggleInfo,
+    ToggleWarn,
+    ToggleError,
+    NextError,
+    PrevError,
+    Filter,
+    ClearFilters,
+    ToggleRegex,
+    CycleCase,
+    ToggleWord,
+    Presets,
+    GotoTime,
+    TimeRange,
+    LessContext,
+    MoreContext,
+    Collapse,
+    ExpandGroup,
+    Templates,
+    FollowCorrelation,
+    Wrap,
+    ScrollLeft,
+    ScrollRight,
+    LineStart,
+    LineEnd,
+    CycleLayout,
+    Visual,
+    Yank,
+    YankJson,
+    Cancel,
+    AddBookmark,
+    NextBookmark,
+    PrevBookmark,
+    BookmarkList,
+    Command,
+    Export,
+    Help,
+}
+
+pub struct ActionInfo {
+    pub action: Action,
+    /// Name used in the `[keys]` table.
+    pub name: &'static str,
+    pub help: &'static str,
+    pub defaults: &'static [&'static str],
+}
+
+/// Every action in help order, with its default keys.
+pub const ACTIONS: &[ActionInfo] = &[
+    info(Action::Quit, "quit", "quit", &["q", "ctrl-c"]),
+    info(
+        Action::TogglePause,
+        "toggle-pause",
+        "pause / resume",
+        &["space"],
+    ),
+    info(Action::GoLive, "go-live", "go live", &["g", "end"]),
+    info(
+        Action::ScrollUp,
+        "scroll-up",
+        "scroll up (pauses)",
+        &["up", "k"],
+    ),
+    info(
+        Action::ScrollDown,
+        "scroll-down",
+        "scroll down",
+        &["down", "j"],
+    ),
+    info(Action::PageUp, "page-up", "page up", &["pageup"]),
+    info(Action::PageDown, "page-down", "page down", &["pagedown"]),
+    info(Action::ScrollTop, "scroll-top", "oldest line", &["home"]),
+    info(
+        Action::TimelineBack,
+        "timeline-back",
+        "timeline cursor older",
+        &["left"],
+    ),
+    info(
+        Action::TimelineForward,
+        "timeline-forward",
+        "timeline cursor newer",
+        &["right"],
+    ),
+    info(Action::ToggleInfo, "toggle-info", "toggle INFO", &["1"]),
+    info(Action::ToggleWarn, "toggle-warn", "toggle WARN", &["2"]),
+    info(Action::ToggleError, "toggle-error", "toggle ERROR", &["3"]),
+    info(Action::NextError, "next-error", "next error", &["n"]),
+    info(Action::PrevError, "prev-error", "previous error", &["p"]),
+    info(
+        Action::Filter,
+        "filter",
+        "type a filter (Enter keep, Esc restore, Up/Down history)",
+        &["/"],
+    ),
+    info(
+        Action::ClearFilters,
+        "clear-filters",
+        "clear filters",
+        &["F", "C"],
+    ),
+    info(
+        Action::ToggleRegex,
+        "toggle-regex",
+        "regex filter on/off",
+        &["R"],
+    ),
+    info(
+        Action::CycleCase,
+        "cycle-case",
+        "case: smart -> match -> ignore",
+        &["I"],
+    ),
+    info(
+        Action::ToggleWord,
+        "toggle-word",
+        "whole-word filter on/off",
+        &["W"],
+    ),
+    info(Action::Presets, "presets", "filter preset picker", &["P"]),
+    info(
+        Action::GotoTime,
+        "goto-time",
+        "go to a time (12:03:10, -5m)",
+        &["G"],
+    ),
+    info(
+        Action::TimeRange,
+        "time-range",
+        "time range (-10m.. or 12:00..12:05)",
+        &["T"],
+    ),
+    info(
+        Action::LessContext,
+        "less-context",
+        "fewer context lines",
+        &["("],
+    ),
+    info(
+        Action::MoreContext,
+        "more-context",
+        "more context lines (-- marks gaps)",
+        &[")"],
+    ),
+    info(
+        Action::Collapse,
+        "collapse",
+        "collapse repeated lines (\u{d7}N)",
+        &["z"],
+    ),
+    info(
+        Action::ExpandGroup,
+        "expand-group",
+        "expand/fold the selected group",
+        &["o"],
+    ),
+    info(
+        Action::Templates,
+        "templates",
+        "message templates (Enter filters, s sorts)",
+        &["t"],
+    ),
+    info(
+        Action::FollowCorrelation,
+        "follow-correlation",
+        "follow the line's req/trace id (again: next, then off)",
+        &["x"],
+    ),
+    info(Action::Wrap, "wrap", "wrap long lines", &["w"]),
+    info(
+        Action::ScrollLeft,
+        "scroll-left",
+        "scroll sideways left (wrap off)",
+        &["h"],
+    ),
+    info(
+        Action::ScrollRight,
+        "scroll-right",
+        "scroll sideways right (wrap off)",
+        &["l"],
+    ),
+    info(
+        Action::LineStart,
+        "line-start",
+        "jump to line start",
+        &["0"],
+    ),
+    info(Action::LineEnd, "line-end", "jump to line end", &["$"]),
+    info(
+        Action::CycleLayout,
+        "cycle-layout",
+        "cycle column layouts",
+        &["L"],
+    ),
+    info(
+        Action::Visual,
+        "visual",
+        "visual selection (move to extend)",
+        &["v"],
+    ),
+    info(
+        Action::Yank,
+        "yank",
+        "copy selection as lines (OSC 52)",
+        &["y"],
+    ),
+    info(
+        Action::YankJson,
+        "yank-json",
+        "copy selection as JSON lines",
+        &["Y"],
+    ),
+    info(
+        Action::Cancel,
+        "cancel",
+        "drop the visual selection",
+        &["esc"],
+    ),
+    info(
+        Action::AddBookmark,
+        "add-bookmark",
+        "bookmark the selected line (label #tag -- note)",
+        &["b"],
+    ),
+    info(
+        Action::NextBookmark,
+        "next-bookmark",
+        "next bookmark",
+        &["]"],
+    ),
+    info(
+        Action::PrevBookmark,
+        "prev-bookmark",
+        "previous bookmark",
+        &["["],
+    ),
+    info(
+        Action::BookmarkList,
+        "bookmark-list",
+        "bookmark list (Enter jump, r rename, t tag, d delete)",
+        &["B"],
+    ),
+    info(
+        Action::Command,
+        "command",
+        "command line (Tab completes)",
+        &[":"],
+    ),
+    info(
+        Action::Export,
+        "export",
+        "export matches (:export PATH ...)",
+        &["E"],
+    ),
+    info(Action::Help, "help", "this help", &["?"]),
+];
+
+const fn info(
+    action: Action,
+    name: &'static str,
+    help: &'static str,
+    defaults: &'static [&'static str],
+) -> ActionInfo {
+    ActionInfo {
+        action,
+        name,
+        help,
+        defaults,
+    }
+}
+
+impl Action {
+    pub fn info(self) -> &'static ActionInfo {
+        ACTIONS
+            .iter()
+            .find(|info| info.action == self)
+            .expect("every action is listed in ACTIONS")
+    }
+}
+
+/// A key plus modifiers, written like `ctrl-d`, `space`, `G` or `pagedown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Shift is folded into the character itself, so `G` and `shift-g` are the same chord.
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) => {
+                let shifted = modifiers.contains(KeyModifiers::SHIFT);
+                let c = if shifted { c.to_ascii_uppercase() } else { c };
+                let modifiers = modifiers - KeyModifiers::SHIFT;
+                // Terminals report ctrl-C as ctrl-c.
+                let c = if modifiers.contains(KeyModifiers::CONTROL) {
+                    c.to_ascii_lowercase()
+                } else {
+                    c
+                };
+                Self {
+                    code: KeyCode::Char(c),
+                    modifiers,
+                }
+            }
+            code => Self { code, modifiers },
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec.trim();
+        // A trailing `-` is the minus key itself, as in `ctrl--`.
+        while let Some((prefix, tail)) = rest.split_once('-').filter(|(_, tail)| !tail.is_empty()) {
+            modifiers |= match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {prefix:?} in {spec:?}")),
+            };
+            rest = tail;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "insert" => KeyCode::Insert,
+                "delete" | "del" => KeyCode::Delete,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key {rest:?} in {spec:?}")),
+                },
+            },
+        };
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => write!(f, "\u{2191}"),
+            KeyCode::Down => write!(f, "\u{2193}"),
+            KeyCode::Left => write!(f, "\u{2190}"),
+            KeyCode::Right => write!(f, "\u{2192}"),
+            KeyCode::PageUp => write!(f, "pgup"),
+            KeyCode::PageDown => write!(f, "pgdn"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            other => write!(f, "{}", format!("{other:?}").to_ascii_lowercase()),
+        }
+    }
+}
+
+/// Active bindings: the defaults in `ACTIONS`, changed by the `[keys]` config table.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|info| {
+                info.defaults.iter().map(|spec| {
+                    let chord: KeyChord = spec.parse().expect("default keys parse");
+                    (chord, info.action)
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Applies `chord = "action"` entries (`"none"` unbinds), reporting bad entries,
+    /// chords written more than once (e.g. `G` and `shift-g`) and bindings that
+    /// take the last key of another action, which keeps that key.
+    pub fn with_overrides(keys: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut errors = Vec::new();
+        let mut seen: HashMap<KeyChord, &str> = HashMap::new();
+        let mut taken: Vec<(&str, KeyChord, Action)> = Vec::new();
+        for (spec, name) in keys {
+            let chord = match spec.parse::<KeyChord>() {
+                Ok(chord) => chord,
+                Err(err) => {
+                    errors.push(format!("keys: {err}"));
+                    continue;
+                }
+            };
+            if let Some(first) = seen.insert(chord, spec) {
+                errors.push(format!(
+                    "keys: {spec:?} and {first:?} are the same key; keeping {first:?}"
+                ));
+                seen.insert(chord, first);
+                continue;
+            }
+            match name.as_str() {
+                "none" => {
+                    keymap.bindings.remove(&chord);
+                }
+                name => match ACTIONS.iter().find(|info| info.name == name) {
+                    Some(info) => {
+                        if let Some(previous) = keymap.bindings.insert(chord, info.action) {
+                            if previous != info.action {
+                                taken.push((spec, chord, previous));
+                            }
+                        }
+                    }
+                    None => errors.push(format!("keys: unknown action {name:?} for {spec:?}")),
+                },
+            }
+        }
+        for (spec, chord, previous) in taken {
+            if !keymap.bindings.values().any(|bound| *bound == previous) {
+                keymap.bindings.insert(chord, previous);
+                errors.push(format!(
+                    "keys: {spec:?} is the only key left for {}; bind it elsewhere first",
+                    previous.info().name
+                ));
+            }
+        }
+        (keymap, errors)
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyChord::from(key)).copied()
+    }
+
+    /// Action bound to a plain character key, if any.
+    pub fn char_action(&self, c: char) -> Option<Action> {
+        self.bindings
+            .get(&KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE))
+            .copied()
+    }
+
+    /// Keys bound to `action`, e.g. `q/ctrl-c`, or `unbound`.
+    pub fn keys(&self, action: Action) -> String {
+        let mut chords: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(chord, _)| chord.to_string())
+            .collect();
+        if chords.is_empty() {
+            return "unbound".to_string();
+        }
+        // Defaults first, in their listed order, then anything added in the config.
+        let defaults = action.info().defaults;
+        chords.sort_by_key(|chord| {
+            let listed = defaults
+                .iter()
+                .position(|d| d.parse::<KeyChord>().is_ok_and(|d| d.to_string() == *chord));
+            (listed.unwrap_or(usize::MAX), chord.clone())
+        });
+        chords.join("/")
+    }
+
+    /// `(keys, description)` for every action, in help order.
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        ACTIONS
+            .iter()
+            .map(|info| (self.keys(info.action), info.help))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn overrides_rebind_unbind_and_report_conflicts() {
+        let keys: BTreeMap<String, String> = [
+            ("ctrl-d", "page-down"),
+            ("C-D", "page-up"),
+            ("q", "none"),
+            ("shift-x", "export"),
+            ("f13", "quit"),
+            ("z", "fly"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let (keymap, errors) = Keymap::with_overrides(&keys);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors.iter().any(|e| e.contains("same key")));
+
+        let ctrl_d = key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        // "C-D" sorts first in the table, so it wins and "ctrl-d" is the duplicate.
+        assert_eq!(keymap.action(&ctrl_d), Some(Action::PageUp));
+        assert_eq!(keymap.char_action('q'), None);
+        assert_eq!(keymap.keys(Action::Quit), "ctrl-c");
+        let shift_x = key(KeyCode::Char('X'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&shift_x), Some(Action::Export));
+        assert_eq!(keymap.keys(Action::Export), "E/X");
+        assert_eq!(keymap.keys(Action::ScrollUp), "\u{2191}/k");
+        assert_eq!(keymap.keys(Action::Collapse), "z");
+    }
+
+    #[test]
+    fn taking_the_last_key_of_an_action_is_reported() {
+        let overrides = |pairs: &[(&str, &str)]| {
+            let keys: BTreeMap<String, String> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            Keymap::with_overrides(&keys)
+        };
+        let (keymap, errors) = overrides(&[("n", "wrap")]);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("next-error"));
+        assert_eq!(keymap.char_action('n'), Some(Action::NextError));
+
+        let (keymap, errors) = overrides(&[("n", "wrap"), ("ctrl-n", "next-error")]);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(keymap.char_action('n'), Some(Action::Wrap));
+        assert_eq!(keymap.keys(Action::NextError), "ctrl-n");
+
+        let (keymap, errors) = overrides(&[("q", "help")]);
+        assert!(errors.is_empty(), "quit keeps ctrl-c: {errors:?}");
+        assert_eq!(keymap.char_action('q'), Some(Action::Help));
+    }
+}
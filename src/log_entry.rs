@@ -1,8 +1,11 @@
+use std::borrow::Cow;
+
 use chrono::{DateTime, Local};
-use rand::{rngs::SmallRng, Rng};
 use serde::Deserialize;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::correlate::scalar_text;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Level {
     Info,
     Warn,
@@ -18,11 +21,13 @@ impl Level {
         }
     }
 
-    pub fn color(self) -> ratatui::style::Color {
-        match self {
-            Level::Info => ratatui::style::Color::White,
-            Level::Warn => ratatui::style::Color::Yellow,
-            Level::Error => ratatui::style::Color::Red,
+    /// Accepts the common spellings found in log lines (`warning`, `err`, `fatal`, ...).
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.to_ascii_uppercase().as_str() {
+            "INFO" => Some(Level::Info),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "ERROR" | "ERR" | "FATAL" => Some(Level::Error),
+            _ => None,
         }
     }
 }
@@ -33,6 +38,12 @@ pub struct LogEntry {
     pub level: Level,
     pub target: String,
     pub message: String,
+    /// Scalar fields of a JSON line besides its timestamp, level, target and
+    /// message, in key order; empty for plain-text lines.
+    pub fields: Vec<(String, String)>,
+    /// The line as read, kept when `to_line` would not give it back (JSON lines,
+    /// missing timestamps, other spacing).
+    pub raw: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,28 +62,54 @@ struct JsonLog {
     msg: Option<String>,
     #[serde(default)]
     message: Option<String>,
+    /// Any other fields; scalar ones are kept in `LogEntry::fields`.
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
-pub fn parse_line(line: &str) -> LogEntry {
-    if let Some(entry) = parse_json_log(line) {
-        return entry;
+impl LogEntry {
+    /// Renders the entry back into the plain-text format `parse_line` understands.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, false),
+            self.level.label(),
+            self.target,
+            self.message
+        )
     }
 
+    /// The line as it was read, or its plain-text form for generated entries.
+    pub fn raw_line(&self) -> Cow<'_, str> {
+        match &self.raw {
+            Some(raw) => Cow::Borrowed(raw),
+            None => Cow::Owned(self.to_line()),
+        }
+    }
+}
+
+/// Parses a raw line; `fallback` is used when the line carries no usable timestamp.
+pub fn parse_line(line: &str, fallback: DateTime<Local>) -> LogEntry {
+    let mut entry =
+        parse_json_log(line, fallback).unwrap_or_else(|| parse_plain_log(line, fallback));
+    if entry.to_line() != line {
+        entry.raw = Some(line.to_string());
+    }
+    entry
+}
+
+fn parse_plain_log(line: &str, fallback: DateTime<Local>) -> LogEntry {
     let mut parts = line.split_whitespace();
     let timestamp = parts
         .next()
         .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
         .map(|dt| dt.with_timezone(&Local))
-        .unwrap_or_else(Local::now);
+        .unwrap_or(fallback);
 
     let level = parts
         .next()
-        .and_then(|lvl| match lvl.to_ascii_uppercase().as_str() {
-            "INFO" => Some(Level::Info),
-            "WARN" | "WARNING" => Some(Level::Warn),
-            "ERROR" | "ERR" | "FATAL" => Some(Level::Error),
-            _ => None,
-        })
+        .and_then(Level::from_name)
         .unwrap_or(Level::Info);
 
     let target = parts.next().unwrap_or("log").to_string();
@@ -83,10 +120,12 @@ pub fn parse_line(line: &str) -> LogEntry {
         level,
         target,
         message,
+        fields: Vec::new(),
+        raw: None,
     }
 }
 
-fn parse_json_log(line: &str) -> Option<LogEntry> {
+fn parse_json_log(line: &str, fallback: DateTime<Local>) -> Option<LogEntry> {
     let json: JsonLog = serde_json::from_str(line).ok()?;
     let timestamp = json
         .timestamp
@@ -95,83 +134,42 @@ fn parse_json_log(line: &str) -> Option<LogEntry> {
         .or(json.time.as_deref())
         .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
         .map(|dt| dt.with_timezone(&Local))
-        .unwrap_or_else(Local::now);
+        .unwrap_or(fallback);
     let level = json
         .level
         .as_deref()
-        .and_then(|lvl| match lvl.to_ascii_uppercase().as_str() {
-            "INFO" => Some(Level::Info),
-            "WARN" | "WARNING" => Some(Level::Warn),
-            "ERROR" | "ERR" | "FATAL" => Some(Level::Error),
-            _ => None,
-        })
+        .and_then(Level::from_name)
         .unwrap_or(Level::Info);
     let target = json.target.unwrap_or_else(|| "log".to_string());
     let message = json
         .message
         .or(json.msg)
         .unwrap_or_else(|| "<missing>".to_string());
+    let fields = json
+        .extra
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), scalar_text(value)?)))
+        .collect();
     Some(LogEntry {
         timestamp,
         level,
         target,
         message,
+        fields,
+        raw: None,
     })
 }
 
-pub fn fake_entry(rng: &mut SmallRng) -> LogEntry {
-    let level_roll: u8 = rng.gen_range(0..100);
-    let level = match level_roll {
-        0..=65 => Level::Info,
-        66..=88 => Level::Warn,
-        _ => Level::Error,
-    };
-    let target = COMPONENTS[rng.gen_range(0..COMPONENTS.len())];
-    let base_msg = match level {
-        Level::Info => INFO_MESSAGES[rng.gen_range(0..INFO_MESSAGES.len())],
-        Level::Warn => WARN_MESSAGES[rng.gen_range(0..WARN_MESSAGES.len())],
-        Level::Error => ERROR_MESSAGES[rng.gen_range(0..ERROR_MESSAGES.len())],
-    };
-    let detail_id: u16 = rng.gen_range(1000..9999);
-    let message = format!("{base_msg} target={target} req={detail_id}");
-    LogEntry {
-        timestamp: Local::now(),
-        level,
-        target: target.to_string(),
-        message,
-    }
-}
-
-const COMPONENTS: &[&str] = &["http", "db", "cache", "worker", "auth", "search"];
-const INFO_MESSAGES: &[&str] = &[
-    "GET /health 200",
-    "job completed successfully",
-    "cache warm completed",
-    "user session refreshed",
-    "metrics flushed",
-];
-const WARN_MESSAGES: &[&str] = &[
-    "cache miss rate spiked",
-    "retrying request",
-    "slow query detected",
-    "upstream took too long",
-    "backoff applied",
-];
-const ERROR_MESSAGES: &[&str] = &[
-    "database transaction deadlock",
-    "timeout talking to upstream",
-    "panic in worker thread",
-    "failed to commit offset",
-    "permission denied accessing key",
-];
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn parse_line_understands_timestamp_and_level() {
-        let entry = parse_line("2024-12-17T12:00:00Z ERROR db deadlock retry txn=7 attempt=1");
+        let entry = parse_line(
+            "2024-12-17T12:00:00Z ERROR db deadlock retry txn=7 attempt=1",
+            Local::now(),
+        );
         assert_eq!(entry.level, Level::Error);
         assert_eq!(entry.target, "db");
         assert!(entry.message.contains("deadlock"));
@@ -180,12 +178,49 @@ mod tests {
     #[test]
     fn json_parser_ignores_message_for_timestamp() {
         let before = Local::now();
-        let entry = parse_line(r#"{"level":"warn","target":"api","msg":"hello world"}"#);
+        let entry = parse_line(
+            r#"{"level":"warn","target":"api","msg":"hello world"}"#,
+            Local::now(),
+        );
         let after = Local::now();
         assert_eq!(entry.level, Level::Warn);
         assert_eq!(entry.target, "api");
         assert_eq!(entry.message, "hello world");
         // Timestamp should fall within the parse window (i.e., defaulted to now)
         assert!(entry.timestamp >= before && entry.timestamp <= after);
+
+        let entry = parse_line(
+            r#"{"msg":"charged","trace_id":"ab12","amount":3,"note":"two words","tags":[1]}"#,
+            Local::now(),
+        );
+        assert_eq!(entry.message, "charged");
+        let fields: Vec<_> = entry
+            .fields
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect();
+        assert_eq!(fields, vec!["amount=3", "note=two words", "trace_id=ab12"]);
+        assert!(entry.raw_line().starts_with(r#"{"msg":"charged""#));
+    }
+
+    #[test]
+    fn to_line_round_trips_through_parser() {
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: Level::Warn,
+            target: "cache".to_string(),
+            message: "cache miss rate spiked req=4821".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let parsed = parse_line(&entry.to_line(), Local::now());
+        assert_eq!(parsed.timestamp, entry.timestamp);
+        assert_eq!(parsed.level, entry.level);
+        assert_eq!(parsed.target, entry.target);
+        assert_eq!(parsed.message, entry.message);
+        assert_eq!(
+            parsed.raw, None,
+            "lines that round-trip are not stored twice"
+        );
     }
 }
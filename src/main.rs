@@ -1,9 +1,27 @@
 mod app;
 mod baseline;
+mod bookmarks;
+mod clipboard;
+mod collapse;
+mod columns;
+mod commands;
 mod config;
+mod correlate;
+mod export;
 mod filters;
+mod highlight;
+mod history;
 mod ingest;
+mod keymap;
 mod log_entry;
+mod mock;
+mod queue;
+mod record;
+mod saved_filters;
+mod session;
+mod templates;
+mod text_index;
+mod theme;
 mod timeline;
 mod ui;
 
@@ -11,58 +29,169 @@ use std::time::Duration;
 
 use anyhow::Result;
 use clap::Parser;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
 
-use crate::config::{AppConfig, Args, SourceConfig, TailStart, TICK_RATE};
+use crate::{
+    config::{
+        AppConfig, Args, SourceConfig, TailStart, H_SCROLL_STEP, MOUSE_SCROLL_LINES, TICK_RATE,
+    },
+    keymap::Action,
+};
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let app_cfg = AppConfig::load(&args);
-    let source = if args.stdin {
+    let mut session = None;
+    let mut restored = None;
+    let mut session_error = None;
+    let mut history_truncated = false;
+    let source = if let Some(path) = args.replay.clone() {
+        SourceConfig::Replay(path)
+    } else if args.stdin {
         SourceConfig::Stdin
-    } else if let Some(file) = args.file {
-        SourceConfig::File {
-            path: file,
-            start: TailStart::End,
+    } else if let Some(file) = args.file.clone() {
+        if let (Some(dir), false) = (&app_cfg.state_dir, args.fresh) {
+            match session::FileSession::open(dir, &file) {
+                Ok((opened, state)) => (session, restored) = (Some(opened), state),
+                Err(err) => session_error = Some(format!("Saved state not loaded: {err:#}")),
+            }
+        }
+        // Saved bookmarks or a saved position need the lines already in the
+        // file (and in rotated parts holding bookmarks), not just new ones. Only
+        // half the memory budget is read back, leaving room for new lines.
+        let preload = match (&session, &restored) {
+            (Some(session), Some(state)) if state.needs_history() => {
+                Some(session.preload(state, app_cfg.max_bytes as u64 / 2))
+            }
+            _ => None,
+        };
+        history_truncated = preload.as_ref().is_some_and(|p| p.truncated);
+        match preload {
+            Some(preload) => SourceConfig::File {
+                start: match preload.start {
+                    0 => TailStart::Beginning,
+                    offset => TailStart::Offset(offset),
+                },
+                preload: preload.parts,
+                path: file,
+            },
+            None => SourceConfig::File {
+                start: TailStart::End,
+                preload: Vec::new(),
+                path: file,
+            },
         }
     } else {
-        SourceConfig::Mock
+        SourceConfig::Mock {
+            scenario: args.scenario.clone(),
+            seed: args.seed,
+        }
     };
 
-    let ingest = ingest::Ingest::new(source.clone());
+    let ingest = ingest::Ingest::new(source.clone(), app_cfg.ingest)?;
+    if args.emit {
+        if let ingest::Ingest::Mock(gen) = ingest {
+            return mock::emit(*gen, &mut std::io::stdout().lock());
+        }
+        anyhow::bail!("--emit only works with the mock feed");
+    }
     let mut app = app::App::new(
         ingest,
         app_cfg.max_lines,
         source.label(),
         app_cfg.baseline.clone(),
     );
+    app.set_max_bytes(app_cfg.max_bytes);
+    app.set_presets(app_cfg.presets.clone());
+    let context = args.context.unwrap_or(0);
+    app.set_context(
+        args.before_context.unwrap_or(context),
+        args.after_context.unwrap_or(context),
+    );
+    if args.since.is_some() || args.until.is_some() {
+        let (since, until) =
+            config::time_range(args.since.as_deref(), args.until.as_deref(), app.now())
+                .map_err(|err| anyhow::anyhow!("--since/--until: {err}"))?;
+        app.set_time_range(since, until);
+    }
+    app.set_highlighter(app_cfg.highlighter.clone());
+    app.set_correlation_fields(app_cfg.correlation_fields.clone());
+    app.set_layouts(app_cfg.layouts.clone());
+    app.set_clipboard_mode(app_cfg.clipboard);
+    app.set_keymap(app_cfg.keymap.clone());
+    app.set_theme(app_cfg.theme.clone());
+    if let Some(err) = app_cfg.config_errors.first() {
+        app.set_notice(format!("Skipped invalid config entry: {err}"));
+    }
+    if let Some(path) = &app_cfg.filter_history {
+        match saved_filters::FilterHistory::load(path) {
+            Ok(history) => app.set_filter_history(history),
+            Err(err) => app.set_notice(format!("Filter history not loaded: {err:#}")),
+        }
+    }
+    if let Some(dir) = &app_cfg.spill_dir {
+        app.enable_spill(dir)?;
+    }
+    if let Some(path) = &args.tee {
+        app.start_tee(path)?;
+    }
+    if let Some(state) = restored {
+        app.restore_session(state, history_truncated);
+    }
+    if let Some(err) = session_error {
+        app.set_notice(err);
+    }
+    for line in &app_cfg.startup {
+        if let Err(err) = app.run_command(line) {
+            app.set_notice(format!("Startup command {line:?} failed: {err}"));
+        }
+    }
 
     let mut terminal = ui::setup_terminal()?;
     let result = run(&mut terminal, &mut app);
     ui::restore_terminal(&mut terminal)?;
+    app.finish_tee()?;
     if result.is_ok() {
         if let Some(path) = app.baseline_target() {
             app.save_baseline(path)?;
         }
+        if let Some(path) = &app_cfg.filter_history {
+            app.filter_history().save(path)?;
+        }
+        if let Some(session) = &session {
+            session.save(app.session_state())?;
+        }
     }
     result
 }
 
 fn run(terminal: &mut ui::Term, app: &mut app::App) -> Result<()> {
     loop {
-        terminal.draw(|frame| ui::draw(frame, app))?;
+        let mut view = app::ViewLayout::default();
+        terminal.draw(|frame| view = ui::draw(frame, app))?;
+        app.set_view_layout(view);
 
         let timeout = TICK_RATE
             .checked_sub(app.last_tick().elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                if !app.show_help && matches!(app.input_mode(), filters::InputMode::Normal) {
+                    handle_mouse(app, mouse);
+                }
+                continue;
+            }
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
                     if app.show_help {
-                        match key.code {
-                            KeyCode::Char('?') | KeyCode::Esc => app.show_help = false,
-                            _ => {}
+                        if key.code == KeyCode::Esc
+                            || app.keymap().action(&key) == Some(Action::Help)
+                        {
+                            app.show_help = false;
                         }
                         continue;
                     }
@@ -74,6 +203,13 @@ fn run(terminal: &mut ui::Term, app: &mut app::App) -> Result<()> {
                             }
                         }
                         filters::InputMode::FilterText(_) => handle_filter_key(app, key),
+                        filters::InputMode::PresetPicker(_) => handle_preset_key(app, key),
+                        filters::InputMode::Templates(_) => handle_template_key(app, key),
+                        filters::InputMode::Bookmarks(_) => handle_bookmark_key(app, key),
+                        filters::InputMode::GotoTime(_)
+                        | filters::InputMode::TimeRange(_)
+                        | filters::InputMode::Command(_)
+                        | filters::InputMode::BookmarkEdit(..) => handle_prompt_key(app, key),
                     }
                 }
             }
@@ -86,63 +222,166 @@ fn run(terminal: &mut ui::Term, app: &mut app::App) -> Result<()> {
 }
 
 fn handle_normal_key(app: &mut app::App, key: crossterm::event::KeyEvent) -> Result<bool> {
-    match key.code {
-        KeyCode::Char('q') | KeyCode::Char('c')
-            if key.modifiers.contains(KeyModifiers::CONTROL) =>
-        {
-            return Ok(true)
-        }
-        KeyCode::Char('q') => return Ok(true),
-        KeyCode::Char(' ') => app.toggle_pause(),
-        KeyCode::Char('g') => app.go_live(),
-        KeyCode::Char('r') => app.go_live(),
-        KeyCode::Up | KeyCode::Char('k') => app.scroll_up(1),
-        KeyCode::Down | KeyCode::Char('j') => app.scroll_down(1),
-        KeyCode::PageUp => app.scroll_up(8),
-        KeyCode::PageDown => app.scroll_down(8),
-        KeyCode::Home => app.scroll_up(app.filtered_len()),
-        KeyCode::End => app.go_live(),
-        KeyCode::Left => app.move_timeline_cursor(1),
-        KeyCode::Right => app.move_timeline_cursor(-1),
-        KeyCode::Char('1') => app.toggle_level(log_entry::Level::Info),
-        KeyCode::Char('2') => app.toggle_level(log_entry::Level::Warn),
-        KeyCode::Char('3') => app.toggle_level(log_entry::Level::Error),
-        KeyCode::Char('/') => {
-            let seed = app.filters().text.clone().unwrap_or_default();
-            app.set_input_mode(filters::InputMode::FilterText(seed));
-        }
-        KeyCode::Char('F') | KeyCode::Char('c') | KeyCode::Char('C') => app.clear_filters(),
-        KeyCode::Char('R') => app.set_regex_mode(!app.filters().regex_mode),
-        KeyCode::Char('n') => app.jump_error(1),
-        KeyCode::Char('p') => app.jump_error(-1),
-        KeyCode::Char('b') => app.add_bookmark(),
-        KeyCode::Char(']') => app.jump_bookmark(1),
-        KeyCode::Char('[') => app.jump_bookmark(-1),
-        KeyCode::Char('?') => app.show_help = !app.show_help,
-        _ => {}
+    let Some(action) = app.keymap().action(&key) else {
+        if let KeyCode::Char(c) = key.code {
+            app.apply_preset_key(c);
+        }
+        return Ok(false);
+    };
+    match action {
+        Action::Quit => return Ok(true),
+        Action::TogglePause => app.toggle_pause(),
+        Action::GoLive => app.go_live(),
+        Action::ScrollUp => app.scroll_up(1),
+        Action::ScrollDown => app.scroll_down(1),
+        Action::PageUp => app.scroll_up(8),
+        Action::PageDown => app.scroll_down(8),
+        Action::ScrollTop => app.scroll_up(app.filtered_len()),
+        Action::TimelineBack => app.move_timeline_cursor(1),
+        Action::TimelineForward => app.move_timeline_cursor(-1),
+        Action::ToggleInfo => app.toggle_level(log_entry::Level::Info),
+        Action::ToggleWarn => app.toggle_level(log_entry::Level::Warn),
+        Action::ToggleError => app.toggle_level(log_entry::Level::Error),
+        Action::NextError => app.jump_error(1),
+        Action::PrevError => app.jump_error(-1),
+        Action::Filter => app.begin_filter_edit(),
+        Action::ClearFilters => app.clear_filters(),
+        Action::ToggleRegex => app.set_regex_mode(!app.filters().regex_mode),
+        Action::CycleCase => app.cycle_case_mode(),
+        Action::ToggleWord => app.toggle_whole_word(),
+        Action::Presets => app.open_preset_picker(),
+        Action::GotoTime => app.begin_goto_edit(),
+        Action::TimeRange => app.begin_time_range_edit(),
+        Action::LessContext => app.adjust_context(-1),
+        Action::MoreContext => app.adjust_context(1),
+        Action::Collapse => app.toggle_collapse(),
+        Action::ExpandGroup => app.toggle_selected_group(),
+        Action::Templates => app.open_template_panel(),
+        Action::FollowCorrelation => app.follow_correlation(),
+        Action::Wrap => app.toggle_wrap(),
+        Action::ScrollLeft => app.scroll_horizontal(-H_SCROLL_STEP),
+        Action::ScrollRight => app.scroll_horizontal(H_SCROLL_STEP),
+        Action::LineStart => app.scroll_line_start(),
+        Action::LineEnd => app.scroll_line_end(),
+        Action::CycleLayout => app.cycle_layout(),
+        Action::Visual => app.toggle_visual(),
+        Action::Yank => app.yank(false),
+        Action::YankJson => app.yank(true),
+        Action::Cancel => app.cancel_visual(),
+        Action::AddBookmark => app.begin_bookmark_edit(),
+        Action::BookmarkList => app.open_bookmark_panel(),
+        Action::NextBookmark => app.jump_bookmark(1),
+        Action::PrevBookmark => app.jump_bookmark(-1),
+        Action::Command => app.begin_command_edit(""),
+        Action::Export => app.begin_command_edit("export "),
+        Action::Help => app.show_help = !app.show_help,
     }
     Ok(false)
 }
 
-fn handle_filter_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
-    match key.code {
-        KeyCode::Esc => app.set_input_mode(filters::InputMode::Normal),
-        KeyCode::Enter => {
-            let mode = std::mem::replace(app.input_mode_mut(), filters::InputMode::Normal);
-            if let filters::InputMode::FilterText(buf) = mode {
-                let text = if buf.is_empty() { None } else { Some(buf) };
-                app.set_filter_text(text);
+fn handle_mouse(app: &mut app::App, mouse: MouseEvent) {
+    let (x, y) = (mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::ScrollUp => app.scroll_up(MOUSE_SCROLL_LINES),
+        MouseEventKind::ScrollDown => app.scroll_down(MOUSE_SCROLL_LINES),
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let Some(level) = app.view().level_at(x, y) {
+                app.toggle_level(level);
+            } else if let Some(bin) = app.view().timeline_bin_at(x, y) {
+                app.seek_timeline(bin);
+            } else if let Some(index) = app.view().row_at(x, y) {
+                app.select_row(index);
+            }
+        }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            if let Some(bin) = app.view().timeline_bin_at(x, y) {
+                app.seek_timeline(bin);
             }
         }
+        _ => {}
+    }
+}
+
+fn handle_filter_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.cancel_filter_edit(),
+        KeyCode::Enter => app.commit_filter_edit(),
+        KeyCode::Up => app.step_filter_history(true),
+        KeyCode::Down => app.step_filter_history(false),
         KeyCode::Backspace => {
             if let filters::InputMode::FilterText(buf) = app.input_mode_mut() {
                 buf.pop();
             }
+            app.preview_filter_edit();
         }
         KeyCode::Char(c) => {
             if let filters::InputMode::FilterText(buf) = app.input_mode_mut() {
                 buf.push(c);
             }
+            app.preview_filter_edit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_preset_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('P') => app.close_input(),
+        KeyCode::Enter => app.pick_preset(),
+        KeyCode::Up | KeyCode::Char('k') => app.move_preset_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_preset_cursor(1),
+        _ => {}
+    }
+}
+
+fn handle_template_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('t') => app.close_input(),
+        KeyCode::Enter => app.pick_template(),
+        KeyCode::Char('s') => app.cycle_template_sort(),
+        KeyCode::Up | KeyCode::Char('k') => app.move_template_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_template_cursor(1),
+        KeyCode::PageUp => app.move_template_cursor(-10),
+        KeyCode::PageDown => app.move_template_cursor(10),
+        _ => {}
+    }
+}
+
+fn handle_bookmark_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('B') => app.close_input(),
+        KeyCode::Enter => app.pick_bookmark(),
+        KeyCode::Char('r') => app.begin_bookmark_rename(),
+        KeyCode::Char('d') | KeyCode::Delete => app.delete_bookmark(),
+        KeyCode::Char('t') => app.cycle_bookmark_tag(),
+        KeyCode::Up | KeyCode::Char('k') => app.move_bookmark_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_bookmark_cursor(1),
+        _ => {}
+    }
+}
+
+fn handle_prompt_key(app: &mut app::App, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_input(),
+        KeyCode::Enter => app.submit_prompt(),
+        KeyCode::Tab => app.complete_command(),
+        KeyCode::Backspace => {
+            if let filters::InputMode::GotoTime(buf)
+            | filters::InputMode::TimeRange(buf)
+            | filters::InputMode::Command(buf)
+            | filters::InputMode::BookmarkEdit(_, buf) = app.input_mode_mut()
+            {
+                buf.pop();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let filters::InputMode::GotoTime(buf)
+            | filters::InputMode::TimeRange(buf)
+            | filters::InputMode::Command(buf)
+            | filters::InputMode::BookmarkEdit(_, buf) = app.input_mode_mut()
+            {
+                buf.push(c);
+            }
         }
         _ => {}
     }
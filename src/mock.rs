@@ -0,0 +1,577 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::{
+    config::{parse_duration, TICK_RATE},
+    log_entry::{Level, LogEntry},
+};
+
+pub const DEFAULT_SEED: u64 = 42;
+
+/// A mock feed definition: what components exist, what they say, how often, and
+/// which incidents happen when.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub components: Vec<String>,
+    #[serde(default)]
+    pub levels: LevelMix,
+    pub messages: Messages,
+    /// Lines per second over time, linearly interpolated between points.
+    #[serde(default, rename = "rate")]
+    pub rates: Vec<RatePoint>,
+    /// Restart the rate curve and incidents after this long (e.g. "15m").
+    #[serde(default)]
+    pub period: Option<String>,
+    #[serde(default, rename = "incident")]
+    pub incidents: Vec<Incident>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelMix {
+    #[serde(default)]
+    pub info: u32,
+    #[serde(default)]
+    pub warn: u32,
+    #[serde(default)]
+    pub error: u32,
+}
+
+impl LevelMix {
+    /// Sum of the weights, or `None` when it does not fit in a `u32`.
+    fn total(&self) -> Option<u32> {
+        self.info.checked_add(self.warn)?.checked_add(self.error)
+    }
+}
+
+impl Default for LevelMix {
+    fn default() -> Self {
+        Self {
+            info: 66,
+            warn: 23,
+            error: 11,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Messages {
+    #[serde(default)]
+    pub info: Vec<String>,
+    #[serde(default)]
+    pub warn: Vec<String>,
+    #[serde(default)]
+    pub error: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RatePoint {
+    pub at: String,
+    pub per_sec: f64,
+}
+
+/// Extra traffic injected on top of the base rate, e.g. an error burst in `db`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Incident {
+    pub at: String,
+    pub duration: String,
+    pub per_sec: f64,
+    #[serde(default)]
+    pub component: Option<String>,
+    #[serde(default = "incident_levels")]
+    pub levels: LevelMix,
+    /// Message templates; falls back to the scenario's messages for the rolled level.
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+fn incident_levels() -> LevelMix {
+    LevelMix {
+        info: 0,
+        warn: 0,
+        error: 1,
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        let with_suffix = |msgs: &[&str]| {
+            msgs.iter()
+                .map(|m| format!("{m} target={{component}} req={{int:1000-9998}}"))
+                .collect()
+        };
+        Self {
+            seed: None,
+            components: COMPONENTS.iter().map(|c| c.to_string()).collect(),
+            levels: LevelMix::default(),
+            messages: Messages {
+                info: with_suffix(INFO_MESSAGES),
+                warn: with_suffix(WARN_MESSAGES),
+                error: with_suffix(ERROR_MESSAGES),
+            },
+            rates: vec![RatePoint {
+                at: "0s".to_string(),
+                per_sec: 7.5,
+            }],
+            period: None,
+            incidents: Vec::new(),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let scenario: Scenario =
+            toml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
+        scenario
+            .validate()
+            .with_context(|| format!("invalid scenario {}", path.display()))?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.components.is_empty() {
+            anyhow::bail!("at least one component is required");
+        }
+        check_mix(&self.levels, &self.messages, "levels")?;
+        for template in self
+            .messages
+            .info
+            .iter()
+            .chain(&self.messages.warn)
+            .chain(&self.messages.error)
+        {
+            check_template(template)?;
+        }
+        for point in &self.rates {
+            duration_field(&point.at, "rate.at")?;
+        }
+        if let Some(period) = &self.period {
+            duration_field(period, "period")?;
+        }
+        for incident in &self.incidents {
+            duration_field(&incident.at, "incident.at")?;
+            duration_field(&incident.duration, "incident.duration")?;
+            for template in &incident.messages {
+                check_template(template)?;
+            }
+            if incident.messages.is_empty() {
+                check_mix(&incident.levels, &self.messages, "incident.levels")?;
+            } else {
+                check_total(&incident.levels, "incident.levels")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_mix(mix: &LevelMix, messages: &Messages, field: &str) -> anyhow::Result<()> {
+    check_total(mix, field)?;
+    let missing = [
+        (mix.info, &messages.info, "info"),
+        (mix.warn, &messages.warn, "warn"),
+        (mix.error, &messages.error, "error"),
+    ]
+    .into_iter()
+    .find(|(weight, msgs, _)| *weight > 0 && msgs.is_empty());
+    if let Some((_, _, level)) = missing {
+        anyhow::bail!("{field} gives {level} a weight but messages.{level} is empty");
+    }
+    Ok(())
+}
+
+fn check_total(mix: &LevelMix, field: &str) -> anyhow::Result<()> {
+    match mix.total() {
+        None => anyhow::bail!("{field} must add up to at most {}", u32::MAX),
+        Some(0) => anyhow::bail!("{field} must not all be zero"),
+        Some(_) => Ok(()),
+    }
+}
+
+fn duration_field(spec: &str, field: &str) -> anyhow::Result<Duration> {
+    parse_duration(spec).with_context(|| format!("{field}: invalid duration {spec:?}"))
+}
+
+/// Generates entries from a [`Scenario`] on a clock that starts with the first batch.
+#[derive(Debug)]
+pub struct MockGen {
+    scenario: Scenario,
+    rng: SmallRng,
+    started: Option<DateTime<Local>>,
+    last: Option<DateTime<Local>>,
+    rates: Vec<(f64, f64)>,
+    period: Option<f64>,
+    incidents: Vec<(f64, f64)>,
+    carry: f64,
+    incident_carry: Vec<f64>,
+}
+
+impl MockGen {
+    pub fn new(scenario: Scenario, seed: Option<u64>) -> Self {
+        let seed = seed.or(scenario.seed).unwrap_or(DEFAULT_SEED);
+        let secs = |spec: &str| parse_duration(spec).map_or(0.0, |d| d.as_secs_f64());
+        let mut rates: Vec<(f64, f64)> = scenario
+            .rates
+            .iter()
+            .map(|p| (secs(&p.at), p.per_sec.max(0.0)))
+            .collect();
+        rates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let incidents = scenario
+            .incidents
+            .iter()
+            .map(|i| (secs(&i.at), secs(&i.at) + secs(&i.duration)))
+            .collect();
+        Self {
+            period: scenario.period.as_deref().map(secs).filter(|p| *p > 0.0),
+            incident_carry: vec![0.0; scenario.incidents.len()],
+            scenario,
+            rng: SmallRng::seed_from_u64(seed),
+            started: None,
+            last: None,
+            rates,
+            incidents,
+            carry: 0.0,
+        }
+    }
+
+    /// Produces the entries due between the previous call and `now`.
+    pub fn generate(&mut self, now: DateTime<Local>) -> Vec<LogEntry> {
+        let started = *self.started.get_or_insert(now);
+        let last = self.last.replace(now).unwrap_or(now);
+        let dt = (now - last).to_std().unwrap_or_default().as_secs_f64();
+        let mut elapsed = (now - started).to_std().unwrap_or_default().as_secs_f64();
+        if let Some(period) = self.period {
+            elapsed %= period;
+        }
+
+        let mut entries = Vec::new();
+        self.carry += self.rate_at(elapsed) * dt;
+        for _ in 0..take_whole(&mut self.carry) {
+            let level = roll_level(&mut self.rng, &self.scenario.levels);
+            let component = pick(&mut self.rng, &self.scenario.components).clone();
+            let template = pick(&mut self.rng, messages_for(&self.scenario.messages, level));
+            entries.push(self.build(now, level, component, template.clone()));
+        }
+        for idx in 0..self.incidents.len() {
+            let (start, end) = self.incidents[idx];
+            let active = (elapsed.min(end) - (elapsed - dt).max(start)).max(0.0);
+            if active == 0.0 {
+                continue;
+            }
+            let incident = &self.scenario.incidents[idx];
+            self.incident_carry[idx] += incident.per_sec.max(0.0) * active;
+            for _ in 0..take_whole(&mut self.incident_carry[idx]) {
+                let incident = &self.scenario.incidents[idx];
+                let level = roll_level(&mut self.rng, &incident.levels);
+                let component = match &incident.component {
+                    Some(component) => component.clone(),
+                    None => pick(&mut self.rng, &self.scenario.components).clone(),
+                };
+                let template = if incident.messages.is_empty() {
+                    pick(&mut self.rng, messages_for(&self.scenario.messages, level)).clone()
+                } else {
+                    pick(&mut self.rng, &incident.messages).clone()
+                };
+                entries.push(self.build(now, level, component, template));
+            }
+        }
+        entries
+    }
+
+    fn rate_at(&self, elapsed: f64) -> f64 {
+        let Some(first) = self.rates.first() else {
+            return 0.0;
+        };
+        if elapsed <= first.0 {
+            return first.1;
+        }
+        for pair in self.rates.windows(2) {
+            let (t0, r0) = pair[0];
+            let (t1, r1) = pair[1];
+            if elapsed < t1 {
+                let span = (t1 - t0).max(f64::EPSILON);
+                return r0 + (r1 - r0) * (elapsed - t0) / span;
+            }
+        }
+        self.rates.last().map_or(0.0, |p| p.1)
+    }
+
+    fn build(
+        &mut self,
+        now: DateTime<Local>,
+        level: Level,
+        component: String,
+        template: String,
+    ) -> LogEntry {
+        let message = expand(&template, &component, &mut self.rng);
+        LogEntry {
+            timestamp: now,
+            level,
+            target: component,
+            message,
+            fields: Vec::new(),
+            raw: None,
+        }
+    }
+}
+
+/// Writes the feed as plain log lines until the reader goes away.
+pub fn emit(mut gen: MockGen, out: &mut impl Write) -> anyhow::Result<()> {
+    loop {
+        for entry in gen.generate(Local::now()) {
+            if let Err(err) = writeln!(out, "{}", entry.to_line()) {
+                return ignore_broken_pipe(err);
+            }
+        }
+        if let Err(err) = out.flush() {
+            return ignore_broken_pipe(err);
+        }
+        thread::sleep(TICK_RATE);
+    }
+}
+
+fn ignore_broken_pipe(err: io::Error) -> anyhow::Result<()> {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        Ok(())
+    } else {
+        Err(err.into())
+    }
+}
+
+fn take_whole(carry: &mut f64) -> usize {
+    let whole = carry.floor();
+    *carry -= whole;
+    whole as usize
+}
+
+fn roll_level(rng: &mut SmallRng, mix: &LevelMix) -> Level {
+    let Some(total) = mix.total().filter(|total| *total > 0) else {
+        return Level::Info;
+    };
+    let roll = rng.gen_range(0..total);
+    if roll < mix.info {
+        Level::Info
+    } else if roll - mix.info < mix.warn {
+        Level::Warn
+    } else {
+        Level::Error
+    }
+}
+
+fn messages_for(messages: &Messages, level: Level) -> &Vec<String> {
+    match level {
+        Level::Info => &messages.info,
+        Level::Warn => &messages.warn,
+        Level::Error => &messages.error,
+    }
+}
+
+fn pick<'a, T>(rng: &mut SmallRng, items: &'a [T]) -> &'a T {
+    &items[rng.gen_range(0..items.len())]
+}
+
+/// Supported placeholders: `{component}`, `{int:A-B}`, `{hex:N}`, `{uuid}`,
+/// `{choice:a|b|c}`.
+fn check_template(template: &str) -> anyhow::Result<()> {
+    let mut rng = SmallRng::seed_from_u64(0);
+    for placeholder in placeholders(template) {
+        if expand_placeholder(placeholder, "", &mut rng).is_none() {
+            anyhow::bail!("unknown placeholder {{{placeholder}}} in {template:?}");
+        }
+    }
+    Ok(())
+}
+
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(p, _)| p))
+}
+
+fn expand(template: &str, component: &str, rng: &mut SmallRng) -> String {
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('}') {
+            Some(close) => {
+                let placeholder = &after[..close];
+                match expand_placeholder(placeholder, component, rng) {
+                    Some(value) => out.push_str(&value),
+                    None => {
+                        out.push('{');
+                        out.push_str(placeholder);
+                        out.push('}');
+                    }
+                }
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push_str(&rest[open..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn expand_placeholder(placeholder: &str, component: &str, rng: &mut SmallRng) -> Option<String> {
+    let (kind, arg) = placeholder
+        .split_once(':')
+        .map_or((placeholder, None), |(k, a)| (k, Some(a)));
+    match (kind, arg) {
+        ("component", None) => Some(component.to_string()),
+        ("uuid", None) => {
+            let bytes: [u8; 16] = rng.gen();
+            let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            Some(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[0..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..32]
+            ))
+        }
+        ("int", Some(range)) => {
+            let (lo, hi) = range.split_once('-')?;
+            let lo: i64 = lo.trim().parse().ok()?;
+            let hi: i64 = hi.trim().parse().ok()?;
+            (lo <= hi).then(|| rng.gen_range(lo..=hi).to_string())
+        }
+        ("hex", Some(len)) => {
+            let len: usize = len.trim().parse().ok()?;
+            Some(
+                (0..len)
+                    .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0'))
+                    .collect(),
+            )
+        }
+        ("choice", Some(options)) => {
+            let options: Vec<&str> = options.split('|').collect();
+            Some(pick(rng, &options).to_string())
+        }
+        _ => None,
+    }
+}
+
+const COMPONENTS: &[&str] = &["http", "db", "cache", "worker", "auth", "search"];
+const INFO_MESSAGES: &[&str] = &[
+    "GET /health 200",
+    "job completed successfully",
+    "cache warm completed",
+    "user session refreshed",
+    "metrics flushed",
+];
+const WARN_MESSAGES: &[&str] = &[
+    "cache miss rate spiked",
+    "retrying request",
+    "slow query detected",
+    "upstream took too long",
+    "backoff applied",
+];
+const ERROR_MESSAGES: &[&str] = &[
+    "database transaction deadlock",
+    "timeout talking to upstream",
+    "panic in worker thread",
+    "failed to commit offset",
+    "permission denied accessing key",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scenario(toml: &str) -> Scenario {
+        let scenario: Scenario = toml::from_str(toml).unwrap();
+        scenario.validate().unwrap();
+        scenario
+    }
+
+    #[test]
+    fn same_seed_produces_same_feed() {
+        let start = Local::now();
+        let run = |seed| {
+            let mut gen = MockGen::new(Scenario::default(), Some(seed));
+            (0..10)
+                .flat_map(|i| gen.generate(start + chrono::Duration::milliseconds(200 * i)))
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(9), run(9));
+        assert!(!run(9).is_empty());
+    }
+
+    #[test]
+    fn incidents_add_scheduled_bursts() {
+        let scenario = scenario(
+            r#"
+            components = ["db", "http"]
+            levels = { info = 1 }
+            messages = { info = ["ok"] }
+            rate = [{ at = "0s", per_sec = 0 }]
+            incident = [{ at = "5m", duration = "30s", per_sec = 10, component = "db", messages = ["deadlock txn={int:1-9}"] }]
+            "#,
+        );
+        let mut gen = MockGen::new(scenario, None);
+        let start = Local::now();
+        assert!(gen.generate(start).is_empty());
+        assert!(gen
+            .generate(start + chrono::Duration::minutes(4))
+            .is_empty());
+        let burst = gen.generate(start + chrono::Duration::seconds(301));
+        assert_eq!(burst.len(), 10);
+        assert!(burst.iter().all(|e| e.level == Level::Error
+            && e.target == "db"
+            && e.message.starts_with("deadlock txn=")));
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let scenario: Scenario = toml::from_str(
+            r#"
+            components = ["db"]
+            levels = { info = 1 }
+            messages = { info = ["id={nope}"] }
+            "#,
+        )
+        .unwrap();
+        assert!(scenario.validate().is_err());
+    }
+
+    #[test]
+    fn overflowing_level_weights_are_rejected() {
+        for levels in [
+            "levels = { info = 4294967295, warn = 1 }",
+            "incident = [{ at = \"0s\", duration = \"1s\", per_sec = 1, levels = { warn = 4294967295, error = 4294967295 }, messages = [\"x\"] }]",
+        ] {
+            let scenario: Scenario = toml::from_str(&format!(
+                "components = [\"db\"]\nmessages = {{ info = [\"ok\"], warn = [\"w\"], error = [\"e\"] }}\n{levels}"
+            ))
+            .unwrap();
+            let err = format!("{:#}", scenario.validate().unwrap_err());
+            assert!(err.contains("add up to at most"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex},
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::ingest::Ingested;
+
+/// Every Nth line is kept while a `sample` queue is full.
+pub const SAMPLE_EVERY: u64 = 10;
+
+/// What a reader thread does when the UI has not caught up yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DropPolicy {
+    /// Stop reading until there is room (nothing is lost; the source may stall).
+    #[default]
+    Block,
+    /// Evict the oldest queued line to make room for the newest.
+    DropOldest,
+    /// Keep one in every `SAMPLE_EVERY` lines while full, evicting the oldest.
+    Sample,
+}
+
+impl DropPolicy {
+    pub fn label(self) -> &'static str {
+        match self {
+            DropPolicy::Block => "block",
+            DropPolicy::DropOldest => "drop-oldest",
+            DropPolicy::Sample => "sample",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QueueStats {
+    pub queued: usize,
+    pub capacity: usize,
+    pub dropped: u64,
+    pub policy: DropPolicy,
+}
+
+/// Bounded hand-off between reader threads (which parse) and the UI thread.
+#[derive(Debug)]
+pub struct LineQueue {
+    state: Mutex<QueueState>,
+    not_full: Condvar,
+    capacity: usize,
+    policy: DropPolicy,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    items: VecDeque<Ingested>,
+    dropped: u64,
+    overflow_seen: u64,
+}
+
+impl LineQueue {
+    pub fn new(capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            not_full: Condvar::new(),
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+
+    pub fn push(&self, item: Ingested) {
+        let mut state = self.state.lock().expect("ingest queue poisoned");
+        if state.items.len() < self.capacity {
+            state.items.push_back(item);
+            return;
+        }
+        match self.policy {
+            DropPolicy::Block => {
+                let mut state = self
+                    .not_full
+                    .wait_while(state, |s| s.items.len() >= self.capacity)
+                    .expect("ingest queue poisoned");
+                state.items.push_back(item);
+            }
+            DropPolicy::DropOldest => {
+                state.items.pop_front();
+                state.dropped += 1;
+                state.items.push_back(item);
+            }
+            DropPolicy::Sample => {
+                state.overflow_seen += 1;
+                state.dropped += 1;
+                if state.overflow_seen.is_multiple_of(SAMPLE_EVERY) {
+                    state.items.pop_front();
+                    state.items.push_back(item);
+                }
+            }
+        }
+    }
+
+    /// Takes up to `max` lines, oldest first.
+    pub fn drain(&self, max: usize) -> Vec<Ingested> {
+        let mut state = self.state.lock().expect("ingest queue poisoned");
+        let take = state.items.len().min(max);
+        let items: Vec<Ingested> = state.items.drain(..take).collect();
+        if state.items.len() < self.capacity {
+            state.overflow_seen = 0;
+        }
+        drop(state);
+        if !items.is_empty() {
+            self.not_full.notify_all();
+        }
+        items
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let state = self.state.lock().expect("ingest queue poisoned");
+        QueueStats {
+            queued: state.items.len(),
+            capacity: self.capacity,
+            dropped: state.dropped,
+            policy: self.policy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::parse_line;
+    use chrono::Local;
+
+    fn line(raw: &str) -> Ingested {
+        let at = Local::now();
+        Ingested {
+            at,
+            raw: raw.to_string(),
+            entry: parse_line(raw, at),
+        }
+    }
+
+    #[test]
+    fn drop_oldest_keeps_newest_lines() {
+        let queue = LineQueue::new(3, DropPolicy::DropOldest);
+        for i in 0..5 {
+            queue.push(line(&format!("line {i}")));
+        }
+        let stats = queue.stats();
+        assert_eq!((stats.queued, stats.dropped), (3, 2));
+        let raws: Vec<_> = queue.drain(10).into_iter().map(|l| l.raw).collect();
+        assert_eq!(raws, vec!["line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn sample_keeps_one_in_n_while_full() {
+        let queue = LineQueue::new(2, DropPolicy::Sample);
+        for i in 0..(2 + SAMPLE_EVERY * 2) {
+            queue.push(line(&format!("line {i}")));
+        }
+        let raws: Vec<_> = queue.drain(10).into_iter().map(|l| l.raw).collect();
+        assert_eq!(raws.len(), 2);
+        assert_eq!(raws[1], format!("line {}", 1 + SAMPLE_EVERY * 2));
+        assert_eq!(queue.stats().dropped, SAMPLE_EVERY * 2);
+    }
+
+    #[test]
+    fn block_waits_for_the_consumer() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+        let queue = Arc::new(LineQueue::new(1, DropPolicy::Block));
+        queue.push(line("first"));
+        let pushed = Arc::new(AtomicBool::new(false));
+        let producer = {
+            let (queue, pushed) = (queue.clone(), pushed.clone());
+            std::thread::spawn(move || {
+                queue.push(line("second"));
+                pushed.store(true, Ordering::SeqCst);
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(
+            !pushed.load(Ordering::SeqCst),
+            "push must wait while the queue is full"
+        );
+        let raws: Vec<_> = queue.drain(1).into_iter().map(|l| l.raw).collect();
+        assert_eq!(raws, vec!["first"]);
+        producer.join().unwrap();
+        assert!(pushed.load(Ordering::SeqCst));
+        let raws: Vec<_> = queue.drain(10).into_iter().map(|l| l.raw).collect();
+        assert_eq!(raws, vec!["second"]);
+        assert_eq!(queue.stats().dropped, 0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+/// One line of a session recording. Records without a `line` are clock markers
+/// written when the session starts and ends.
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<String>,
+}
+
+/// Lines that arrived together, stamped with their arrival time.
+#[derive(Debug, Clone)]
+pub struct RecordedBatch {
+    pub at: DateTime<Local>,
+    pub lines: Vec<String>,
+}
+
+/// Writes every raw input line with its arrival time as JSON lines (gzip when the
+/// path ends in `.gz`).
+pub struct TeeWriter {
+    out: Output,
+}
+
+/// The recording file, kept as its concrete type so `finish` can write the gzip
+/// trailer and report its errors instead of leaving that to `Drop`.
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Output {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Output::Plain(out) => out,
+            Output::Gzip(out) => out,
+        }
+    }
+}
+
+impl TeeWriter {
+    pub fn create(path: &Path, started_at: DateTime<Local>) -> anyhow::Result<Self> {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let out = if is_gzip(path) {
+            Output::Gzip(GzEncoder::new(BufWriter::new(file), Compression::default()))
+        } else {
+            Output::Plain(BufWriter::new(file))
+        };
+        let mut tee = Self { out };
+        tee.write_record(started_at, None)?;
+        Ok(tee)
+    }
+
+    pub fn write_line(&mut self, at: DateTime<Local>, line: &str) -> anyhow::Result<()> {
+        self.write_record(at, Some(line))
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.out.writer().flush()?;
+        Ok(())
+    }
+
+    /// Writes the closing clock marker so a replay ends at the same moment, then
+    /// completes the gzip stream.
+    pub fn finish(mut self, at: DateTime<Local>) -> anyhow::Result<()> {
+        self.write_record(at, None)?;
+        match self.out {
+            Output::Plain(mut out) => out.flush()?,
+            Output::Gzip(out) => out.finish()?.flush()?,
+        }
+        Ok(())
+    }
+
+    fn write_record(&mut self, at: DateTime<Local>, line: Option<&str>) -> anyhow::Result<()> {
+        let record = Record {
+            at: at.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            line: line.map(str::to_string),
+        };
+        let out = self.out.writer();
+        serde_json::to_writer(&mut *out, &record)?;
+        out.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Loads a recording into arrival-ordered batches, grouping lines that share an
+/// arrival time.
+pub fn load_recording(path: &Path) -> anyhow::Result<VecDeque<RecordedBatch>> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let reader: Box<dyn Read> = if is_gzip(path) {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    let mut batches: VecDeque<RecordedBatch> = VecDeque::new();
+    for (idx, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.with_context(|| format!("reading {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid record", path.display(), idx + 1))?;
+        let at = DateTime::parse_from_rfc3339(&record.at)
+            .with_context(|| format!("{}:{}: invalid time", path.display(), idx + 1))?
+            .with_timezone(&Local);
+        match batches.back_mut() {
+            Some(last) if last.at == at => last.lines.extend(record.line),
+            _ => batches.push_back(RecordedBatch {
+                at,
+                lines: record.line.into_iter().collect(),
+            }),
+        }
+    }
+    if batches.is_empty() {
+        anyhow::bail!("{} contains no records", path.display());
+    }
+    Ok(batches)
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips_with_gzip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl.gz");
+        let start = Local::now();
+        let later = start + chrono::Duration::milliseconds(200);
+        let mut tee = TeeWriter::create(&path, start).unwrap();
+        tee.write_line(later, "first").unwrap();
+        tee.write_line(later, "second").unwrap();
+        tee.finish(later + chrono::Duration::seconds(1)).unwrap();
+
+        let batches = load_recording(&path).unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].at, start);
+        assert!(batches[0].lines.is_empty());
+        assert_eq!(batches[1].lines, vec!["first", "second"]);
+        assert!(batches[2].lines.is_empty());
+
+        // The gzip trailer ends with the uncompressed length.
+        let bytes = std::fs::read(&path).unwrap();
+        let mut text = String::new();
+        GzDecoder::new(&bytes[..])
+            .read_to_string(&mut text)
+            .unwrap();
+        let trailer: [u8; 4] = bytes[bytes.len() - 4..].try_into().unwrap();
+        assert_eq!(u32::from_le_bytes(trailer) as usize, text.len());
+    }
+}
@@ -0,0 +1,335 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filters::{CaseMode, Filters},
+    log_entry::Level,
+};
+
+/// Oldest entries are forgotten once the filter history grows past this.
+pub const FILTER_HISTORY_LIMIT: usize = 200;
+
+/// A filter written as a one-line query, e.g. `level>=warn target:db re:timeout.*`.
+///
+/// Recognised terms: `level>=LEVEL`, `level=LEVEL[,LEVEL]` (or `level:`),
+/// `target:NAME`, `case:smart|match|ignore` and `word`. `re:` starts a regex that
+/// runs to the end of the query; any other words become the literal text filter.
+///
+/// Session state stores the fields themselves rather than the query text, since
+/// literal text may contain anything, including words that look like terms.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(rename = "regex", skip_serializing_if = "is_false")]
+    pub regex_mode: bool,
+    pub case: CaseMode,
+    #[serde(rename = "word", skip_serializing_if = "is_false")]
+    pub whole_word: bool,
+    pub levels: [bool; 3],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+fn is_false(flag: &bool) -> bool {
+    !*flag
+}
+
+impl Default for FilterQuery {
+    fn default() -> Self {
+        Self {
+            text: None,
+            regex_mode: false,
+            case: CaseMode::default(),
+            whole_word: false,
+            levels: [true; 3],
+            target: None,
+        }
+    }
+}
+
+impl FromStr for FilterQuery {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut parsed = FilterQuery::default();
+        let mut words = Vec::new();
+        let mut rest = query.trim();
+        while !rest.is_empty() {
+            if let Some(pattern) = rest.strip_prefix("re:") {
+                parsed.regex_mode = true;
+                parsed.text = Some(pattern.to_string()).filter(|p| !p.is_empty());
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let term = &rest[..end];
+            rest = rest[end..].trim_start();
+            if let Some(min) = term.strip_prefix("level>=") {
+                let min = parse_level(min)?;
+                parsed.levels = [Level::Info, Level::Warn, Level::Error].map(|l| l >= min);
+            } else if let Some(list) = term
+                .strip_prefix("level=")
+                .or_else(|| term.strip_prefix("level:"))
+            {
+                parsed.levels = [false; 3];
+                for name in list.split(',') {
+                    parsed.levels[parse_level(name)? as usize] = true;
+                }
+            } else if let Some(target) = term.strip_prefix("target:") {
+                parsed.target = Some(target.to_string());
+            } else if let Some(case) = term.strip_prefix("case:") {
+                parsed.case = match case {
+                    "smart" => CaseMode::Smart,
+                    "match" => CaseMode::Sensitive,
+                    "ignore" => CaseMode::Insensitive,
+                    other => return Err(format!("unknown case mode {other:?}")),
+                };
+            } else if term == "word" {
+                parsed.whole_word = true;
+            } else {
+                words.push(term);
+            }
+        }
+        if !parsed.regex_mode && !words.is_empty() {
+            parsed.text = Some(words.join(" "));
+        } else if parsed.regex_mode && !words.is_empty() {
+            return Err("plain words cannot be combined with re:".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_level(name: &str) -> Result<Level, String> {
+    Level::from_name(name).ok_or_else(|| format!("unknown level {name:?}"))
+}
+
+impl FilterQuery {
+    /// The query form of the matcher options, levels, target and text in
+    /// `filters` (the time range, template and trace are not part of it).
+    pub fn from_filters(filters: &Filters) -> Self {
+        Self {
+            text: filters.text.clone(),
+            regex_mode: filters.regex_mode,
+            case: filters.case,
+            whole_word: filters.whole_word,
+            levels: [filters.info, filters.warn, filters.error],
+            target: filters.target.clone(),
+        }
+    }
+
+    pub fn to_filters(&self) -> Result<Filters, regex::Error> {
+        let [info, warn, error] = self.levels;
+        let mut filters = Filters {
+            info,
+            warn,
+            error,
+            regex_mode: self.regex_mode,
+            case: self.case,
+            whole_word: self.whole_word,
+            target: self.target.clone(),
+            ..Filters::default()
+        };
+        filters.set_text(self.text.clone())?;
+        Ok(filters)
+    }
+}
+
+/// How a preset is written under `[presets]` in `config.toml`: either just the
+/// query, or a table that also binds it to a key.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PresetSpec {
+    Query(String),
+    Keyed { query: String, key: Option<char> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub source: String,
+    pub query: FilterQuery,
+    pub key: Option<char>,
+}
+
+impl Preset {
+    pub fn parse(name: &str, spec: &PresetSpec) -> Result<Self, String> {
+        let (source, key) = match spec {
+            PresetSpec::Query(query) => (query.clone(), None),
+            PresetSpec::Keyed { query, key } => (query.clone(), *key),
+        };
+        let query = source
+            .parse::<FilterQuery>()
+            .map_err(|err| format!("preset {name}: {err}"))?;
+        query
+            .to_filters()
+            .map_err(|err| format!("preset {name}: {err}"))?;
+        Ok(Self {
+            name: name.to_string(),
+            source,
+            query,
+            key,
+        })
+    }
+}
+
+/// Text filters typed in the filter bar, oldest first, with an Up/Down cursor.
+#[derive(Debug, Default)]
+pub struct FilterHistory {
+    entries: Vec<String>,
+    cursor: Option<usize>,
+    draft: String,
+}
+
+impl FilterHistory {
+    /// Reads one filter per line; a missing file is an empty history.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading {}", path.display()));
+            }
+        };
+        let mut history = Self::default();
+        for line in contents.lines() {
+            history.push(line);
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+        fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Records `text` as the newest entry, moving an earlier copy to the end.
+    pub fn push(&mut self, text: &str) {
+        self.cursor = None;
+        if text.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != text);
+        self.entries.push(text.to_string());
+        let excess = self.entries.len().saturating_sub(FILTER_HISTORY_LIMIT);
+        self.entries.drain(..excess);
+    }
+
+    /// Steps back in time; `current` is kept so stepping forward past the newest
+    /// entry restores what was being typed.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let idx = match self.cursor {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.cursor = Some(idx);
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    pub fn newer(&mut self) -> Option<&str> {
+        let idx = self.cursor?;
+        if idx + 1 < self.entries.len() {
+            self.cursor = Some(idx + 1);
+            self.entries.get(idx + 1).map(String::as_str)
+        } else {
+            self.cursor = None;
+            Some(self.draft.as_str())
+        }
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_set_levels_target_and_text() {
+        let query: FilterQuery = "level>=warn target:db deadlock retry".parse().unwrap();
+        assert_eq!(query.levels, [false, true, true]);
+        assert_eq!(query.target.as_deref(), Some("db"));
+        assert_eq!(query.text.as_deref(), Some("deadlock retry"));
+
+        let query: FilterQuery = "level=error,info case:match re:time.*out x"
+            .parse()
+            .unwrap();
+        assert_eq!(query.levels, [true, false, true]);
+        assert!(query.regex_mode);
+        assert_eq!(query.text.as_deref(), Some("time.*out x"));
+        assert_eq!(query.case, CaseMode::Sensitive);
+
+        assert!("level>=loud".parse::<FilterQuery>().is_err());
+        let bad_regex = PresetSpec::Query("re:(".to_string());
+        assert!(Preset::parse("broken", &bad_regex).is_err());
+    }
+
+    #[test]
+    fn queries_round_trip_through_session_json() {
+        let queries = [
+            FilterQuery {
+                levels: [false; 3],
+                ..FilterQuery::default()
+            },
+            FilterQuery {
+                text: Some("word re:x target:db case:match level=info".to_string()),
+                ..FilterQuery::default()
+            },
+            FilterQuery {
+                text: Some("two  spaces\tand a tab ".to_string()),
+                case: CaseMode::Insensitive,
+                whole_word: true,
+                target: Some("api".to_string()),
+                ..FilterQuery::default()
+            },
+            FilterQuery {
+                text: Some("time.*out".to_string()),
+                regex_mode: true,
+                levels: [false, true, true],
+                ..FilterQuery::default()
+            },
+        ];
+        for query in queries {
+            let json = serde_json::to_string(&query).unwrap();
+            assert_eq!(serde_json::from_str::<FilterQuery>(&json).unwrap(), query);
+        }
+        assert_eq!(
+            serde_json::from_str::<FilterQuery>("{}").unwrap(),
+            FilterQuery::default()
+        );
+    }
+
+    #[test]
+    fn history_walks_back_and_restores_the_draft() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/filter_history");
+        let mut history = FilterHistory::default();
+        for text in ["timeout", "deadlock", "timeout", ""] {
+            history.push(text);
+        }
+        history.save(&path).unwrap();
+
+        let mut history = FilterHistory::load(&path).unwrap();
+        assert_eq!(history.older("dra"), Some("timeout"));
+        assert_eq!(history.older("ignored"), Some("deadlock"));
+        assert_eq!(history.older("ignored"), Some("deadlock"));
+        assert_eq!(history.newer(), Some("timeout"));
+        assert_eq!(history.newer(), Some("dra"));
+        assert_eq!(history.newer(), None);
+        assert!(FilterHistory::load(&dir.path().join("missing"))
+            .unwrap()
+            .older("")
+            .is_none());
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::{excerpt, Bookmark, BookmarkTag},
+    ingest::FileId,
+    log_entry::LogEntry,
+    saved_filters::FilterQuery,
+};
+
+/// What is remembered about a log file between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<FilterQuery>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    /// The selected line when the view was paused; live views save none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<Anchor>,
+    #[serde(default)]
+    pub bookmarks: Vec<SavedBookmark>,
+}
+
+impl SessionState {
+    /// Whether restoring needs the file's earlier lines, not just new ones.
+    pub fn needs_history(&self) -> bool {
+        self.selected.is_some() || !self.bookmarks.is_empty()
+    }
+}
+
+/// Finds a line again: its timestamp, the start of its message and how many
+/// earlier lines looked the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub timestamp: String,
+    pub excerpt: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub repeat: usize,
+}
+
+impl Anchor {
+    pub fn of(timestamp: DateTime<Local>, excerpt: &str, repeat: usize) -> Self {
+        Self {
+            timestamp: timestamp_string(timestamp),
+            excerpt: excerpt.to_string(),
+            repeat,
+        }
+    }
+
+    pub fn of_entry(entry: &LogEntry, repeat: usize) -> Self {
+        Self::of(entry.timestamp, &excerpt(&entry.message), repeat)
+    }
+
+    /// Whether `entry` looks like the anchored line; `repeat` picks among matches.
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        timestamp_string(entry.timestamp) == self.timestamp
+            && excerpt(&entry.message) == self.excerpt
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBookmark {
+    pub anchor: Anchor,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Rotated part the bookmark was restored from; `None` is the live file.
+    #[serde(skip)]
+    pub part: Option<PathBuf>,
+}
+
+impl From<&Bookmark> for SavedBookmark {
+    fn from(bookmark: &Bookmark) -> Self {
+        Self {
+            anchor: Anchor::of(bookmark.timestamp, &bookmark.excerpt, bookmark.repeat),
+            label: bookmark.label.clone(),
+            note: bookmark.note.clone(),
+            tag: Some(bookmark.tag)
+                .filter(|tag| *tag != BookmarkTag::Plain)
+                .map(|tag| tag.name().to_string()),
+            part: bookmark.part.clone(),
+        }
+    }
+}
+
+impl SavedBookmark {
+    pub fn into_bookmark(self) -> Option<Bookmark> {
+        Some(Bookmark {
+            seq: None,
+            timestamp: parse_timestamp(&self.anchor.timestamp)?,
+            label: self.label,
+            note: self.note,
+            tag: self
+                .tag
+                .and_then(|tag| tag.parse().ok())
+                .unwrap_or_default(),
+            excerpt: self.anchor.excerpt,
+            repeat: self.anchor.repeat,
+            part: self.part,
+        })
+    }
+}
+
+pub fn timestamp_string(ts: DateTime<Local>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+}
+
+pub fn parse_timestamp(text: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|ts| ts.with_timezone(&Local))
+}
+
+/// The saved history to read back on startup.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Preload {
+    /// Rotated parts to read before the live file, oldest first, each from a byte offset.
+    pub parts: Vec<(PathBuf, u64)>,
+    /// Byte offset in the live file to start from.
+    pub start: u64,
+    /// Whether older lines (and any bookmarks on them) were left out to stay in budget.
+    pub truncated: bool,
+}
+
+/// One state file: the state plus the file it belongs to.
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    path: PathBuf,
+    file: FileId,
+    saved_at: String,
+    #[serde(flatten)]
+    state: SessionState,
+}
+
+/// Saved state for a tailed file and for its rotated parts (`app.log.1`,
+/// `app.log-20240501`, ...). State files live in `dir`, one per file identity,
+/// so a part keeps its bookmarks after being renamed by rotation.
+#[derive(Debug)]
+pub struct FileSession {
+    dir: PathBuf,
+    path: PathBuf,
+    file: FileId,
+    /// Rotated parts that have saved state, oldest first.
+    parts: Vec<(PathBuf, FileId)>,
+    /// Every rotated part on disk, oldest first.
+    siblings: Vec<PathBuf>,
+}
+
+impl FileSession {
+    /// Reads what was saved for `path` and its rotated parts. Filters and the
+    /// selected line come from the newest state; bookmarks from all of them.
+    pub fn open(dir: &Path, path: &Path) -> anyhow::Result<(Self, Option<SessionState>)> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("resolving {}", path.display()))?;
+        let file = FileId::of_path(&path).with_context(|| format!("opening {}", path.display()))?;
+        let siblings = rotated_parts(&path);
+        let mut session = Self {
+            dir: dir.to_path_buf(),
+            path,
+            file,
+            parts: Vec::new(),
+            siblings,
+        };
+        let mut found: Vec<(Option<PathBuf>, StateFile)> = Vec::new();
+        if let Some(saved) = session.read(&file) {
+            found.push((None, saved));
+        }
+        for part in session.siblings.clone() {
+            let Ok(id) = FileId::of_path(&part) else {
+                continue;
+            };
+            if let Some(saved) = session.read(&id) {
+                session.parts.push((part.clone(), id));
+                found.push((Some(part), saved));
+            }
+        }
+        if found.is_empty() {
+            return Ok((session, None));
+        }
+        let newest = found
+            .iter()
+            .max_by_key(|(part, saved)| (part.is_none(), saved.saved_at.clone()))
+            .map(|(_, saved)| saved.state.clone())
+            .unwrap_or_default();
+        let mut merged = SessionState {
+            bookmarks: Vec::new(),
+            ..newest
+        };
+        for (part, saved) in found {
+            merged
+                .bookmarks
+                .extend(
+                    saved
+                        .state
+                        .bookmarks
+                        .into_iter()
+                        .map(|bookmark| SavedBookmark {
+                            part: part.clone(),
+                            ..bookmark
+                        }),
+                );
+        }
+        Ok((session, Some(merged)))
+    }
+
+    /// Where to start reading so that restored bookmarks can be reached: every
+    /// rotated part from the oldest one holding a bookmark on, then the live
+    /// file, but no more than the last `budget` bytes of them all.
+    pub fn preload(&self, state: &SessionState, budget: u64) -> Preload {
+        let oldest = self.siblings.iter().position(|part| {
+            state
+                .bookmarks
+                .iter()
+                .any(|bookmark| bookmark.part.as_ref() == Some(part))
+        });
+        let parts = oldest.map_or(&[][..], |idx| &self.siblings[idx..]);
+        let mut preload = Preload::default();
+        let mut remaining = budget;
+        let files: Vec<&PathBuf> = parts.iter().chain([&self.path]).collect();
+        for (idx, file) in files.into_iter().enumerate().rev() {
+            let len = fs::metadata(file).map_or(0, |meta| meta.len());
+            let offset = len.saturating_sub(remaining);
+            remaining -= len - offset;
+            if idx == parts.len() {
+                preload.start = offset;
+            } else {
+                preload.parts.insert(0, (file.clone(), offset));
+            }
+            if offset > 0 {
+                preload.truncated = true;
+                break;
+            }
+        }
+        preload
+    }
+
+    /// Writes `state` back: bookmarks from rotated parts go to those parts'
+    /// state files (removed once they hold none), the rest to the live file's.
+    pub fn save(&self, state: SessionState) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating {}", self.dir.display()))?;
+        let (live, rotated): (Vec<_>, Vec<_>) = state
+            .bookmarks
+            .iter()
+            .cloned()
+            .partition(|bookmark| bookmark.part.is_none());
+        for (part, id) in &self.parts {
+            let bookmarks: Vec<_> = rotated
+                .iter()
+                .filter(|bookmark| bookmark.part.as_ref() == Some(part))
+                .cloned()
+                .collect();
+            if bookmarks.is_empty() {
+                let _ = fs::remove_file(self.state_path(id));
+                continue;
+            }
+            self.write(
+                part,
+                id,
+                SessionState {
+                    bookmarks,
+                    ..SessionState::default()
+                },
+            )?;
+        }
+        self.write(
+            &self.path,
+            &self.file,
+            SessionState {
+                bookmarks: live,
+                ..state
+            },
+        )
+    }
+
+    fn state_path(&self, id: &FileId) -> PathBuf {
+        self.dir.join(format!("{}.json", id.key()))
+    }
+
+    /// The state saved under `id`, if it was saved for this file or one of its
+    /// rotated names (an inode reused by an unrelated file is ignored).
+    fn read(&self, id: &FileId) -> Option<StateFile> {
+        let text = fs::read_to_string(self.state_path(id)).ok()?;
+        let saved: StateFile = serde_json::from_str(&text).ok()?;
+        let same_log = saved.path == self.path
+            || saved.path.parent() == self.path.parent() && is_rotation_of(&self.path, &saved.path);
+        (saved.file == *id && same_log).then_some(saved)
+    }
+
+    fn write(&self, path: &Path, id: &FileId, state: SessionState) -> anyhow::Result<()> {
+        let saved = StateFile {
+            path: path.to_path_buf(),
+            file: *id,
+            saved_at: timestamp_string(Local::now()),
+            state,
+        };
+        let target = self.state_path(id);
+        let json = serde_json::to_string_pretty(&saved)?;
+        fs::write(&target, json).with_context(|| format!("writing {}", target.display()))
+    }
+}
+
+/// Whether `other` is `path` or a rotated name of it: `app.log.1`, `app.log-2024...`.
+fn is_rotation_of(path: &Path, other: &Path) -> bool {
+    let (Some(name), Some(other)) = (
+        path.file_name().and_then(|n| n.to_str()),
+        other.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return false;
+    };
+    other == name
+        || other
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with(['.', '-', '_']))
+}
+
+/// Uncompressed rotated parts of `path` in its directory, oldest first.
+fn rotated_parts(path: &Path) -> Vec<PathBuf> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut parts: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|part| {
+            part != path
+                && part.is_file()
+                && is_rotation_of(path, part)
+                && !part
+                    .extension()
+                    .is_some_and(|ext| ["gz", "bz2", "xz", "zst"].iter().any(|c| ext == *c))
+        })
+        .filter_map(|part| Some((fs::metadata(&part).ok()?.modified().ok()?, part)))
+        .collect();
+    parts.sort();
+    parts.into_iter().map(|(_, part)| part).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::Level;
+
+    fn bookmark_on(message: &str, label: &str) -> SavedBookmark {
+        let entry = LogEntry {
+            timestamp: Local::now(),
+            level: Level::Info,
+            target: "api".to_string(),
+            message: message.to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        SavedBookmark::from(&Bookmark::new(0, &entry, 0, label.to_string()))
+    }
+
+    #[test]
+    fn state_follows_a_log_into_its_rotated_part() {
+        let logs = tempfile::tempdir().unwrap();
+        let states = tempfile::tempdir().unwrap();
+        let log = logs.path().join("app.log");
+        fs::write(&log, "first\n").unwrap();
+
+        let (session, restored) = FileSession::open(states.path(), &log).unwrap();
+        assert!(restored.is_none());
+        session
+            .save(SessionState {
+                filter: Some("level>=warn".parse().unwrap()),
+                bookmarks: vec![bookmark_on("first", "deploy")],
+                ..SessionState::default()
+            })
+            .unwrap();
+
+        let (_, restored) = FileSession::open(states.path(), &log).unwrap();
+        let restored = restored.unwrap();
+        assert_eq!(restored.filter, Some("level>=warn".parse().unwrap()));
+        assert_eq!(restored.bookmarks[0].part, None);
+
+        // Rotate: the old file keeps its inode under a new name.
+        let part = logs.path().join("app.log.1");
+        fs::rename(&log, &part).unwrap();
+        fs::write(&log, "second\n").unwrap();
+        let (session, restored) = FileSession::open(states.path(), &log).unwrap();
+        let restored = restored.unwrap();
+        let part = part.canonicalize().unwrap();
+        assert_eq!(restored.bookmarks[0].label, "deploy");
+        assert_eq!(restored.bookmarks[0].part.as_ref(), Some(&part));
+        assert_eq!(restored.filter, Some("level>=warn".parse().unwrap()));
+        let preload = session.preload(&restored, u64::MAX);
+        assert_eq!(preload.parts, vec![(part.clone(), 0)]);
+        assert!(!preload.truncated);
+        // A budget smaller than the history reads only its newest lines.
+        let preload = session.preload(&restored, 9);
+        assert_eq!(preload.parts, vec![(part.clone(), 4)]);
+        assert_eq!(preload.start, 0);
+        assert!(preload.truncated);
+        assert_eq!(session.preload(&restored, 3).parts, Vec::new());
+        assert_eq!(session.preload(&restored, 3).start, 4);
+
+        // Deleting the part's last bookmark drops its state file.
+        session
+            .save(SessionState {
+                bookmarks: vec![bookmark_on("second", "new")],
+                ..SessionState::default()
+            })
+            .unwrap();
+        let (_, restored) = FileSession::open(states.path(), &log).unwrap();
+        let labels: Vec<_> = restored
+            .unwrap()
+            .bookmarks
+            .into_iter()
+            .map(|b| b.label)
+            .collect();
+        assert_eq!(labels, vec!["new"]);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+
+use crate::{
+    collapse::mask_words,
+    log_entry::{Level, LogEntry},
+};
+
+/// Placeholder for the variable parts of a template.
+pub const WILDCARD: &str = "<*>";
+/// Fraction of positions that must agree for a message to join a template.
+const SIMILARITY: f64 = 0.5;
+/// Past this many templates, unmatched messages are only counted in `overflow`.
+const MAX_TEMPLATES: usize = 2000;
+
+/// A message shape such as `deadlock retry txn=<*> attempt=<*>`.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub tokens: Vec<String>,
+    pub count: u64,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    /// Lines per level: info, warn, error.
+    pub levels: [u64; 3],
+}
+
+impl Template {
+    pub fn text(&self) -> String {
+        self.tokens.join(" ")
+    }
+
+    pub fn matcher(&self) -> TemplateMatch {
+        TemplateMatch::new(&self.tokens)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemplateSort {
+    #[default]
+    Count,
+    Recent,
+    Errors,
+}
+
+impl TemplateSort {
+    pub fn label(self) -> &'static str {
+        match self {
+            TemplateSort::Count => "count",
+            TemplateSort::Recent => "last seen",
+            TemplateSort::Errors => "errors",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TemplateSort::Count => TemplateSort::Recent,
+            TemplateSort::Recent => TemplateSort::Errors,
+            TemplateSort::Errors => TemplateSort::Count,
+        }
+    }
+}
+
+/// Online, Drain-style clustering of messages into templates.
+///
+/// Words containing digits are masked up front; messages are then bucketed by
+/// token count and first token, and join the most similar template in their
+/// bucket, turning positions that disagree into `<*>`.
+#[derive(Debug, Default)]
+pub struct TemplateMiner {
+    templates: Vec<Template>,
+    buckets: HashMap<(usize, String), Vec<usize>>,
+    overflow: u64,
+}
+
+impl TemplateMiner {
+    /// Assigns the entry to a template and returns its index, if it got one.
+    pub fn add(&mut self, entry: &LogEntry, at: DateTime<Local>) -> Option<usize> {
+        let masked = mask_words(&entry.message, WILDCARD);
+        let tokens: Vec<&str> = masked.split_whitespace().collect();
+        let first = match tokens.first() {
+            Some(first) if !first.contains(WILDCARD) => first.to_string(),
+            _ => WILDCARD.to_string(),
+        };
+        let bucket = self.buckets.entry((tokens.len(), first)).or_default();
+        let best = bucket
+            .iter()
+            .map(|idx| (*idx, similarity(&self.templates[*idx].tokens, &tokens)))
+            .fold(None, |best: Option<(usize, f64)>, cand| match best {
+                Some(b) if b.1 >= cand.1 => Some(b),
+                _ => Some(cand),
+            });
+        let idx = match best {
+            Some((idx, sim)) if sim >= SIMILARITY || self.templates.len() >= MAX_TEMPLATES => {
+                let template = &mut self.templates[idx];
+                for (slot, token) in template.tokens.iter_mut().zip(&tokens) {
+                    if slot != token {
+                        *slot = WILDCARD.to_string();
+                    }
+                }
+                idx
+            }
+            _ if self.templates.len() >= MAX_TEMPLATES => {
+                self.overflow += 1;
+                return None;
+            }
+            _ => {
+                bucket.push(self.templates.len());
+                self.templates.push(Template {
+                    tokens: tokens.iter().map(|t| t.to_string()).collect(),
+                    count: 0,
+                    first_seen: at,
+                    last_seen: at,
+                    levels: [0; 3],
+                });
+                self.templates.len() - 1
+            }
+        };
+        let template = &mut self.templates[idx];
+        template.count += 1;
+        template.last_seen = at;
+        template.levels[match entry.level {
+            Level::Info => 0,
+            Level::Warn => 1,
+            Level::Error => 2,
+        }] += 1;
+        Some(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    pub fn overflow(&self) -> u64 {
+        self.overflow
+    }
+
+    pub fn sorted(&self, sort: TemplateSort) -> Vec<&Template> {
+        let mut templates: Vec<&Template> = self.templates.iter().collect();
+        match sort {
+            TemplateSort::Count => templates.sort_by_key(|t| Reverse(t.count)),
+            TemplateSort::Recent => templates.sort_by_key(|t| Reverse(t.last_seen)),
+            TemplateSort::Errors => {
+                templates.sort_by_key(|t| Reverse((t.levels[2], t.levels[1], t.count)))
+            }
+        }
+        templates
+    }
+}
+
+fn similarity(template: &[String], tokens: &[&str]) -> f64 {
+    if tokens.is_empty() {
+        return 1.0;
+    }
+    let same = template
+        .iter()
+        .zip(tokens)
+        .filter(|(t, token)| t.as_str() == **token && t.as_str() != WILDCARD)
+        .count();
+    same as f64 / tokens.len() as f64
+}
+
+/// Filter that passes messages shaped like a template.
+#[derive(Debug, Clone)]
+pub struct TemplateMatch {
+    pub text: String,
+    regex: Regex,
+}
+
+impl TemplateMatch {
+    pub fn new(tokens: &[String]) -> Self {
+        let pattern: Vec<String> = tokens
+            .iter()
+            .map(|token| {
+                token
+                    .split(WILDCARD)
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(r"\S+")
+            })
+            .collect();
+        let regex = Regex::new(&format!(r"^\s*{}\s*$", pattern.join(r"\s+")))
+            .expect("escaped template is a valid regex");
+        Self {
+            text: tokens.join(" "),
+            regex,
+        }
+    }
+
+    pub fn matches(&self, message: &str) -> bool {
+        self.regex.is_match(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Local::now(),
+            level,
+            target: "db".to_string(),
+            message: message.to_string(),
+            fields: Vec::new(),
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn similar_messages_share_a_template_that_matches_them() {
+        let mut miner = TemplateMiner::default();
+        let now = Local::now();
+        let lines = [
+            (Level::Warn, "deadlock retry txn=41 attempt=1"),
+            (Level::Error, "deadlock retry txn=97 attempt=3"),
+            (Level::Info, "cache warmed for tenant acme"),
+            (Level::Info, "cache warmed for tenant globex"),
+            (Level::Info, "shutting down"),
+        ];
+        let ids: Vec<_> = lines
+            .iter()
+            .map(|(level, msg)| miner.add(&entry(*level, msg), now).unwrap())
+            .collect();
+        assert_eq!(ids, vec![0, 0, 1, 1, 2]);
+
+        let top = miner.sorted(TemplateSort::Count);
+        assert_eq!(top[0].text(), "deadlock retry txn=<*> attempt=<*>");
+        assert_eq!(top[0].levels, [0, 1, 1]);
+        assert_eq!(top[1].text(), "cache warmed for tenant <*>");
+        assert_eq!(miner.sorted(TemplateSort::Errors)[0].count, 2);
+
+        let matcher = top[1].matcher();
+        assert!(matcher.matches("cache warmed for tenant initech"));
+        assert!(!matcher.matches("cache warmed for tenant a b"));
+    }
+}
@@ -0,0 +1,344 @@
+use std::{collections::BTreeMap, ops::Bound};
+
+use crate::{
+    filters::{write_haystack, Filters},
+    log_entry::LogEntry,
+};
+
+/// Inverted index from lowercased tokens of the filter haystack to entry sequence
+/// numbers. It only narrows candidates; `Filters::matches` still has the final say.
+#[derive(Debug, Default)]
+pub struct TextIndex {
+    postings: BTreeMap<Box<str>, Vec<u64>>,
+    /// Entries below this sequence number have been evicted; their postings are
+    /// dropped lazily by `compact`.
+    floor: u64,
+    /// Lowest sequence number that was ever indexed (`None` until the first add).
+    first_indexed: Option<u64>,
+    stale: usize,
+    live: usize,
+    scratch: String,
+}
+
+/// One requirement on a token of a matching entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Constraint {
+    Exact(String),
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+}
+
+impl TextIndex {
+    pub fn add(&mut self, seq: u64, entry: &LogEntry) {
+        self.first_indexed.get_or_insert(seq);
+        let mut haystack = std::mem::take(&mut self.scratch);
+        write_haystack(&mut haystack, entry);
+        let mut tokens: Vec<String> = tokenize(&haystack).collect();
+        tokens.sort_unstable();
+        tokens.dedup();
+        for token in tokens {
+            let posting = match self.postings.get_mut(token.as_str()) {
+                Some(posting) => posting,
+                None => self.postings.entry(token.into_boxed_str()).or_default(),
+            };
+            if posting.last() != Some(&seq) {
+                posting.push(seq);
+            }
+        }
+        self.scratch = haystack;
+        self.live += 1;
+    }
+
+    /// Marks everything up to and including `seq` as evicted.
+    pub fn evict_through(&mut self, seq: u64) {
+        if seq < self.floor {
+            return;
+        }
+        self.floor = seq + 1;
+        self.stale += 1;
+        self.live = self.live.saturating_sub(1);
+        if self.stale > self.live.max(1024) {
+            self.compact();
+        }
+    }
+
+    /// Lowest sequence number the index can answer for; older entries (e.g. paged
+    /// back in from disk) must be scanned.
+    pub fn covers_from(&self) -> u64 {
+        self.floor.max(self.first_indexed.unwrap_or(u64::MAX))
+    }
+
+    /// Sorted sequence numbers that may match `filters`, or `None` when the text
+    /// filter gives the index nothing to narrow on.
+    pub fn candidates(&self, filters: &Filters) -> Option<Vec<u64>> {
+        let text = filters.text.as_deref().filter(|t| !t.is_empty())?;
+        let constraints = if filters.regex_mode {
+            regex_constraints(text)
+        } else {
+            literal_constraints(text, filters.whole_word)
+        };
+        if constraints.is_empty() {
+            return None;
+        }
+        let mut result: Option<Vec<u64>> = None;
+        for constraint in &constraints {
+            let seqs = self.lookup(constraint);
+            result = Some(match result {
+                None => seqs,
+                Some(prev) => intersect(&prev, &seqs),
+            });
+            if result.as_ref().is_some_and(|r| r.is_empty()) {
+                break;
+            }
+        }
+        result
+    }
+
+    fn lookup(&self, constraint: &Constraint) -> Vec<u64> {
+        let floor = self.floor;
+        let mut lists: Vec<&Vec<u64>> = match constraint {
+            Constraint::Exact(token) => self.postings.get(token.as_str()).into_iter().collect(),
+            Constraint::Prefix(prefix) => self
+                .postings
+                .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+                .take_while(|(token, _)| token.starts_with(prefix.as_str()))
+                .map(|(_, seqs)| seqs)
+                .collect(),
+            Constraint::Suffix(suffix) => self
+                .postings
+                .iter()
+                .filter(|(token, _)| token.ends_with(suffix.as_str()))
+                .map(|(_, seqs)| seqs)
+                .collect(),
+            Constraint::Contains(part) => self
+                .postings
+                .iter()
+                .filter(|(token, _)| token.contains(part.as_str()))
+                .map(|(_, seqs)| seqs)
+                .collect(),
+        };
+        let mut seqs: Vec<u64> = match lists.len() {
+            0 => Vec::new(),
+            1 => lists.remove(0).clone(),
+            _ => {
+                let mut merged: Vec<u64> = lists.into_iter().flatten().copied().collect();
+                merged.sort_unstable();
+                merged.dedup();
+                merged
+            }
+        };
+        let live_from = seqs.partition_point(|seq| *seq < floor);
+        seqs.drain(..live_from);
+        seqs
+    }
+
+    fn compact(&mut self) {
+        let floor = self.floor;
+        self.postings.retain(|_, seqs| {
+            let live_from = seqs.partition_point(|seq| *seq < floor);
+            seqs.drain(..live_from);
+            !seqs.is_empty()
+        });
+        self.stale = 0;
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// A literal needle matches inside one haystack; its inner pieces are whole tokens,
+/// while the first and last pieces may be cut off mid-token unless `whole_word` is set.
+fn literal_constraints(needle: &str, whole_word: bool) -> Vec<Constraint> {
+    let pieces: Vec<String> = tokenize(needle).collect();
+    let open_left = !whole_word && needle.chars().next().is_some_and(char::is_alphanumeric);
+    let open_right = !whole_word && needle.chars().last().is_some_and(char::is_alphanumeric);
+    let last = pieces.len().saturating_sub(1);
+    pieces
+        .into_iter()
+        .enumerate()
+        .map(
+            |(i, piece)| match (i == 0 && open_left, i == last && open_right) {
+                (true, true) => Constraint::Contains(piece),
+                (true, false) => Constraint::Suffix(piece),
+                (false, true) => Constraint::Prefix(piece),
+                (false, false) => Constraint::Exact(piece),
+            },
+        )
+        .collect()
+}
+
+/// Pulls literal runs every match must contain out of simple patterns. Anything
+/// with alternation, optional groups or group flags yields no constraints (full scan).
+fn regex_constraints(pattern: &str) -> Vec<Constraint> {
+    let unsupported = ["|", ")?", ")*", "){", "(?"];
+    if unsupported.iter().any(|s| pattern.contains(s)) {
+        return Vec::new();
+    }
+    let mut runs = Vec::new();
+    let mut run = String::new();
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        if in_class {
+            if c == '\\' {
+                skip_escape(&mut chars);
+            } else if c == ']' {
+                in_class = false;
+            }
+            continue;
+        }
+        if c.is_alphanumeric() {
+            run.push(c);
+            if matches!(chars.peek(), Some('?' | '*' | '{')) {
+                run.pop();
+                runs.push(std::mem::take(&mut run));
+            }
+            continue;
+        }
+        runs.push(std::mem::take(&mut run));
+        match c {
+            '\\' => skip_escape(&mut chars),
+            '[' => in_class = true,
+            _ => {}
+        }
+    }
+    runs.push(run);
+    runs.into_iter()
+        .filter(|r| r.chars().count() >= 2)
+        .map(|r| Constraint::Contains(r.to_lowercase()))
+        .collect()
+}
+
+/// Consumes the rest of an escape after its backslash, so hex, unicode and class
+/// escapes like `\x41`, `\u0041` or `\p{Greek}` never leak digits or names into a run.
+fn skip_escape(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    let digits = match chars.next() {
+        Some('x') => 2,
+        Some('u') => 4,
+        Some('U') => 8,
+        Some('p' | 'P') => 1,
+        _ => return,
+    };
+    if chars.peek() == Some(&'{') {
+        for c in chars.by_ref() {
+            if c == '}' {
+                break;
+            }
+        }
+    } else {
+        for _ in 0..digits {
+            chars.next();
+        }
+    }
+}
+
+fn intersect(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::with_capacity(a.len().min(b.len()));
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_entry::Level;
+    use chrono::Local;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Local::now(),
+            level: Level::Info,
+            target: "db".to_string(),
+            message: message.to_string(),
+            fields: Vec::new(),
+            raw: None,
+        }
+    }
+
+    fn filters(text: &str, regex_mode: bool) -> Filters {
+        let mut filters = Filters {
+            regex_mode,
+            ..Filters::default()
+        };
+        filters.set_text(Some(text.to_string())).unwrap();
+        filters
+    }
+
+    #[test]
+    fn literal_queries_are_a_superset_of_matches() {
+        let mut index = TextIndex::default();
+        let messages = [
+            "deadlock retry txn=7",
+            "ingest worker started",
+            "stderr closed",
+            "commit ok",
+        ];
+        for (seq, msg) in messages.iter().enumerate() {
+            index.add(seq as u64, &entry(msg));
+        }
+        for needle in ["ingest work", "err", "txn=7", "lock ret", "DEADLOCK"] {
+            let f = filters(needle, false);
+            let candidates = index.candidates(&f).unwrap();
+            for (seq, msg) in messages.iter().enumerate() {
+                if f.matches(&entry(msg)) {
+                    assert!(candidates.contains(&(seq as u64)), "{needle} missed {msg}");
+                }
+            }
+        }
+        assert_eq!(
+            index.candidates(&filters("ingest work", false)),
+            Some(vec![1])
+        );
+    }
+
+    #[test]
+    fn regex_literals_narrow_and_eviction_hides_old_entries() {
+        let mut index = TextIndex::default();
+        index.add(0, &entry("timeout talking to upstream"));
+        index.add(1, &entry("upstream took too long"));
+        index.add(2, &entry("timeout again"));
+        assert_eq!(
+            index.candidates(&filters("time?out.*upstream", true)),
+            Some(vec![0])
+        );
+        assert_eq!(index.candidates(&filters("a|b", true)), None);
+        index.evict_through(0);
+        assert_eq!(index.candidates(&filters("timeout", false)), Some(vec![2]));
+        assert_eq!(index.covers_from(), 1);
+    }
+
+    #[test]
+    fn regex_escapes_do_not_become_required_literals() {
+        let mut index = TextIndex::default();
+        index.add(0, &entry("Aλ timeout"));
+        for pattern in [
+            r"\x41",
+            r"\x{41}",
+            r"\u0041",
+            r"\p{Greek} timeout",
+            r"\pL\s+timeout",
+        ] {
+            let f = filters(pattern, true);
+            assert!(f.matches(&entry("Aλ timeout")), "{pattern}");
+            assert!(
+                index.candidates(&f).is_none_or(|c| c.contains(&0)),
+                "{pattern} dropped a matching line"
+            );
+        }
+    }
+}
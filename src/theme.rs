@@ -0,0 +1,396 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::{app::Mode, log_entry::Level};
+
+/// Names accepted by `theme = "..."` besides a theme file path.
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "high-contrast"];
+
+/// Every colour slot a theme sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub info: Color,
+    pub warn: Color,
+    pub error: Color,
+    pub live: Color,
+    pub paused: Color,
+    pub border: Color,
+    /// Borders of popups: help, presets, templates.
+    pub popup: Color,
+    /// Headings, the target column and the activity sparkline.
+    pub accent: Color,
+    /// Secondary text such as counters.
+    pub muted: Color,
+    /// Timestamps, gap markers, context rows and the baseline ghost.
+    pub faint: Color,
+    pub notice: Color,
+    /// Options that are switched on, group badges and new templates.
+    pub active: Color,
+    pub source: Color,
+    pub field: Color,
+    /// Background of marked rows and highlighted list entries.
+    pub selection: Color,
+    pub band_info: Color,
+    pub band_warn: Color,
+    pub band_error: Color,
+    pub band_empty: Color,
+    pub cursor: Color,
+    pub bookmark: Color,
+    pub trace: Color,
+    pub drift: Color,
+}
+
+const DARK: Palette = Palette {
+    info: Color::White,
+    warn: Color::Yellow,
+    error: Color::Red,
+    live: Color::Green,
+    paused: Color::Yellow,
+    border: Color::DarkGray,
+    popup: Color::Magenta,
+    accent: Color::Cyan,
+    muted: Color::Gray,
+    faint: Color::DarkGray,
+    notice: Color::Green,
+    active: Color::Magenta,
+    source: Color::Blue,
+    field: Color::Green,
+    selection: Color::DarkGray,
+    band_info: Color::White,
+    band_warn: Color::Yellow,
+    band_error: Color::Red,
+    band_empty: Color::DarkGray,
+    cursor: Color::White,
+    bookmark: Color::Magenta,
+    trace: Color::Green,
+    drift: Color::Red,
+};
+
+/// For light backgrounds: dark text, no white or yellow foregrounds.
+const LIGHT: Palette = Palette {
+    info: Color::Black,
+    warn: Color::Rgb(0xa0, 0x50, 0x00),
+    error: Color::Rgb(0xc0, 0x00, 0x00),
+    live: Color::Rgb(0x00, 0x70, 0x00),
+    paused: Color::Rgb(0xa0, 0x50, 0x00),
+    border: Color::Gray,
+    popup: Color::Magenta,
+    accent: Color::Blue,
+    muted: Color::DarkGray,
+    faint: Color::Rgb(0x80, 0x80, 0x80),
+    notice: Color::Rgb(0x00, 0x70, 0x00),
+    active: Color::Magenta,
+    source: Color::Blue,
+    field: Color::Rgb(0x00, 0x70, 0x00),
+    selection: Color::Rgb(0xd8, 0xd8, 0xd8),
+    band_info: Color::Rgb(0xa8, 0xa8, 0xa8),
+    band_warn: Color::Rgb(0xe8, 0xa0, 0x00),
+    band_error: Color::Rgb(0xd0, 0x00, 0x00),
+    band_empty: Color::Rgb(0xec, 0xec, 0xec),
+    cursor: Color::Black,
+    bookmark: Color::Magenta,
+    trace: Color::Rgb(0x00, 0x70, 0x00),
+    drift: Color::Rgb(0xc0, 0x00, 0x00),
+};
+
+/// Bright, saturated colours only.
+const HIGH_CONTRAST: Palette = Palette {
+    info: Color::White,
+    warn: Color::LightYellow,
+    error: Color::LightRed,
+    live: Color::LightGreen,
+    paused: Color::LightYellow,
+    border: Color::White,
+    popup: Color::LightMagenta,
+    accent: Color::LightCyan,
+    muted: Color::White,
+    faint: Color::Gray,
+    notice: Color::LightGreen,
+    active: Color::LightMagenta,
+    source: Color::LightBlue,
+    field: Color::LightGreen,
+    selection: Color::Blue,
+    band_info: Color::White,
+    band_warn: Color::LightYellow,
+    band_error: Color::LightRed,
+    band_empty: Color::Black,
+    cursor: Color::LightCyan,
+    bookmark: Color::LightMagenta,
+    trace: Color::LightGreen,
+    drift: Color::LightRed,
+};
+
+impl Palette {
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(DARK),
+            "light" => Some(LIGHT),
+            "high-contrast" => Some(HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, slot: &str, color: Color) -> Result<(), String> {
+        let target = match slot {
+            "info" => &mut self.info,
+            "warn" => &mut self.warn,
+            "error" => &mut self.error,
+            "live" => &mut self.live,
+            "paused" => &mut self.paused,
+            "border" => &mut self.border,
+            "popup" => &mut self.popup,
+            "accent" => &mut self.accent,
+            "muted" => &mut self.muted,
+            "faint" => &mut self.faint,
+            "notice" => &mut self.notice,
+            "active" => &mut self.active,
+            "source" => &mut self.source,
+            "field" => &mut self.field,
+            "selection" => &mut self.selection,
+            "band_info" => &mut self.band_info,
+            "band_warn" => &mut self.band_warn,
+            "band_error" => &mut self.band_error,
+            "band_empty" => &mut self.band_empty,
+            "cursor" => &mut self.cursor,
+            "bookmark" => &mut self.bookmark,
+            "trace" => &mut self.trace,
+            "drift" => &mut self.drift,
+            other => return Err(format!("unknown colour slot {other:?}")),
+        };
+        *target = color;
+        Ok(())
+    }
+}
+
+/// A theme file: an optional built-in `base` plus `slot = "colour"` overrides.
+#[derive(Debug, Deserialize)]
+struct ThemeFile {
+    base: Option<String>,
+    #[serde(flatten)]
+    colors: BTreeMap<String, String>,
+}
+
+/// Styles for every part of the UI. With `plain` (`NO_COLOR`), no colours are
+/// emitted and markers are told apart by modifiers and glyphs instead.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+    pub plain: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "dark".to_string(),
+            palette: DARK,
+            plain: false,
+        }
+    }
+}
+
+impl Theme {
+    /// Resolves `theme = "..."`: a built-in name, or a theme file (relative
+    /// paths are looked up next to the config file).
+    pub fn load(spec: &str, config_dir: &Path) -> anyhow::Result<Self> {
+        if let Some(palette) = Palette::builtin(spec) {
+            return Ok(Self {
+                name: spec.to_string(),
+                palette,
+                plain: false,
+            });
+        }
+        let path = config_dir.join(spec);
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let file: ThemeFile =
+            toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        let base = file.base.as_deref().unwrap_or("dark");
+        let mut palette = Palette::builtin(base).with_context(|| {
+            format!(
+                "unknown base theme {base:?} ({})",
+                BUILTIN_THEMES.join(", ")
+            )
+        })?;
+        for (slot, name) in &file.colors {
+            let color = name
+                .parse::<Color>()
+                .map_err(|_| anyhow::anyhow!("{slot}: unknown colour {name:?}"))?;
+            palette.set(slot, color).map_err(anyhow::Error::msg)?;
+        }
+        Ok(Self {
+            name: path
+                .file_stem()
+                .map_or_else(|| spec.to_string(), |s| s.to_string_lossy().into_owned()),
+            palette,
+            plain: false,
+        })
+    }
+
+    /// `NO_COLOR` set to anything non-empty turns colours off (see no-color.org).
+    pub fn no_color_requested() -> bool {
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    }
+
+    /// Foreground `color`, or `plain` modifiers when colours are off.
+    fn fg(&self, color: Color, plain: Modifier) -> Style {
+        if self.plain {
+            Style::default().add_modifier(plain)
+        } else {
+            Style::default().fg(color)
+        }
+    }
+
+    pub fn level(&self, level: Level) -> Style {
+        match level {
+            Level::Info => self.fg(self.palette.info, Modifier::empty()),
+            Level::Warn => self.fg(self.palette.warn, Modifier::BOLD),
+            Level::Error => self.fg(self.palette.error, Modifier::BOLD | Modifier::UNDERLINED),
+        }
+    }
+
+    pub fn mode(&self, mode: Mode) -> Style {
+        match mode {
+            Mode::Live => self.fg(self.palette.live, Modifier::BOLD),
+            Mode::Paused => self.fg(self.palette.paused, Modifier::BOLD | Modifier::REVERSED),
+        }
+    }
+
+    pub fn border(&self) -> Style {
+        self.fg(self.palette.border, Modifier::empty())
+    }
+
+    pub fn popup(&self) -> Style {
+        self.fg(self.palette.popup, Modifier::BOLD)
+    }
+
+    pub fn accent(&self) -> Style {
+        self.fg(self.palette.accent, Modifier::empty())
+    }
+
+    pub fn muted(&self) -> Style {
+        self.fg(self.palette.muted, Modifier::empty())
+    }
+
+    pub fn faint(&self) -> Style {
+        self.fg(self.palette.faint, Modifier::DIM)
+    }
+
+    pub fn notice(&self) -> Style {
+        self.fg(self.palette.notice, Modifier::empty())
+    }
+
+    /// Problems worth noticing: filter errors, dropped lines.
+    pub fn alert(&self) -> Style {
+        self.fg(self.palette.error, Modifier::BOLD)
+    }
+
+    pub fn active(&self) -> Style {
+        self.fg(self.palette.active, Modifier::BOLD)
+    }
+
+    pub fn source(&self) -> Style {
+        self.fg(self.palette.source, Modifier::empty())
+    }
+
+    pub fn field(&self) -> Style {
+        self.fg(self.palette.field, Modifier::ITALIC)
+    }
+
+    /// Marked (visual selection) rows and the highlighted entry in lists.
+    pub fn selection(&self) -> Style {
+        if self.plain {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.palette.selection)
+        }
+    }
+
+    /// Glyph and style for one timeline band cell: coloured blanks, or shaded
+    /// blocks when colours are off.
+    pub fn band(&self, level: Option<Level>) -> (&'static str, Style) {
+        let (glyph, color) = match level {
+            Some(Level::Error) => ("\u{2588}", self.palette.band_error),
+            Some(Level::Warn) => ("\u{2593}", self.palette.band_warn),
+            Some(Level::Info) => ("\u{2591}", self.palette.band_info),
+            None => (" ", self.palette.band_empty),
+        };
+        if self.plain {
+            (glyph, Style::default())
+        } else {
+            (" ", Style::default().bg(color))
+        }
+    }
+
+    /// Style of a timeline marker character (`^`, `*`, `#`, `+`, `!`, or a
+    /// bookmark tag's letter).
+    pub fn marker(&self, mark: char) -> Style {
+        let p = &self.palette;
+        match mark {
+            '^' | '#' => self.fg(p.cursor, Modifier::BOLD),
+            '*' => self.fg(p.bookmark, Modifier::BOLD),
+            'R' => self.fg(p.error, Modifier::BOLD),
+            'Y' => self.fg(p.warn, Modifier::BOLD),
+            'G' => self.fg(p.notice, Modifier::BOLD),
+            'B' => self.fg(p.source, Modifier::BOLD),
+            '+' => self.fg(p.trace, Modifier::empty()),
+            '!' => self.fg(p.drift, Modifier::BOLD | Modifier::REVERSED),
+            _ => self.faint(),
+        }
+    }
+
+    /// Highlight rules keep their emphasis without colour when colours are off.
+    pub fn adapt(&self, style: Style) -> Style {
+        if !self.plain || (style.fg.is_none() && style.bg.is_none()) {
+            return style;
+        }
+        Style {
+            fg: None,
+            bg: None,
+            ..style
+        }
+        .add_modifier(Modifier::BOLD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_files_override_a_base_and_plain_drops_colours() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("mine.toml"),
+            "base = \"light\"\nerror = \"#ff0000\"\n",
+        )
+        .unwrap();
+        let theme = Theme::load("mine.toml", dir.path()).unwrap();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.palette.error, Color::Rgb(255, 0, 0));
+        assert_eq!(theme.palette.info, Color::Black);
+        assert_eq!(theme.level(Level::Error).fg, Some(Color::Rgb(255, 0, 0)));
+
+        fs::write(dir.path().join("bad.toml"), "errro = \"red\"\n").unwrap();
+        let err = Theme::load("bad.toml", dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("unknown colour slot"));
+        assert!(Theme::load("missing", dir.path()).is_err());
+
+        let plain = Theme {
+            plain: true,
+            ..Theme::load("high-contrast", dir.path()).unwrap()
+        };
+        let error = plain.level(Level::Error);
+        assert_eq!(error.fg, None);
+        assert!(error.add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(
+            plain.band(Some(Level::Warn)),
+            ("\u{2593}", Style::default())
+        );
+        let rule = plain.adapt(Style::default().fg(Color::Red));
+        assert_eq!((rule.fg, rule.add_modifier), (None, Modifier::BOLD));
+    }
+}
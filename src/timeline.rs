@@ -8,9 +8,11 @@ pub struct Timeline {
     bins: VecDeque<Bin>,
     bin_width: chrono::Duration,
     last_bin_start: DateTime<Local>,
+    /// Where bin number 0 starts; bins keep their number as the timeline advances.
+    origin: DateTime<Local>,
 }
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bin {
     pub info: u64,
     pub warn: u64,
@@ -18,15 +20,16 @@ pub struct Bin {
 }
 
 impl Timeline {
-    pub fn new(bin_count: usize, window: std::time::Duration) -> Self {
+    /// Builds a timeline whose newest bin starts at `now`.
+    pub fn new_at(bin_count: usize, window: std::time::Duration, now: DateTime<Local>) -> Self {
         let total_secs = window.as_secs().max(1);
         let bin_secs = (total_secs / bin_count.max(1) as u64).max(1);
         let bin_width = chrono::Duration::seconds(bin_secs as i64);
-        let now = Local::now();
         Self {
             bins: VecDeque::from(vec![Bin::default(); bin_count.max(1)]),
             bin_width,
             last_bin_start: now - bin_width,
+            origin: now - bin_width,
         }
     }
 
@@ -74,6 +77,17 @@ impl Timeline {
         start + self.bin_width * (idx_from_oldest as i32)
     }
 
+    /// Stable number of the bin `ts` falls in, whether or not it is on screen.
+    pub fn bin_number(&self, ts: DateTime<Local>) -> i64 {
+        let bin_secs = self.bin_width.num_seconds().max(1);
+        (ts - self.origin).num_seconds().div_euclid(bin_secs)
+    }
+
+    /// Bin number of the oldest bin shown.
+    pub fn first_bin_number(&self) -> i64 {
+        self.bin_number(self.last_bin_start) - (self.bins.len() as i64 - 1)
+    }
+
     pub fn bin_index_for(&self, ts: DateTime<Local>) -> Option<usize> {
         let (start, end) = self.range();
         if ts < start || ts >= end {
@@ -98,8 +112,8 @@ mod tests {
 
     #[test]
     fn timeline_tracks_bins() {
-        let mut timeline = Timeline::new(5, Duration::from_secs(5));
         let now = Local::now();
+        let mut timeline = Timeline::new_at(5, Duration::from_secs(5), now);
         timeline.record(now, 3, 1, 0);
         timeline.record(now + chrono::Duration::seconds(6), 2, 0, 1);
         assert_eq!(timeline.data().len(), 5);
@@ -107,5 +121,12 @@ mod tests {
             .data()
             .iter()
             .any(|v| v.info + v.warn + v.error >= 2));
+
+        let late = now + chrono::Duration::seconds(6);
+        let first = timeline.first_bin_number();
+        assert_eq!(
+            timeline.bin_index_for(late),
+            Some((timeline.bin_number(late) - first) as usize)
+        );
     }
 }
@@ -1,26 +1,35 @@
 use std::io;
 
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Sparkline, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline, Wrap},
     Frame, Terminal,
 };
 
-use crate::app::App;
+use crate::{
+    app::{App, ViewLayout, VisibleRow},
+    columns::{CellContext, Column},
+    commands::COMMANDS,
+    highlight::Highlighter,
+    keymap::Action,
+    log_entry::{Level, LogEntry},
+    theme::Theme,
+};
 
 pub type Term = Terminal<CrosstermBackend<io::Stdout>>;
 
 pub fn setup_terminal() -> anyhow::Result<Term> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
@@ -28,12 +37,17 @@ pub fn setup_terminal() -> anyhow::Result<Term> {
 
 pub fn restore_terminal(terminal: &mut Term) -> anyhow::Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
     Ok(())
 }
 
-pub fn draw(frame: &mut Frame, app: &App) {
+/// Draws one frame and reports the geometry that key handling depends on.
+pub fn draw(frame: &mut Frame, app: &App) -> ViewLayout {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -46,37 +60,176 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .split(frame.size());
 
     render_header(frame, chunks[0], app);
-    render_logs(frame, chunks[1], app);
-    render_timeline(frame, chunks[2], app);
-    render_status(frame, chunks[3], app);
+    let log_area = match app.input_mode() {
+        crate::filters::InputMode::Bookmarks(selected)
+        | crate::filters::InputMode::BookmarkEdit(Some(selected), _) => {
+            let parts = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(40), Constraint::Percentage(35)])
+                .split(chunks[1]);
+            render_bookmark_panel(frame, parts[1], app, *selected);
+            parts[0]
+        }
+        _ => chunks[1],
+    };
+    let mut view = render_logs(frame, log_area, app);
+    (view.timeline_area, view.timeline_step) = render_timeline(frame, chunks[2], app);
+    view.level_chips = render_status(frame, chunks[3], app);
+
+    if let crate::filters::InputMode::PresetPicker(selected) = app.input_mode() {
+        render_preset_picker(frame, app, *selected);
+    }
+    if let crate::filters::InputMode::Templates(selected) = app.input_mode() {
+        render_template_panel(frame, app, *selected);
+    }
 
     if app.show_help {
-        let area = centered_rect(70, 60, frame.size());
-        frame.render_widget(Clear, area);
-        let help = Paragraph::new(vec![
-            Line::from("Keys:"),
-            Line::from(" q/ctrl-c quit | space pause/resume | g/end go live"),
-            Line::from(" arrows/pgup/pgdn scroll | left/right timeline"),
-            Line::from(" / filter (Enter apply, Esc cancel) | R toggle regex | F/C clear"),
-            Line::from(" 1=info 2=warn 3=error level toggles | n/p next/prev error"),
-            Line::from(" b add bookmark | ]/[ next/prev bookmark"),
-            Line::from(" Filters match level/target/timestamp/message."),
-            Line::from(
-                " Timeline: red=error, yellow=warn, white=info; ^ cursor, * bookmark, # overlap, ! drift vs baseline.",
-            ),
-            Line::from(""),
-            Line::from("While scrolling up we auto-pause; queued lines show as +N."),
-            Line::from("Timeline cursor moves with left/right; markers show bookmarks and cursor."),
-            Line::from("Press ? or Esc to close this help."),
-        ])
+        render_help(frame, app);
+    }
+    view
+}
+
+fn render_preset_picker(frame: &mut Frame, app: &App, selected: usize) {
+    let area = centered_rect(60, 40, frame.size());
+    frame.render_widget(Clear, area);
+    let items: Vec<ListItem> = app
+        .presets()
+        .iter()
+        .map(|preset| {
+            let key = preset
+                .key
+                .map_or_else(String::new, |key| format!(" [{key}]"));
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{}{key}", preset.name),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(preset.source.clone(), app.theme().muted()),
+            ]))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    let list = List::new(items)
         .block(
             Block::default()
-                .title("Help")
+                .title("Presets (Enter apply, Esc close)")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Magenta)),
-        );
-        frame.render_widget(help, area);
-    }
+                .border_style(app.theme().popup()),
+        )
+        .highlight_style(app.theme().selection());
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// Side panel listing every bookmark: marker, time and label, then the start
+/// of the line and the note.
+fn render_bookmark_panel(frame: &mut Frame, area: Rect, app: &App, selected: usize) {
+    let theme = app.theme();
+    let items: Vec<ListItem> = app
+        .bookmarks()
+        .iter()
+        .map(|bm| {
+            let marker = bm.tag.marker();
+            let mut lines = vec![
+                Line::from(vec![
+                    Span::styled(format!("{marker} "), theme.marker(marker)),
+                    Span::styled(
+                        format!("{} ", bm.timestamp.format("%H:%M:%S")),
+                        theme.faint(),
+                    ),
+                    Span::styled(
+                        bm.label.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        bm.part
+                            .as_ref()
+                            .and_then(|part| part.file_name())
+                            .map_or_else(String::new, |name| {
+                                format!(" (in {})", name.to_string_lossy())
+                            }),
+                        theme.faint(),
+                    ),
+                ]),
+                Line::from(Span::styled(format!("  {}", bm.excerpt), theme.muted())),
+            ];
+            if let Some(note) = &bm.note {
+                lines.push(Line::from(Span::styled(
+                    format!("  {note}"),
+                    theme.notice().add_modifier(Modifier::ITALIC),
+                )));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Bookmarks ({})", app.bookmarks().len()))
+                .borders(Borders::ALL)
+                .border_style(theme.popup()),
+        )
+        .highlight_style(theme.selection());
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_template_panel(frame: &mut Frame, app: &App, selected: usize) {
+    let area = centered_rect(80, 60, frame.size());
+    frame.render_widget(Clear, area);
+    let items: Vec<ListItem> = app
+        .templates()
+        .into_iter()
+        .map(|template| {
+            let [info, warn, error] = template.levels;
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:>7} ", template.count),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("{error:>5}E "), app.theme().level(Level::Error)),
+                Span::styled(format!("{warn:>5}W "), app.theme().level(Level::Warn)),
+                Span::styled(format!("{info:>5}I "), app.theme().muted()),
+                Span::styled(
+                    format!(
+                        "{}-{} ",
+                        template.first_seen.format("%H:%M:%S"),
+                        template.last_seen.format("%H:%M:%S")
+                    ),
+                    app.theme().faint(),
+                ),
+            ];
+            match app.baseline_template_count(&template.text()) {
+                Some(0) => spans.push(Span::styled("new ", app.theme().active())),
+                Some(base) => {
+                    spans.push(Span::styled(format!("base {base} "), app.theme().faint()))
+                }
+                None => {}
+            }
+            spans.push(Span::raw(template.text()));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let overflow = match app.template_overflow() {
+        0 => String::new(),
+        n => format!(", {n} lines past the template limit"),
+    };
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(
+                    "Templates by {} (Enter filter, s sort, Esc close{overflow})",
+                    app.template_sort().label()
+                ))
+                .borders(Borders::ALL)
+                .border_style(app.theme().popup()),
+        )
+        .highlight_style(app.theme().selection());
+    frame.render_stateful_widget(list, area, &mut state);
 }
 
 fn render_header(frame: &mut Frame, area: Rect, app: &App) {
@@ -90,6 +243,19 @@ fn render_header(frame: &mut Frame, area: Rect, app: &App) {
         }
         _ => "none".to_string(),
     };
+    let mut mode_chips = filter_mode_chips(app.filters());
+    if app.collapse_enabled() {
+        mode_chips.push_str(" [collapsed]");
+    }
+    if let Some(range) = app.visual_range() {
+        mode_chips.push_str(&format!(" [VISUAL {}]", range.count()));
+    }
+    match app.context() {
+        _ if app.collapse_enabled() => {}
+        (0, 0) => {}
+        (before, after) if before == after => mode_chips.push_str(&format!(" [-C{before}]")),
+        (before, after) => mode_chips.push_str(&format!(" [-B{before} -A{after}]")),
+    }
     let level_display = format!(
         "1={}  2={}  3={}",
         if app.filters().info { "INFO" } else { "info" },
@@ -102,13 +268,27 @@ fn render_header(frame: &mut Frame, area: Rect, app: &App) {
     );
     let input_status = match app.input_mode() {
         crate::filters::InputMode::FilterText(buf) => {
-            format!("typing: {buf}_ (Enter apply, Esc cancel)")
+            format!("typing: {buf}_ (Enter apply, Esc cancel, Up/Down history)")
         }
+        crate::filters::InputMode::GotoTime(buf) => format!("go to: {buf}_"),
+        crate::filters::InputMode::TimeRange(buf) => format!("time range: {buf}_"),
+        crate::filters::InputMode::Command(buf) => format!("command: :{buf}_"),
+        crate::filters::InputMode::PresetPicker(_) => "picking preset".to_string(),
+        crate::filters::InputMode::Templates(_) => "browsing templates".to_string(),
+        crate::filters::InputMode::Bookmarks(_) => "browsing bookmarks".to_string(),
+        crate::filters::InputMode::BookmarkEdit(_, buf) => format!("bookmark: {buf}_"),
         crate::filters::InputMode::Normal => "normal".to_string(),
     };
     let queued = app.queued_len();
+    let keys = |action| app.keymap().keys(action);
     let timeline_hint = app.timeline_cursor_from_end().map_or_else(
-        || "timeline: live (left/right to scrub)".to_string(),
+        || {
+            format!(
+                "timeline: live ({}/{} to scrub)",
+                app.keymap().keys(Action::TimelineBack),
+                app.keymap().keys(Action::TimelineForward)
+            )
+        },
         |cursor| {
             let len = app.timeline().len();
             let idx_from_oldest = len.saturating_sub(cursor + 1);
@@ -137,41 +317,160 @@ fn render_header(frame: &mut Frame, area: Rect, app: &App) {
             timeline_hint
         )),
         Line::from(
-            "space pause/resume | arrows/pgup/pgdn scroll | g/end go live | left/right timeline | n/p next/prev error | b add bookmark | ]/[ jump mark | ?: help",
+            [
+                (Action::TogglePause, "pause/resume"),
+                (Action::GoLive, "go live"),
+                (Action::NextError, "next error"),
+                (Action::AddBookmark, "add bookmark"),
+                (Action::NextBookmark, "jump mark"),
+                (Action::Help, "help"),
+            ]
+            .map(|(action, label)| format!("{} {label}", keys(action)))
+            .join(" | "),
         ),
         Line::from(format!(
-            "Filter (/ start, R regex, F/C clear): {} | Levels: {} | Input: {}",
-            filter_display, level_display, input_status
+            "Filter ({} start, {} regex, {} case, {} word, {} clear): {} {} | Levels: {} | Input: {}",
+            keys(Action::Filter),
+            keys(Action::ToggleRegex),
+            keys(Action::CycleCase),
+            keys(Action::ToggleWord),
+            keys(Action::ClearFilters),
+            filter_display,
+            mode_chips,
+            level_display,
+            input_status
         )),
     ])
     .block(
         Block::default()
             .title("Overview")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(app.theme().border()),
     );
     frame.render_widget(header, area);
 }
 
-fn render_logs(frame: &mut Frame, area: Rect, app: &App) {
-    let max_visible = area.height.saturating_sub(2) as usize;
-    let visible_logs = app.visible_logs(max_visible);
+/// `[regex] [Aa smart] [word]`: the matcher options currently in effect.
+fn filter_mode_chips(filters: &crate::filters::Filters) -> String {
+    let mut chips = Vec::new();
+    if filters.regex_mode {
+        chips.push("[regex]".to_string());
+    }
+    chips.push(format!("[Aa {}]", filters.case.label()));
+    if filters.whole_word {
+        chips.push("[word]".to_string());
+    }
+    if let Some(target) = &filters.target {
+        chips.push(format!("[target:{target}]"));
+    }
+    if filters.since.is_some() || filters.until.is_some() {
+        let fmt = |ts: Option<chrono::DateTime<chrono::Local>>| {
+            ts.map_or_else(String::new, |ts| ts.format("%H:%M:%S").to_string())
+        };
+        chips.push(format!("[{}..{}]", fmt(filters.since), fmt(filters.until)));
+    }
+    if let Some(trace) = &filters.trace {
+        chips.push(format!("[trace {}={}]", trace.key, trace.value));
+    }
+    if let Some(template) = &filters.template {
+        let text: String = template.text.chars().take(40).collect();
+        chips.push(format!("[template: {text}]"));
+    }
+    chips.join(" ")
+}
+
+/// How rows that are wider than the log pane are shown.
+#[derive(Debug, Clone, Copy)]
+enum RowFit {
+    /// Cut at the pane edge after hiding this many message characters.
+    Clip { skip: usize },
+    /// Continue on further lines of this width.
+    Wrap { width: usize },
+}
 
+/// Draws the log pane and returns where its rows and level chips landed.
+fn render_logs(frame: &mut Frame, area: Rect, app: &App) -> ViewLayout {
+    let max_visible = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let fit = if app.wrap_enabled() {
+        RowFit::Wrap { width }
+    } else {
+        RowFit::Clip {
+            skip: app.h_scroll(),
+        }
+    };
+    // Widths come from the rows that fit unwrapped; wrapping only ever shows fewer.
+    let candidates = app.visible_logs(max_visible, |_| 1);
+    let widths = column_widths(&candidates, app);
+    let visible_logs = match fit {
+        RowFit::Wrap { width } => app.visible_logs(max_visible, |row| {
+            row_width(row, app, &widths).div_ceil(width)
+        }),
+        RowFit::Clip { .. } => candidates,
+    };
     let selected_idx_from_end = app.selected_from_end();
     let filtered_total = app.filtered_len();
-    let items: Vec<ListItem> = visible_logs
-        .into_iter()
-        .map(|(filtered_idx, entry)| {
-            let selected = filtered_total.saturating_sub(filtered_idx + 1) == selected_idx_from_end;
-            to_list_item(entry, selected)
-        })
-        .collect();
+    let mut view = ViewLayout {
+        log_area: Rect {
+            x: area.x + 1,
+            y: area.y + 1,
+            width: width as u16,
+            height: max_visible as u16,
+        },
+        ..ViewLayout::default()
+    };
+    let visual = app.visual_range();
+    let mut line = area.y + 1;
+    let mut items: Vec<ListItem> = Vec::with_capacity(visible_logs.len());
+    for row in visible_logs {
+        if row.gap_before {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "--",
+                app.theme().faint(),
+            ))));
+            line += 1;
+        }
+        let row_chars = row_width(&row, app, &widths);
+        let height = match fit {
+            RowFit::Wrap { width } => row_chars.div_ceil(width).max(1) as u16,
+            RowFit::Clip { .. } => 1,
+        };
+        view.log_rows.push((line, height, row.index));
+        line += height;
+        view.line_end_scroll = view.line_end_scroll.max(row_chars.saturating_sub(width));
+        let selected = filtered_total.saturating_sub(row.index + 1) == selected_idx_from_end;
+        let marked = visual
+            .as_ref()
+            .is_some_and(|range| range.contains(&row.index));
+        let prefix = row_prefix(&row, app, &widths);
+        items.push(to_list_item(
+            &row,
+            prefix,
+            app.highlighter(),
+            app.theme(),
+            selected,
+            marked,
+            fit,
+        ));
+    }
 
-    let list = List::new(items).block(Block::default().title("Logs").borders(Borders::ALL));
+    let title = match fit {
+        RowFit::Wrap { .. } => "Logs (wrap)".to_string(),
+        RowFit::Clip { skip: 0 } => "Logs".to_string(),
+        RowFit::Clip { skip } => format!("Logs (\u{2192}{skip})"),
+    };
+    let list = List::new(items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(app.theme().border()),
+    );
     frame.render_widget(list, area);
+    view
 }
 
-fn render_timeline(frame: &mut Frame, area: Rect, app: &App) {
+/// Draws the timeline and returns its clickable band area and bins per column.
+fn render_timeline(frame: &mut Frame, area: Rect, app: &App) -> (Rect, usize) {
     let data = app.timeline().data();
     let max_value = data
         .iter()
@@ -223,10 +522,15 @@ fn render_timeline(frame: &mut Frame, area: Rect, app: &App) {
 
     let combined: Vec<u64> = data.iter().map(|b| b.info + b.warn + b.error).collect();
     let sparkline = Sparkline::default()
-        .block(Block::default().title(title).borders(Borders::ALL))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme().border()),
+        )
         .data(&combined)
         .max(max_value)
-        .style(Style::default().fg(Color::Cyan));
+        .style(app.theme().accent());
     frame.render_widget(sparkline, parts[0]);
 
     if let Some(profile) = baseline {
@@ -241,20 +545,25 @@ fn render_timeline(frame: &mut Frame, area: Rect, app: &App) {
             profile.bin_count, profile.window_secs
         );
         let base_spark = Sparkline::default()
-            .block(Block::default().title(baseline_title).borders(Borders::ALL))
+            .block(
+                Block::default()
+                    .title(baseline_title)
+                    .borders(Borders::ALL)
+                    .border_style(app.theme().border()),
+            )
             .data(&base_combined)
             .max(base_max)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(app.theme().faint());
         frame.render_widget(base_spark, parts[1]);
     }
 
     let band_idx = if has_baseline { 2 } else { 1 };
     let marker_idx = if has_baseline { 3 } else { 2 };
-    let band = build_band_spans(&data, parts[band_idx].width as usize);
+    let band = build_band_spans(app.theme(), &data, parts[band_idx].width as usize);
     let band_para = Paragraph::new(Line::from(band)).block(
         Block::default()
             .borders(Borders::LEFT | Borders::RIGHT)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(app.theme().border()),
     );
     frame.render_widget(band_para, parts[band_idx]);
 
@@ -271,7 +580,14 @@ fn render_timeline(frame: &mut Frame, area: Rect, app: &App) {
     for bm in app.bookmarks() {
         if let Some(idx) = app.timeline().bin_index_for(bm.timestamp) {
             if let Some(slot) = marks.get_mut(idx) {
-                *slot = if *slot == '^' { '#' } else { '*' };
+                *slot = if *slot == '^' { '#' } else { bm.tag.marker() };
+            }
+        }
+    }
+    if let Some(traced) = app.trace_bins() {
+        for (idx, hit) in traced.iter().enumerate() {
+            if let (true, Some(slot)) = (*hit, marks.get_mut(idx)) {
+                *slot = if *slot == '.' { '+' } else { *slot };
             }
         }
     }
@@ -284,33 +600,63 @@ fn render_timeline(frame: &mut Frame, area: Rect, app: &App) {
             }
         }
     }
-    let legend = Line::from(vec![
-        Span::styled(" error", Style::default().bg(Color::Red).fg(Color::Black)),
-        Span::raw(" "),
-        Span::styled(" warn", Style::default().bg(Color::Yellow).fg(Color::Black)),
-        Span::raw(" "),
-        Span::styled(" info", Style::default().bg(Color::White).fg(Color::Black)),
-        Span::raw("   ^ cursor  * bookmark  # cursor+bookmark  ! drift"),
-    ]);
+    let mut legend = Vec::new();
+    for (level, label) in [
+        (Some(Level::Error), "error"),
+        (Some(Level::Warn), "warn"),
+        (Some(Level::Info), "info"),
+    ] {
+        let (glyph, style) = app.theme().band(level);
+        legend.push(Span::styled(glyph, style));
+        legend.push(Span::raw(format!(" {label}  ")));
+    }
+    for (mark, label) in [
+        ('^', "cursor"),
+        ('*', "bookmark"),
+        ('R', ""),
+        ('Y', ""),
+        ('G', ""),
+        ('B', "tagged"),
+        ('#', "cursor+bookmark"),
+        ('+', "trace"),
+        ('!', "drift"),
+    ] {
+        legend.push(Span::styled(mark.to_string(), app.theme().marker(mark)));
+        if !label.is_empty() {
+            legend.push(Span::raw(format!(" {label}  ")));
+        }
+    }
+    let legend = Line::from(legend);
 
-    let marker_str: String = marks.iter().collect();
-    let trimmed = if marker_str.len() > parts[marker_idx].width as usize {
-        marker_str
-            .chars()
-            .take(parts[marker_idx].width as usize)
-            .collect::<String>()
-    } else {
-        marker_str
-    };
-    let markers = Paragraph::new(vec![Line::from(trimmed), legend]).block(
+    let marker_spans: Vec<Span> = marks
+        .iter()
+        .take(parts[marker_idx].width as usize)
+        .map(|&mark| Span::styled(mark.to_string(), app.theme().marker(mark)))
+        .collect();
+    let markers = Paragraph::new(vec![Line::from(marker_spans), legend]).block(
         Block::default()
             .borders(Borders::LEFT | Borders::RIGHT)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(app.theme().border()),
     );
     frame.render_widget(markers, parts[marker_idx]);
+    let band_width = parts[band_idx].width.saturating_sub(2);
+    let clickable = Rect {
+        x: area.x + 1,
+        y: area.y,
+        width: band_width,
+        height: area.height,
+    };
+    (
+        clickable,
+        data.len().div_ceil(band_width.max(1) as usize).max(1),
+    )
 }
 
-fn build_band_spans(data: &[crate::timeline::Bin], width: usize) -> Vec<Span<'static>> {
+fn build_band_spans(
+    theme: &Theme,
+    data: &[crate::timeline::Bin],
+    width: usize,
+) -> Vec<Span<'static>> {
     if data.is_empty() || width == 0 {
         return vec![Span::raw("")];
     }
@@ -327,22 +673,24 @@ fn build_band_spans(data: &[crate::timeline::Bin], width: usize) -> Vec<Span<'st
             warn += bin.warn;
             error += bin.error;
         }
-        let color = if error > 0 {
-            Color::Red
+        let level = if error > 0 {
+            Some(Level::Error)
         } else if warn > 0 {
-            Color::Yellow
+            Some(Level::Warn)
         } else if info > 0 {
-            Color::White
+            Some(Level::Info)
         } else {
-            Color::DarkGray
+            None
         };
-        spans.push(Span::styled(" ", Style::default().bg(color)));
+        let (glyph, style) = theme.band(level);
+        spans.push(Span::styled(glyph, style));
         idx += step;
     }
     spans
 }
 
-fn render_status(frame: &mut Frame, area: Rect, app: &App) {
+/// Draws the status pane and returns the screen areas of its level chips.
+fn render_status(frame: &mut Frame, area: Rect, app: &App) -> Vec<(Level, Rect)> {
     let queued = app.queued_len();
     let drift_bins = app.drift_bins();
     let filter_text = match &app.filters().text {
@@ -357,15 +705,41 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
     };
     let input_hint = match app.input_mode() {
         crate::filters::InputMode::FilterText(buf) => Some(format!("typing filter: {buf}_")),
+        crate::filters::InputMode::GotoTime(buf) => Some(format!(
+            "go to time (12:03:10, 2024-05-01T12:03:10Z, -5m): {buf}_"
+        )),
+        crate::filters::InputMode::TimeRange(buf) => Some(format!(
+            "time range SINCE..UNTIL (either side optional, empty clears): {buf}_"
+        )),
+        crate::filters::InputMode::Command(buf) => Some(command_hint(buf)),
+        crate::filters::InputMode::PresetPicker(_) => {
+            Some("presets: Up/Down choose, Enter apply, Esc close".to_string())
+        }
+        crate::filters::InputMode::Templates(_) => {
+            Some("templates: Up/Down choose, Enter filter, s sort, Esc close".to_string())
+        }
+        crate::filters::InputMode::Bookmarks(_) => Some(
+            "bookmarks: Up/Down choose, Enter jump, r rename, t tag, d delete, Esc close"
+                .to_string(),
+        ),
+        crate::filters::InputMode::BookmarkEdit(target, buf) => Some(format!(
+            "{} LABEL [#red|#yellow|#green|#blue] [-- NOTE]: {buf}_",
+            if target.is_some() {
+                "edit bookmark"
+            } else {
+                "bookmark the selected line"
+            }
+        )),
         crate::filters::InputMode::Normal => None,
     };
-    let levels = (
-        level_chip("INFO", app.filters().info, Color::White),
-        level_chip("WARN", app.filters().warn, Color::Yellow),
-        level_chip("ERROR", app.filters().error, Color::Red),
-    );
     let timeline_status = app.timeline_cursor_from_end().map_or_else(
-        || "timeline: live (left/right to scrub)".to_string(),
+        || {
+            format!(
+                "timeline: live ({}/{} to scrub)",
+                app.keymap().keys(Action::TimelineBack),
+                app.keymap().keys(Action::TimelineForward)
+            )
+        },
         |cursor| {
             let len = app.timeline().len();
             let idx_from_oldest = len.saturating_sub(cursor + 1);
@@ -388,14 +762,16 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
     let mut lines = vec![Line::from(vec![
         Span::styled(
             format!(" {} ", app.mode.label()),
-            Style::default()
-                .fg(app.mode.color())
-                .add_modifier(Modifier::BOLD),
+            app.theme().mode(app.mode).add_modifier(Modifier::BOLD),
         ),
         Span::raw(" · "),
         Span::styled(
-            format!("logs buffered: {}", app.total_logs()),
-            Style::default().fg(Color::Gray),
+            format!(
+                "logs buffered: {} ({:.1} MiB)",
+                app.total_logs(),
+                app.memory_bytes() as f64 / (1024.0 * 1024.0)
+            ),
+            app.theme().muted(),
         ),
         Span::raw(" · "),
         Span::raw(format!("scroll offset: {}", app.scroll_offset())),
@@ -406,38 +782,67 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
             "live view".to_string()
         }),
         Span::raw(" · "),
-        Span::styled(
-            format!("queued: +{queued}"),
-            Style::default().fg(Color::Yellow),
-        ),
+        Span::styled(format!("queued: +{queued}"), app.theme().level(Level::Warn)),
         Span::raw(" · "),
         Span::raw(timeline_status),
     ])];
+    if let Some(spilled) = app.spilled_len() {
+        lines[0].spans.extend([
+            Span::raw(" · "),
+            Span::styled(format!("on disk: {spilled} older"), app.theme().muted()),
+        ]);
+    }
+    if let Some(stats) = app.ingest_stats() {
+        let backed_up = stats.queued * 2 >= stats.capacity;
+        lines[0].spans.extend([
+            Span::raw(" · "),
+            Span::styled(
+                format!(
+                    "ingest: {}/{} queued, {} dropped ({})",
+                    stats.queued,
+                    stats.capacity,
+                    stats.dropped,
+                    stats.policy.label()
+                ),
+                if stats.dropped > 0 || backed_up {
+                    app.theme().alert()
+                } else {
+                    app.theme().muted()
+                },
+            ),
+        ]);
+    }
     if matches!(app.mode, crate::app::Mode::Paused) {
         lines.push(Line::from(vec![
             Span::styled(
                 "PAUSED - view frozen; new lines are buffered",
-                Style::default()
-                    .fg(Color::Yellow)
+                app.theme()
+                    .mode(crate::app::Mode::Paused)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::raw(" · press space/g to resume"),
+            Span::raw(format!(
+                " · press {}/{} to resume",
+                app.keymap().keys(Action::TogglePause),
+                app.keymap().keys(Action::GoLive)
+            )),
         ]));
     }
 
+    let mut chip_offsets = Vec::new();
     let filter_spans = {
         let mut spans = vec![Span::styled(
             filter_text.clone(),
             if app.filters().text.is_some() {
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD)
+                app.theme().notice().add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::Gray)
+                app.theme().muted()
             },
         )];
         if app.filters().text.is_some() {
-            spans.push(Span::raw(" (F/C to clear)"));
+            spans.push(Span::raw(format!(
+                " ({} to clear)",
+                app.keymap().keys(Action::ClearFilters)
+            )));
         }
         spans.push(Span::raw(" · "));
         spans.push(Span::styled(
@@ -450,21 +855,65 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
                 }
             ),
             if app.filters().regex_mode {
-                Style::default().fg(Color::Magenta)
+                app.theme().active()
             } else {
-                Style::default().fg(Color::Gray)
+                app.theme().muted()
             },
         ));
         spans.push(Span::raw(" · "));
-        spans.extend_from_slice(&[
-            levels.0.clone(),
-            Span::raw(" "),
-            levels.1.clone(),
-            Span::raw(" "),
-            levels.2.clone(),
-        ]);
+        spans.push(Span::styled(
+            format!(
+                "case: {}{}",
+                app.filters().case.label(),
+                if app.filters().ignores_case() {
+                    ""
+                } else {
+                    " (Aa)"
+                }
+            ),
+            if app.filters().case == crate::filters::CaseMode::Smart {
+                app.theme().muted()
+            } else {
+                app.theme().active()
+            },
+        ));
+        spans.push(Span::raw(" · "));
+        spans.push(Span::styled(
+            format!(
+                "word: {}",
+                if app.filters().whole_word {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            if app.filters().whole_word {
+                app.theme().active()
+            } else {
+                app.theme().muted()
+            },
+        ));
+        spans.push(Span::raw(" · "));
+        for (level, enabled) in [
+            (Level::Info, app.filters().info),
+            (Level::Warn, app.filters().warn),
+            (Level::Error, app.filters().error),
+        ] {
+            if level != Level::Info {
+                spans.push(Span::raw(" "));
+            }
+            chip_offsets.push((
+                level,
+                spans
+                    .iter()
+                    .map(|s| s.content.chars().count())
+                    .sum::<usize>(),
+            ));
+            spans.push(level_chip(level.label(), enabled, app.theme().level(level)));
+        }
         spans
     };
+    let chip_line = lines.len();
     lines.push(Line::from(filter_spans));
     if !matches!(app.baseline_mode(), crate::config::BaselineMode::Off) {
         let drift_count = drift_bins
@@ -477,10 +926,14 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
             }
             crate::config::BaselineMode::Compare(path) => {
                 if app.baseline_overlay().is_some() {
+                    let new_templates = app
+                        .new_template_count()
+                        .map_or_else(String::new, |n| format!(", new templates: {n}"));
                     format!(
-                        "Baseline: comparing {} (drift bins: {})",
+                        "Baseline: comparing {} (drift bins: {}{})",
                         path.display(),
-                        drift_count
+                        drift_count,
+                        new_templates
                     )
                 } else {
                     format!("Baseline: {} (window/bins incompatible)", path.display())
@@ -508,25 +961,38 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
     }
     let bookmark_line = if let Some((idx, bm)) = app.current_bookmark_position() {
         format!(
-            "Bookmarks: {} (at {}/{} -> {} @ {})",
+            "Bookmarks: {} (at {}/{} -> {} @ {}{}; {} to list)",
             app.bookmarks().len(),
             idx + 1,
             app.bookmarks().len(),
             bm.label,
-            bm.timestamp.format("%H:%M:%S")
+            bm.timestamp.format("%H:%M:%S"),
+            bm.note
+                .as_ref()
+                .map_or_else(String::new, |note| format!(" - {note}")),
+            app.keymap().keys(Action::BookmarkList)
         )
     } else {
         format!(
-            "Bookmarks: {} (b to add, ]/[ to jump)",
-            app.bookmarks().len()
+            "Bookmarks: {} ({} to add, {}/{} to jump)",
+            app.bookmarks().len(),
+            app.keymap().keys(Action::AddBookmark),
+            app.keymap().keys(Action::NextBookmark),
+            app.keymap().keys(Action::PrevBookmark)
         )
     };
     lines.push(Line::from(bookmark_line));
+    let keymap = app.keymap();
+    let shortcuts: Vec<String> = COMMAND_BAR
+        .iter()
+        .map(|(action, label)| format!("{label} {}", keymap.keys(*action)))
+        .collect();
     let command_bar = Line::from(vec![
-        Span::styled("Commands: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-        Span::raw(
-            "Quit q/ctrl-c | Pause/Live space/g | Scroll \u{2191}/\u{2193}/PgUp/PgDn/Home/End | Timeline \u{2190}/\u{2192} | Filters / type, Enter apply, Esc cancel, F/C clear, R regex | Levels 1/2/3 | Errors n/p | Bookmarks b add, ]/[ jump",
+        Span::styled(
+            "Commands: ",
+            app.theme().accent().add_modifier(Modifier::BOLD),
         ),
+        Span::raw(shortcuts.join(" | ")),
     ]);
     lines.push(command_bar);
     if let Some(hint) = input_hint {
@@ -536,60 +1002,369 @@ fn render_status(frame: &mut Frame, area: Rect, app: &App) {
     if let Some(err) = app.filter_error() {
         lines.push(Line::from(Span::styled(
             format!("filter error: {err}"),
-            Style::default().fg(Color::Red),
+            app.theme().alert(),
         )));
     }
 
     if let Some(msg) = app.last_notice() {
-        lines.push(Line::from(Span::styled(
-            msg.clone(),
-            Style::default().fg(Color::Green),
-        )));
+        lines.push(Line::from(Span::styled(msg.clone(), app.theme().notice())));
     }
 
+    let inner = area.inner(Margin::new(1, 1));
+    let top: u16 = lines[..chip_line]
+        .iter()
+        .map(|line| {
+            wrapped_cells(&line_text(line), inner.width)
+                .last()
+                .map_or(1, |(row, _)| row + 1)
+        })
+        .sum();
+    let cells = wrapped_cells(&line_text(&lines[chip_line]), inner.width);
+    let chips = chip_offsets
+        .into_iter()
+        .filter_map(|(level, offset)| {
+            let (row, col) = *cells.get(offset)?;
+            let y = inner.y.checked_add(top + row)?;
+            let width = (level.label().len() as u16).min(inner.width - col);
+            (y < inner.bottom()).then(|| (level, Rect::new(inner.x + col, y, width, 1)))
+        })
+        .collect();
+
     let status = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::DarkGray)),
+                .border_style(app.theme().border()),
         )
         .wrap(Wrap { trim: true });
     frame.render_widget(status, area);
+    chips
+}
+
+fn line_text(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
 }
 
-fn to_list_item(entry: &crate::log_entry::LogEntry, selected: bool) -> ListItem<'static> {
+/// Row and column of each character of `text` in a trimming word-wrapped
+/// paragraph `width` columns wide; whitespace dropped at a wrap keeps the
+/// position where the next word starts.
+fn wrapped_cells(text: &str, width: u16) -> Vec<(u16, u16)> {
+    let mut cells = Vec::with_capacity(text.len());
+    if width == 0 {
+        return cells;
+    }
+    let (mut row, mut col) = (0u16, 0u16);
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let blank = chars[i].is_whitespace();
+        let len = chars[i..]
+            .iter()
+            .take_while(|c| c.is_whitespace() == blank)
+            .count();
+        if blank {
+            for _ in 0..len {
+                if col > 0 && col < width {
+                    cells.push((row, col));
+                    col += 1;
+                } else {
+                    if col > 0 {
+                        (row, col) = (row + 1, 0);
+                    }
+                    cells.push((row, col));
+                }
+            }
+        } else {
+            if col > 0 && usize::from(col) + len > usize::from(width) {
+                (row, col) = (row + 1, 0);
+            }
+            for _ in 0..len {
+                if col == width {
+                    (row, col) = (row + 1, 0);
+                }
+                cells.push((row, col));
+                col += 1;
+            }
+        }
+        i += len;
+    }
+    cells
+}
+
+fn cell_context<'a>(row: &VisibleRow<'a>, app: &'a App) -> CellContext<'a> {
+    CellContext {
+        entry: row.entry,
+        previous: row.previous,
+        bookmark: app.bookmark_before(row.seq),
+        source: app.source_label(),
+    }
+}
+
+/// Width of each column in the current layout, fitted to `rows`.
+fn column_widths(rows: &[VisibleRow], app: &App) -> Vec<usize> {
+    let contexts: Vec<_> = rows.iter().map(|row| cell_context(row, app)).collect();
+    app.column_layout()
+        .columns
+        .iter()
+        .map(|column| {
+            let cells: Vec<String> = contexts.iter().map(|cx| column.text(cx)).collect();
+            column.width(cells.iter().map(String::as_str))
+        })
+        .collect()
+}
+
+fn column_style(theme: &Theme, column: &Column, entry: &LogEntry) -> Style {
+    match column {
+        Column::Time | Column::Millis | Column::Date | Column::Relative | Column::SinceBookmark => {
+            theme.faint().add_modifier(Modifier::DIM)
+        }
+        Column::Level => theme.level(entry.level).add_modifier(Modifier::BOLD),
+        Column::Target => theme.accent(),
+        Column::Source => theme.source(),
+        Column::Field(_) => theme.field(),
+    }
+}
+
+/// The layout's columns and the group badge: everything in front of the message.
+fn row_prefix(row: &VisibleRow, app: &App, widths: &[usize]) -> Vec<Span<'static>> {
+    let entry = row.entry;
+    let cx = cell_context(row, app);
+    let mut spans = Vec::new();
+    for (column, width) in app.column_layout().columns.iter().zip(widths) {
+        if *width == 0 {
+            continue;
+        }
+        let text: String = column.text(&cx).chars().take(*width).collect();
+        spans.push(Span::styled(
+            format!("{text:<width$} "),
+            column_style(app.theme(), column, entry),
+        ));
+    }
     let ts = entry.timestamp.format("%H:%M:%S").to_string();
-    let mut spans = vec![
-        Span::styled(
-            format!("{ts} "),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::DIM),
-        ),
-        Span::styled(
-            format!("{:5}", entry.level.label()),
-            Style::default()
-                .fg(entry.level.color())
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::raw(" "),
-        Span::styled(
-            format!("{:<7}", entry.target),
-            Style::default().fg(Color::Cyan),
-        ),
-        Span::raw(" "),
-        Span::raw(entry.message.clone()),
-    ];
+    if let Some(group) = &row.group {
+        let badge = if group.expanded {
+            format!("\u{25be}\u{d7}{} ", group.count)
+        } else {
+            format!(
+                "\u{d7}{} {}\u{2013}{} ",
+                group.count,
+                ts,
+                group.last.format("%H:%M:%S")
+            )
+        };
+        spans.push(Span::styled(
+            badge,
+            app.theme().active().add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans
+}
+
+/// Characters the row takes unwrapped.
+fn row_width(row: &VisibleRow, app: &App, widths: &[usize]) -> usize {
+    let prefix: usize = row_prefix(row, app, widths)
+        .iter()
+        .map(|s| s.content.chars().count())
+        .sum();
+    prefix + row.entry.message.chars().count()
+}
+
+fn to_list_item(
+    row: &VisibleRow,
+    mut spans: Vec<Span<'static>>,
+    highlighter: &Highlighter,
+    theme: &Theme,
+    selected: bool,
+    marked: bool,
+    fit: RowFit,
+) -> ListItem<'static> {
+    let entry = row.entry;
+    let prefix_len = spans.len();
+    let mut cursor = 0;
+    for (range, style) in highlighter.spans(&entry.message) {
+        if range.start > cursor {
+            spans.push(Span::raw(entry.message[cursor..range.start].to_string()));
+        }
+        spans.push(Span::styled(
+            entry.message[range.clone()].to_string(),
+            theme.adapt(style),
+        ));
+        cursor = range.end;
+    }
+    spans.push(Span::raw(entry.message[cursor..].to_string()));
+    if let Some(style) = highlighter.line_style(entry).map(|s| theme.adapt(s)) {
+        for span in spans.iter_mut() {
+            span.style = span.style.patch(style);
+        }
+    }
+    if row.is_context {
+        for span in spans.iter_mut() {
+            span.style = span
+                .style
+                .remove_modifier(Modifier::BOLD)
+                .patch(theme.faint())
+                .add_modifier(Modifier::DIM);
+        }
+    }
+    if marked {
+        for span in spans.iter_mut() {
+            span.style = span.style.patch(theme.selection());
+        }
+    }
     if selected {
         for span in spans.iter_mut() {
             span.style = span.style.add_modifier(Modifier::REVERSED);
         }
     }
-    ListItem::new(Line::from(spans))
+    match fit {
+        RowFit::Clip { skip } => {
+            let message = spans.split_off(prefix_len);
+            spans.extend(skip_chars(message, skip));
+            ListItem::new(Line::from(spans))
+        }
+        RowFit::Wrap { width } => ListItem::new(wrap_spans(spans, width)),
+    }
 }
 
-fn level_chip(label: &str, enabled: bool, color: Color) -> Span<'static> {
-    let mut style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+/// Drops the first `n` characters, keeping each remaining character's style.
+fn skip_chars(spans: Vec<Span<'static>>, mut n: usize) -> Vec<Span<'static>> {
+    let mut out = Vec::with_capacity(spans.len());
+    for span in spans {
+        let len = span.content.chars().count();
+        if n >= len {
+            n -= len;
+            continue;
+        }
+        let rest: String = span.content.chars().skip(n).collect();
+        n = 0;
+        out.push(Span::styled(rest, span.style));
+    }
+    out
+}
+
+/// Breaks styled text into lines of at most `width` characters.
+fn wrap_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current: Vec<Span<'static>> = Vec::new();
+    let mut used = 0;
+    for span in spans {
+        let mut chars = span.content.chars().peekable();
+        while chars.peek().is_some() {
+            let piece: String = chars.by_ref().take(width - used).collect();
+            used += piece.chars().count();
+            current.push(Span::styled(piece, span.style));
+            if used == width {
+                lines.push(Line::from(std::mem::take(&mut current)));
+                used = 0;
+            }
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(Line::from(current));
+    }
+    lines
+}
+
+/// Key list generated from the active keymap, in two columns, above a few notes.
+fn render_help(frame: &mut Frame, app: &App) {
+    let area = centered_rect(90, 85, frame.size());
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .title(format!(
+            "Help (keys from the [keys] table in config.toml, theme {}{})",
+            app.theme().name,
+            if app.theme().plain { ", NO_COLOR" } else { "" }
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme().popup());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let keymap = app.keymap();
+    let entries = keymap.help();
+    let key_width = entries
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = entries
+        .into_iter()
+        .map(|(keys, help)| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {keys:<key_width$} "),
+                    app.theme().accent().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(help),
+            ])
+        })
+        .collect();
+    let notes = vec![
+        Line::from(""),
+        Line::from(" Filters match level/target/timestamp/message. : commands: goto, range, filter, export, bookmark, set, source add."),
+        Line::from(" Mouse: wheel scrolls, click selects a row or toggles a level chip, click/drag seeks the timeline."),
+        Line::from(" Timeline: bands show the worst level per bin (legend below it); ^ cursor, * bookmark (R/Y/G/B tagged), # overlap, ! drift vs baseline, + trace."),
+        Line::from(" Scrolling up auto-pauses; queued lines show as +N."),
+        Line::from(format!(" Press {} or Esc to close this help.", keymap.keys(Action::Help))),
+    ];
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(notes.len() as u16)])
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+    let half = lines.len().div_ceil(2);
+    let right = lines[half..].to_vec();
+    let mut left = lines;
+    left.truncate(half);
+    frame.render_widget(Paragraph::new(left), columns[0]);
+    frame.render_widget(Paragraph::new(right), columns[1]);
+    frame.render_widget(Paragraph::new(notes).wrap(Wrap { trim: false }), rows[1]);
+}
+
+/// Actions listed in the status bar; the help overlay has the rest.
+const COMMAND_BAR: &[(Action, &str)] = &[
+    (Action::Quit, "quit"),
+    (Action::TogglePause, "pause"),
+    (Action::GoLive, "live"),
+    (Action::Filter, "filter"),
+    (Action::ClearFilters, "clear"),
+    (Action::Presets, "presets"),
+    (Action::GotoTime, "go to"),
+    (Action::TimeRange, "range"),
+    (Action::Templates, "templates"),
+    (Action::Visual, "select"),
+    (Action::Yank, "copy"),
+    (Action::AddBookmark, "bookmark"),
+    (Action::BookmarkList, "marks"),
+    (Action::Command, "commands"),
+    (Action::Help, "all keys"),
+];
+
+/// The `:` prompt with the usage of the command being typed (or all command names).
+fn command_hint(buf: &str) -> String {
+    let name = buf.split_whitespace().next().unwrap_or("");
+    let matching: Vec<&str> = COMMANDS
+        .iter()
+        .filter(|(full, _)| full.starts_with(name))
+        .map(|(full, usage)| if name.is_empty() { *full } else { *usage })
+        .collect();
+    let help = match matching.as_slice() {
+        [] => "unknown command".to_string(),
+        [usage] => usage.to_string(),
+        names => names.join(" | "),
+    };
+    format!(":{buf}_   {help} (Tab completes, Enter runs, Esc cancels)")
+}
+
+fn level_chip(label: &str, enabled: bool, style: Style) -> Span<'static> {
+    let mut style = style.add_modifier(Modifier::BOLD);
     if !enabled {
         style = style.add_modifier(Modifier::CROSSED_OUT | Modifier::DIM);
     }
@@ -625,3 +1400,79 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight::{HighlightSpec, Scope};
+    use ratatui::{buffer::Buffer, style::Color, widgets::Widget};
+
+    #[test]
+    fn line_rules_restyle_the_whole_row() {
+        let entry = LogEntry {
+            timestamp: chrono::Local::now(),
+            level: Level::Error,
+            target: "db".to_string(),
+            message: "deadlock detected".to_string(),
+            fields: Vec::new(),
+            raw: None,
+        };
+        let row = VisibleRow {
+            index: 0,
+            seq: 0,
+            entry: &entry,
+            is_context: false,
+            gap_before: false,
+            previous: None,
+            group: None,
+        };
+        let (highlighter, _) = Highlighter::from_specs(&[HighlightSpec {
+            pattern: "deadlock".to_string(),
+            fg: Some("magenta".to_string()),
+            bg: None,
+            bold: false,
+            scope: Scope::Line,
+        }]);
+        let prefix = vec![Span::styled("ERROR ", Style::default().fg(Color::Red))];
+        let item = to_list_item(
+            &row,
+            prefix,
+            &highlighter,
+            &Theme::default(),
+            false,
+            false,
+            RowFit::Clip { skip: 0 },
+        );
+        let area = Rect::new(0, 0, 24, 1);
+        let mut buf = Buffer::empty(area);
+        List::new(vec![item]).render(area, &mut buf);
+        for x in 0..23 {
+            assert_eq!(buf.get(x, 0).fg, Color::Magenta, "column {x}");
+        }
+    }
+
+    #[test]
+    fn level_chip_areas_follow_the_wrapped_status_bar() {
+        use crate::{config::BaselineMode, ingest::Ingest, mock::MockGen, mock::Scenario};
+        use ratatui::backend::TestBackend;
+
+        let ingest = Ingest::Mock(Box::new(MockGen::new(Scenario::default(), Some(1))));
+        let app = App::new(ingest, 10, "mock".to_string(), BaselineMode::Off);
+        for width in [240, 90, 50] {
+            let mut terminal = Terminal::new(TestBackend::new(width, 40)).unwrap();
+            let mut view = ViewLayout::default();
+            terminal.draw(|frame| view = draw(frame, &app)).unwrap();
+            let buf = terminal.backend().buffer();
+            let labels: Vec<String> = view
+                .level_chips
+                .iter()
+                .map(|(_, area)| {
+                    (area.x..area.right())
+                        .map(|x| buf.get(x, area.y).symbol())
+                        .collect()
+                })
+                .collect();
+            assert_eq!(labels, vec!["INFO", "WARN", "ERROR"], "width {width}");
+        }
+    }
+}